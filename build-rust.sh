@@ -3,6 +3,9 @@
 # SSH Key Generator - Rust Build Script
 set -e
 
+# Cargo features to build with; FEATURES= ./build-rust.sh gives the minimal binary
+FEATURES="${FEATURES-full}"
+
 # Function to detect current architecture
 detect_arch() {
     local os=$(uname -s | tr '[:upper:]' '[:lower:]')
@@ -127,7 +130,7 @@ build_rust() {
             fi
             
             # Build with target
-            cargo build --release --target "$rust_target"
+            cargo build --release --features "$FEATURES" --target "$rust_target"
             
             # Copy binary with appropriate naming
             local binary_path="../dist/target/$rust_target/release/ssh-keygen"
@@ -144,7 +147,7 @@ build_rust() {
                 return 1
             fi
         else
-            cargo build --release
+            cargo build --release --features "$FEATURES"
             
             if [ -f "../dist/target/release/ssh-keygen" ]; then
                 cp "../dist/target/release/ssh-keygen" "../dist/$output_name"
@@ -157,7 +160,7 @@ build_rust() {
             fi
         fi
     else
-        cargo build --release
+        cargo build --release --features "$FEATURES"
         
         if [ -f "../dist/target/release/ssh-keygen" ]; then
             cp "../dist/target/release/ssh-keygen" "../dist/$output_name"
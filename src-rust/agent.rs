@@ -0,0 +1,110 @@
+use ed25519_dalek::SigningKey;
+use std::io::{self, Read, Write};
+use zeroize::Zeroizing;
+
+/// Agent protocol message numbers (draft-miller-ssh-agent)
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH2_AGENTC_ADD_IDENTITY: u8 = 17;
+const SSH2_AGENTC_ADD_ID_CONSTRAINED: u8 = 25;
+const SSH_AGENT_CONSTRAIN_LIFETIME: u8 = 1;
+const SSH_AGENT_CONSTRAIN_CONFIRM: u8 = 2;
+
+/// Default Windows OpenSSH agent pipe, used when SSH_AUTH_SOCK is not set
+#[cfg(windows)]
+const WINDOWS_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
+/// Constraints applied to an identity when it is added to the agent
+#[derive(Debug, Clone, Default)]
+pub struct AgentConstraints {
+    pub lifetime: Option<u32>,
+    pub confirm: bool,
+}
+
+/// Add an Ed25519 private key to the running ssh-agent
+pub fn add_identity(
+    private_key: &SigningKey,
+    comment: &str,
+    constraints: &AgentConstraints,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = encode_add_identity(private_key, comment, constraints);
+    let mut stream = connect()?;
+
+    stream.write_all(&(request.len() as u32).to_be_bytes())?;
+    stream.write_all(&request)?;
+    stream.flush()?;
+
+    // Read the framed response: uint32 length followed by the message
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf)?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len == 0 {
+        return Err("empty response from ssh-agent".into());
+    }
+    let mut response = vec![0u8; len];
+    stream.read_exact(&mut response)?;
+
+    match response[0] {
+        SSH_AGENT_SUCCESS => Ok(()),
+        SSH_AGENT_FAILURE => Err("ssh-agent refused the key".into()),
+        other => Err(format!("unexpected ssh-agent response type {}", other).into()),
+    }
+}
+
+/// Build the ADD_IDENTITY (or ADD_ID_CONSTRAINED) message body
+fn encode_add_identity(
+    private_key: &SigningKey,
+    comment: &str,
+    constraints: &AgentConstraints,
+) -> Zeroizing<Vec<u8>> {
+    let constrained = constraints.lifetime.is_some() || constraints.confirm;
+    let public = private_key.verifying_key().to_bytes();
+
+    // Ed25519 private keys are sent as the 32-byte seed followed by the public key
+    let mut keypair = Zeroizing::new([0u8; 64]);
+    keypair[..32].copy_from_slice(Zeroizing::new(private_key.to_bytes()).as_ref());
+    keypair[32..].copy_from_slice(&public);
+
+    let mut msg = Zeroizing::new(Vec::with_capacity(160 + comment.len()));
+    msg.push(if constrained {
+        SSH2_AGENTC_ADD_ID_CONSTRAINED
+    } else {
+        SSH2_AGENTC_ADD_IDENTITY
+    });
+    put_string(&mut msg, b"ssh-ed25519");
+    put_string(&mut msg, &public);
+    put_string(&mut msg, keypair.as_ref());
+    put_string(&mut msg, comment.as_bytes());
+
+    if let Some(lifetime) = constraints.lifetime {
+        msg.push(SSH_AGENT_CONSTRAIN_LIFETIME);
+        msg.extend_from_slice(&lifetime.to_be_bytes());
+    }
+    if constraints.confirm {
+        msg.push(SSH_AGENT_CONSTRAIN_CONFIRM);
+    }
+
+    msg
+}
+
+/// Append an SSH wire-format string (uint32 length + bytes)
+fn put_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<std::os::unix::net::UnixStream> {
+    let path = std::env::var_os("SSH_AUTH_SOCK").ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "SSH_AUTH_SOCK is not set; is ssh-agent running?")
+    })?;
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<std::fs::File> {
+    // Named pipes can be opened like regular files on Windows
+    let path = std::env::var_os("SSH_AUTH_SOCK")
+        .unwrap_or_else(|| WINDOWS_AGENT_PIPE.into());
+    std::fs::OpenOptions::new().read(true).write(true).open(path)
+}
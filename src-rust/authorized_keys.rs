@@ -0,0 +1,102 @@
+/// Options understood by OpenSSH in authorized_keys (see sshd(8) AUTHORIZED_KEYS FILE FORMAT)
+const FLAG_OPTIONS: &[&str] = &[
+    "agent-forwarding",
+    "cert-authority",
+    "no-agent-forwarding",
+    "no-port-forwarding",
+    "no-pty",
+    "no-touch-required",
+    "no-user-rc",
+    "no-X11-forwarding",
+    "port-forwarding",
+    "pty",
+    "restrict",
+    "user-rc",
+    "verify-required",
+    "X11-forwarding",
+];
+
+/// Options that take a double-quoted value
+const VALUE_OPTIONS: &[&str] = &[
+    "command",
+    "environment",
+    "expiry-time",
+    "from",
+    "permitlisten",
+    "permitopen",
+    "principals",
+    "tunnel",
+];
+
+/// Split an options string on commas that are not inside double quotes,
+/// rejecting whitespace outside quotes (sshd stops parsing options there)
+fn split_options(options: &str) -> Result<Vec<&str>, String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in options.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                return Err("authorized_keys options cannot contain unquoted whitespace".to_string());
+            }
+            ',' if !in_quotes => {
+                parts.push(&options[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quote in authorized_keys options".to_string());
+    }
+    parts.push(&options[start..]);
+    Ok(parts)
+}
+
+/// Check an authorized_keys options string against the sshd option grammar
+pub fn validate_options(options: &str) -> Result<(), String> {
+    if options.trim().is_empty() {
+        return Err("authorized_keys options cannot be empty".to_string());
+    }
+    if options.contains(['\n', '\r']) {
+        return Err("authorized_keys options cannot span multiple lines".to_string());
+    }
+
+    for option in split_options(options)? {
+        match option.split_once('=') {
+            Some((name, value)) => {
+                if !VALUE_OPTIONS.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                    return Err(format!("unknown authorized_keys option: {}", name));
+                }
+                if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+                    return Err(format!("value of {} must be double-quoted", name));
+                }
+            }
+            None => {
+                if option.is_empty() {
+                    return Err("empty option in authorized_keys options".to_string());
+                }
+                if !FLAG_OPTIONS.iter().any(|known| known.eq_ignore_ascii_case(option)) {
+                    return Err(format!("unknown authorized_keys option: {}", option));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Format a ready-to-paste authorized_keys line
+pub fn format_line(options: &str, public_key: &str) -> String {
+    format!("{} {}\n", options, public_key.trim())
+}
+
+/// Path of the authorized_keys output next to the public key file
+pub fn output_path(public_key_file: &str) -> String {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    format!("{}.authorized_keys", base)
+}
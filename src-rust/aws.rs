@@ -0,0 +1,316 @@
+//! `--upload aws`: import the public key as an EC2 key pair (ImportKeyPair), so
+//! a new deploy key can be named in a launch template straight away. Requests
+//! are signed with Signature Version 4. The credentials come from the same
+//! places the AWS CLI and SDKs look, in the same order: the environment, the
+//! shared credentials file, the AWS CLI itself (SSO, assumed roles,
+//! credential_process), then the ECS task role or EC2 instance role.
+
+use crate::secret::SecretString;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::process::Command;
+use std::time::Duration;
+
+const USER_AGENT: &str = concat!("ssh-keygen/", env!("CARGO_PKG_VERSION"));
+const EC2_API_VERSION: &str = "2016-11-15";
+const FORM: &str = "application/x-www-form-urlencoded; charset=utf-8";
+/// Instance and task metadata answer at once, or not at all off AWS
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: SecretString,
+    session_token: Option<SecretString>,
+}
+
+/// `--region`, else AWS_REGION or AWS_DEFAULT_REGION
+pub fn region(given: Option<&str>) -> Result<String, String> {
+    let region = given
+        .map(str::to_string)
+        .or_else(|| std::env::var("AWS_REGION").ok())
+        .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+        .filter(|region| !region.is_empty())
+        .ok_or("--upload aws needs --region (or AWS_REGION)")?;
+    if !region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err(format!("--region {:?} is not an AWS region name (e.g. eu-west-1)", region));
+    }
+    Ok(region)
+}
+
+/// The regional EC2 endpoint
+pub fn endpoint(region: &str) -> String {
+    format!("https://ec2.{}.amazonaws.com", region)
+}
+
+/// Import `public_key` as the EC2 key pair `key_name`
+pub fn import_key_pair(
+    endpoint: &str,
+    region: &str,
+    key_name: &str,
+    public_key: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use base64::Engine;
+
+    let credentials = credentials()?;
+    let body = form(&[
+        ("Action", "ImportKeyPair"),
+        ("Version", EC2_API_VERSION),
+        ("KeyName", key_name),
+        ("PublicKeyMaterial", &base64::engine::general_purpose::STANDARD.encode(public_key.trim())),
+    ]);
+    let host = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let time = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut headers = vec![("content-type", FORM.to_string()), ("host", host.to_string()), ("x-amz-date", time.clone())];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.expose().to_string()));
+    }
+    let authorization = authorization(&credentials, region, "ec2", &time, "POST", &headers, body.as_bytes());
+
+    let mut request = ureq::post(&format!("{}/", endpoint.trim_end_matches('/')))
+        .set("User-Agent", USER_AGENT)
+        .set("Authorization", &authorization);
+    for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+        request = request.set(name, value);
+    }
+    match request.send_string(&body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("EC2 API returned {}: {}", code, error_message(&body)).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The Signature Version 4 `Authorization` header for a request with an empty
+/// query string to `/`. `headers` are the signed ones, names in lowercase and
+/// sorted, `time` is `YYYYMMDDTHHMMSSZ`.
+fn authorization(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    time: &str,
+    method: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> String {
+    let date = &time[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let canonical_request = format!(
+        "{}\n/\n\n{}\n{}\n{}",
+        method,
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(body))
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        time,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let secret = zeroize::Zeroizing::new(format!("AWS4{}", credentials.secret_access_key.expose()));
+    let key = [date, region, service, "aws4_request"]
+        .iter()
+        .fold(zeroize::Zeroizing::new(secret.as_bytes().to_vec()), |key, part| {
+            zeroize::Zeroizing::new(hmac_sha256(&key, part.as_bytes()))
+        });
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id,
+        scope,
+        signed_headers,
+        hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `name=value&...`, percent-encoding everything but the RFC 3986 unreserved characters
+fn form(fields: &[(&str, &str)]) -> String {
+    let encode = |text: &str| -> String {
+        text.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    };
+    fields
+        .iter()
+        .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The `<Code>` and `<Message>` of an EC2 error response, or the whole body
+fn error_message(body: &str) -> String {
+    let element = |name: &str| {
+        let start = body.find(&format!("<{}>", name))? + name.len() + 2;
+        let end = start + body[start..].find(&format!("</{}>", name))?;
+        Some(body[start..end].to_string())
+    };
+    match (element("Code"), element("Message")) {
+        (Some(code), Some(message)) => format!("{}: {}", code, message),
+        _ => body.trim().to_string(),
+    }
+}
+
+/// The first source in the chain that has credentials
+fn credentials() -> Result<Credentials, Box<dyn std::error::Error>> {
+    if let Some(credentials) = from_env() {
+        return Ok(credentials);
+    }
+    if let Some(credentials) = from_shared_file()? {
+        return Ok(credentials);
+    }
+    if let Some(credentials) = from_cli() {
+        return Ok(credentials);
+    }
+    if let Some(credentials) = from_container()? {
+        return Ok(credentials);
+    }
+    if let Some(credentials) = from_instance() {
+        return Ok(credentials);
+    }
+    Err("no AWS credentials found (set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, configure a profile in \
+         ~/.aws/credentials or with `aws configure`, or run with an IAM role)"
+        .into())
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+fn from_env() -> Option<Credentials> {
+    Some(Credentials {
+        access_key_id: env("AWS_ACCESS_KEY_ID")?,
+        secret_access_key: SecretString::new(env("AWS_SECRET_ACCESS_KEY")?),
+        session_token: env("AWS_SESSION_TOKEN").map(SecretString::new),
+    })
+}
+
+/// The AWS_PROFILE (or `default`) section of AWS_SHARED_CREDENTIALS_FILE or ~/.aws/credentials
+fn from_shared_file() -> Result<Option<Credentials>, Box<dyn std::error::Error>> {
+    let path = match env("AWS_SHARED_CREDENTIALS_FILE") {
+        Some(path) => std::path::PathBuf::from(path),
+        None => match crate::install::home_dir() {
+            Some(home) => home.join(".aws").join("credentials"),
+            None => return Ok(None),
+        },
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => zeroize::Zeroizing::new(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+    let profile = env("AWS_PROFILE").unwrap_or_else(|| "default".to_string());
+    let mut section = None;
+    let (mut access_key_id, mut secret_access_key, mut session_token) = (None, None, None);
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = Some(name.trim().to_string());
+        } else if section.as_deref() == Some(profile.as_str()) {
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim().to_string();
+                match key.trim() {
+                    "aws_access_key_id" => access_key_id = Some(value),
+                    "aws_secret_access_key" => secret_access_key = Some(SecretString::new(value)),
+                    "aws_session_token" => session_token = Some(SecretString::new(value)),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(access_key_id.zip(secret_access_key).map(|(access_key_id, secret_access_key)| Credentials {
+        access_key_id,
+        secret_access_key,
+        session_token,
+    }))
+}
+
+/// Whatever the AWS CLI would use, which covers SSO logins, roles assumed by a
+/// profile and credential_process
+fn from_cli() -> Option<Credentials> {
+    let output = Command::new("aws")
+        .args(["configure", "export-credentials", "--format", "process"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let mut json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let credentials = from_json(&json, "SessionToken");
+    crate::secret::wipe_json(&mut json);
+    credentials
+}
+
+/// The ECS task role, from the container credentials endpoint
+fn from_container() -> Result<Option<Credentials>, Box<dyn std::error::Error>> {
+    let url = match (env("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI"), env("AWS_CONTAINER_CREDENTIALS_FULL_URI")) {
+        (Some(path), _) => format!("http://169.254.170.2{}", path),
+        (None, Some(url)) => url,
+        (None, None) => return Ok(None),
+    };
+    let mut request = ureq::get(&url).timeout(METADATA_TIMEOUT);
+    if let Some(token) = env("AWS_CONTAINER_AUTHORIZATION_TOKEN") {
+        request = request.set("Authorization", &token);
+    }
+    let mut json: serde_json::Value = request
+        .call()
+        .map_err(|e| format!("container credentials from {}: {}", url, e))?
+        .into_json()?;
+    let credentials = from_json(&json, "Token");
+    crate::secret::wipe_json(&mut json);
+    Ok(credentials)
+}
+
+/// The EC2 instance role, through IMDSv2; None off EC2
+fn from_instance() -> Option<Credentials> {
+    const IMDS: &str = "http://169.254.169.254/latest";
+    let token = ureq::put(&format!("{}/api/token", IMDS))
+        .timeout(METADATA_TIMEOUT)
+        .set("X-aws-ec2-metadata-token-ttl-seconds", "60")
+        .call()
+        .ok()?
+        .into_string()
+        .ok()?;
+    let get = |path: &str| {
+        ureq::get(&format!("{}/meta-data/iam/security-credentials/{}", IMDS, path))
+            .timeout(METADATA_TIMEOUT)
+            .set("X-aws-ec2-metadata-token", &token)
+            .call()
+            .ok()
+    };
+    let role = get("")?.into_string().ok()?;
+    let role = role.lines().next()?.trim().to_string();
+    let mut json: serde_json::Value = get(&role)?.into_json().ok()?;
+    let credentials = from_json(&json, "Token");
+    crate::secret::wipe_json(&mut json);
+    credentials
+}
+
+/// `AccessKeyId`, `SecretAccessKey` and the session token, named `token_field`
+fn from_json(json: &serde_json::Value, token_field: &str) -> Option<Credentials> {
+    let field = |name: &str| json[name].as_str().filter(|value| !value.is_empty()).map(str::to_string);
+    Some(Credentials {
+        access_key_id: field("AccessKeyId")?,
+        secret_access_key: SecretString::new(field("SecretAccessKey")?),
+        session_token: field(token_field).map(SecretString::new),
+    })
+}
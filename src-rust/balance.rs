@@ -0,0 +1,142 @@
+//! Per-worker throughput: spots a worker that keeps running well below the
+//! others, e.g. on a core shared with a noisy neighbour, so it is reported
+//! instead of silently lowering the aggregate rate. Where workers share out
+//! the work (seed streams), a lagging worker is also handed smaller chunks.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A worker below this share of the median rate is lagging
+pub const LAG_SHARE: f64 = 0.5;
+/// A lagging worker back at this share has recovered
+const RECOVER_SHARE: f64 = 0.8;
+/// Consecutive progress ticks (seconds) before either counts
+const TICKS: u32 = 5;
+
+/// State shared by the workers and the progress thread
+#[derive(Debug)]
+pub struct Balance {
+    running: Vec<AtomicBool>,
+    slow: Vec<AtomicBool>,
+}
+
+impl Balance {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            running: (0..workers).map(|_| AtomicBool::new(false)).collect(),
+            slow: (0..workers).map(|_| AtomicBool::new(false)).collect(),
+        }
+    }
+
+    /// Count a worker as running until the guard drops. Workers queued behind
+    /// the thread pool, or done, are left out of the comparison.
+    pub fn enter(&self, worker: usize) -> Running<'_> {
+        self.running[worker].store(true, Ordering::Relaxed);
+        Running { balance: self, worker }
+    }
+
+    /// Leave a worker out of the comparison while it waits (paused or parked)
+    /// until the guard drops
+    pub fn idle(&self, worker: usize) -> Idle<'_> {
+        self.running[worker].store(false, Ordering::Relaxed);
+        Idle { balance: self, worker }
+    }
+
+    /// Whether a worker is generating keys right now (not queued, waiting or done)
+    pub fn is_running(&self, worker: usize) -> bool {
+        self.running[worker].load(Ordering::Relaxed)
+    }
+
+    /// Whether a worker is currently flagged as lagging
+    pub fn is_slow(&self, worker: usize) -> bool {
+        self.slow[worker].load(Ordering::Relaxed)
+    }
+}
+
+pub struct Running<'a> {
+    balance: &'a Balance,
+    worker: usize,
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.balance.running[self.worker].store(false, Ordering::Relaxed);
+    }
+}
+
+pub struct Idle<'a> {
+    balance: &'a Balance,
+    worker: usize,
+}
+
+impl Drop for Idle<'_> {
+    fn drop(&mut self) {
+        self.balance.running[self.worker].store(true, Ordering::Relaxed);
+    }
+}
+
+/// Kept by the progress thread, fed the per-worker totals once per tick
+pub struct Monitor {
+    last: Vec<u64>,
+    streak: Vec<u32>,
+    /// What a lagging worker is given instead, for the warning
+    remedy: Option<&'static str>,
+}
+
+impl Monitor {
+    pub fn new(workers: usize, remedy: Option<&'static str>) -> Self {
+        Self {
+            last: vec![0; workers],
+            streak: vec![0; workers],
+            remedy,
+        }
+    }
+
+    /// Compare this tick's attempts per running worker with their median; returns
+    /// a message for every worker that started or stopped lagging
+    pub fn tick(&mut self, balance: &Balance, totals: &[u64]) -> Vec<String> {
+        let deltas: Vec<u64> = totals
+            .iter()
+            .zip(&self.last)
+            .map(|(total, last)| total.saturating_sub(*last))
+            .collect();
+        self.last = totals.to_vec();
+
+        let running: Vec<usize> = (0..deltas.len())
+            .filter(|&worker| balance.running[worker].load(Ordering::Relaxed))
+            .collect();
+        let mut rates: Vec<u64> = running.iter().map(|&worker| deltas[worker]).collect();
+        rates.sort_unstable();
+        let Some(&median) = rates.get(rates.len() / 2).filter(|_| rates.len() >= 2) else {
+            return Vec::new();
+        };
+        if median == 0 {
+            return Vec::new();
+        }
+
+        let mut messages = Vec::new();
+        for worker in running {
+            let share = deltas[worker] as f64 / median as f64;
+            let slow = balance.is_slow(worker);
+            let drifting = if slow { share >= RECOVER_SHARE } else { share < LAG_SHARE };
+            self.streak[worker] = if drifting { self.streak[worker] + 1 } else { 0 };
+            if self.streak[worker] < TICKS {
+                continue;
+            }
+            self.streak[worker] = 0;
+            balance.slow[worker].store(!slow, Ordering::Relaxed);
+            messages.push(if slow {
+                format!("worker {} is back to {:.0}% of the median worker rate", worker, share * 100.0)
+            } else {
+                format!(
+                    "worker {} has run below {:.0}% of the median worker rate for {}s (now {:.0}%; busy or throttled core?){}",
+                    worker,
+                    LAG_SHARE * 100.0,
+                    TICKS,
+                    share * 100.0,
+                    self.remedy.map(|remedy| format!("; {}", remedy)).unwrap_or_default()
+                )
+            });
+        }
+        messages
+    }
+}
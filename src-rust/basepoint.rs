@@ -0,0 +1,157 @@
+//! Basepoint tables for deriving Ed25519 public keys from candidate seeds. The
+//! search only needs the public key of most candidates, so it multiplies the
+//! basepoint itself instead of building a full `SigningKey` for each.
+//!
+//! With the `large-tables` feature a bigger table (up to 480 KB, shared by all
+//! workers) is used when the caches hold it and it measures faster at startup.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+#[cfg(feature = "large-tables")]
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+#[cfg(feature = "large-tables")]
+use curve25519_dalek::edwards::{EdwardsBasepointTableRadix256, EdwardsBasepointTableRadix64};
+#[cfg(feature = "large-tables")]
+use curve25519_dalek::traits::BasepointTable;
+use curve25519_dalek::scalar::{clamp_integer, Scalar};
+use sha2::digest::generic_array::GenericArray;
+use sha2::{Digest, Sha512};
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+pub enum Table {
+    /// The 30 KB radix-16 table ed25519-dalek itself uses
+    Radix16,
+    /// 120 KB, a third fewer additions
+    #[cfg(feature = "large-tables")]
+    Radix64(Box<EdwardsBasepointTableRadix64>),
+    /// 480 KB, about half the additions
+    #[cfg(feature = "large-tables")]
+    Radix256(Box<EdwardsBasepointTableRadix256>),
+}
+
+/// The table in use and how it was picked
+pub struct Choice {
+    pub table: Table,
+    /// For `--verbose`
+    pub reason: String,
+}
+
+/// The table every worker shares, chosen and built on first use
+pub fn choice() -> &'static Choice {
+    static CHOICE: OnceLock<Choice> = OnceLock::new();
+    CHOICE.get_or_init(Table::select)
+}
+
+impl Table {
+    #[cfg(not(feature = "large-tables"))]
+    fn select() -> Choice {
+        Choice {
+            table: Table::Radix16,
+            reason: "larger tables need a build with --features large-tables".to_string(),
+        }
+    }
+
+    /// Bigger tables need fewer additions but each constant-time lookup scans a
+    /// whole row, so whether they pay off depends on the CPU. Every table the
+    /// caches hold is timed against radix-16, and kept only if clearly faster.
+    #[cfg(feature = "large-tables")]
+    fn select() -> Choice {
+        let (l2, l3) = cache_sizes();
+        let fits = |table_size: usize| match (l2, l3) {
+            (None, None) => table_size <= 120 * 1024,
+            (l2, l3) => l2.is_some_and(|size| size >= 2 * table_size) || l3.is_some_and(|size| size >= 16 * table_size),
+        };
+
+        let baseline = Table::Radix16.rate();
+        let mut best = (Table::Radix16, 1.0);
+        let mut notes = Vec::new();
+        let candidates: [(usize, fn() -> Table); 2] = [
+            (120 * 1024, || Table::Radix64(Box::new(EdwardsBasepointTableRadix64::create(&ED25519_BASEPOINT_POINT)))),
+            (480 * 1024, || Table::Radix256(Box::new(EdwardsBasepointTableRadix256::create(&ED25519_BASEPOINT_POINT)))),
+        ];
+        for (size, build) in candidates {
+            if !fits(size) {
+                notes.push(format!("{} KB does not fit the caches", size / 1024));
+                continue;
+            }
+            let table = build();
+            let speedup = table.rate() / baseline;
+            notes.push(format!("{} {:.2}x", table.describe(), speedup));
+            if speedup >= 1.05 && speedup > best.1 {
+                best = (table, speedup);
+            }
+        }
+        Choice {
+            table: best.0,
+            reason: format!("startup check against radix-16: {}", notes.join(", ")),
+        }
+    }
+
+    /// Public keys per second for a short burst, for `select`
+    #[cfg(feature = "large-tables")]
+    fn rate(&self) -> f64 {
+        const SAMPLES: u32 = 500;
+        let mut seed = [0u8; 32];
+        let start = std::time::Instant::now();
+        for i in 0..SAMPLES {
+            seed[..4].copy_from_slice(&i.to_le_bytes());
+            std::hint::black_box(self.public_key(&seed));
+        }
+        SAMPLES as f64 / start.elapsed().as_secs_f64()
+    }
+
+    /// For `--verbose`, e.g. "radix-256 (480 KB)"
+    pub fn describe(&self) -> String {
+        let (radix, size) = match self {
+            Table::Radix16 => (16, 30),
+            #[cfg(feature = "large-tables")]
+            Table::Radix64(_) => (64, 120),
+            #[cfg(feature = "large-tables")]
+            Table::Radix256(_) => (256, 480),
+        };
+        format!("radix-{} ({} KB)", radix, size)
+    }
+
+    /// The Ed25519 public key for `seed`, as `SigningKey::from_bytes` derives it
+    pub fn public_key(&self, seed: &[u8; 32]) -> [u8; 32] {
+        let mut hash = Zeroizing::new([0u8; 64]);
+        Sha512::new_with_prefix(seed).finalize_into(GenericArray::from_mut_slice(hash.as_mut_slice()));
+        let mut scalar_bytes = Zeroizing::new([0u8; 32]);
+        scalar_bytes.copy_from_slice(&hash[..32]);
+        // The clamped integer may exceed the group order; reducing it does not
+        // change the point since the basepoint's order is that order
+        let scalar = Zeroizing::new(Scalar::from_bytes_mod_order(clamp_integer(*scalar_bytes)));
+        let point = match self {
+            Table::Radix16 => ED25519_BASEPOINT_TABLE * &*scalar,
+            #[cfg(feature = "large-tables")]
+            Table::Radix64(table) => &**table * &*scalar,
+            #[cfg(feature = "large-tables")]
+            Table::Radix256(table) => &**table * &*scalar,
+        };
+        point.compress().to_bytes()
+    }
+}
+
+/// Per-core L2 and shared L3 sizes in bytes, from sysfs where there is one
+#[cfg(feature = "large-tables")]
+fn cache_sizes() -> (Option<usize>, Option<usize>) {
+    let mut sizes = (None, None);
+    for index in 0..8 {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{}", index);
+        let read = |name: &str| std::fs::read_to_string(format!("{}/{}", dir, name)).ok();
+        let (Some(level), Some(size)) = (read("level"), read("size")) else {
+            continue;
+        };
+        let size = size.trim();
+        let bytes = match size.strip_suffix('K') {
+            Some(kib) => kib.parse::<usize>().ok().map(|kib| kib * 1024),
+            None => size.strip_suffix('M').and_then(|mib| mib.parse::<usize>().ok()).map(|mib| mib * 1024 * 1024),
+        };
+        match level.trim() {
+            "2" => sizes.0 = bytes,
+            "3" => sizes.1 = bytes,
+            _ => {}
+        }
+    }
+    sizes
+}
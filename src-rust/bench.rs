@@ -0,0 +1,65 @@
+//! Worker count: one per physical core by default, and `ssh-keygen bench` to
+//! measure the alternatives on this machine.
+//!
+//! Key generation is bound by scalar multiplication, which leaves little for a
+//! second hardware thread on the same core to fill in, and workers beyond the
+//! CPU count only add switching.
+
+use crate::error::Error;
+use std::time::Duration;
+
+/// Workers when `--threads` is not given: one per physical core
+pub fn default_workers() -> usize {
+    num_cpus::get_physical().max(1)
+}
+
+/// Options for `ssh-keygen bench`
+pub struct BenchOptions {
+    /// How long each worker count runs
+    pub duration: Duration,
+}
+
+/// Time the search at one worker per physical core, per logical CPU and three
+/// per logical CPU. `measure` runs that many workers for the given time and
+/// returns keys per second.
+pub fn run(options: &BenchOptions, measure: impl Fn(usize, Duration) -> Result<f64, Error>) -> Result<(), Error> {
+    let physical = default_workers();
+    let logical = num_cpus::get();
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    println!(
+        "CPU: {} physical core{}, {} logical CPU{}{}",
+        physical,
+        plural(physical),
+        logical,
+        plural(logical),
+        if logical > physical { " (SMT)" } else { "" }
+    );
+    println!("Measuring each worker count for {}s...", options.duration.as_secs_f64());
+
+    let mut counts = vec![(physical, "one per physical core, the default")];
+    if logical != physical {
+        counts.push((logical, "one per logical CPU"));
+    }
+    counts.push((3 * logical, "three per logical CPU"));
+
+    let mut default_rate = None;
+    let mut fastest = (physical, 0.0);
+    for (workers, label) in counts {
+        let rate = measure(workers, options.duration)?;
+        let baseline = *default_rate.get_or_insert(rate);
+        let change = if workers == physical || baseline == 0.0 {
+            String::new()
+        } else {
+            format!(" ({:+.1}%)", (rate / baseline - 1.0) * 100.0)
+        };
+        println!("  {:>4} workers ({}): {:.0} keys/s{}", workers, label, rate, change);
+        if rate > fastest.1 {
+            fastest = (workers, rate);
+        }
+    }
+    // Differences of a few percent are within run-to-run noise
+    if fastest.0 != physical && fastest.1 > default_rate.unwrap_or(0.0) * 1.05 {
+        println!("Fastest here: {} workers (pass --threads {} to use them)", fastest.0, fastest.0);
+    }
+    Ok(())
+}
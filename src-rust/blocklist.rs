@@ -0,0 +1,87 @@
+use crate::matching::{self, ED25519_PREFIX};
+use aho_corasick::AhoCorasick;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Built in unless `--no-blocklist`: words nobody wants in a key they paste into
+/// public places, and a run of A's that makes a key look broken next to the prefix
+const DEFAULT_ENTRIES: &[&str] = &[
+    "fuck", "shit", "cunt", "piss", "dick", "cock", "twat", "wank", "porn", "slut", "whore", "rape",
+    "nazi", "AAAAAA",
+];
+
+/// Substrings a matching key must not contain. Checked case-insensitively, and
+/// only after the fixed prefix every Ed25519 key shares. An entry some spelling
+/// of the target contains never blocks that target, or the search could not succeed.
+#[derive(Debug)]
+pub struct Blocklist {
+    automaton: AhoCorasick,
+    entries: Vec<String>,
+    /// Keys that matched the target but were thrown away
+    rejected: AtomicU64,
+}
+
+impl Blocklist {
+    /// The built-in entries (if `builtin`) plus those in `files`, one per line with
+    /// `#` comments
+    pub fn load(builtin: bool, files: &[&str]) -> Result<Self, String> {
+        let mut entries: Vec<String> = Vec::new();
+        if builtin {
+            entries.extend(DEFAULT_ENTRIES.iter().map(|entry| entry.to_string()));
+        }
+        for path in files {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            entries.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        entries.sort();
+        entries.dedup();
+
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(&entries)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            automaton,
+            entries,
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Tells lists apart, so a checkpoint is only resumed by a search that
+    /// throws away the same keys
+    pub fn identity(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"ssh-keygen-deluxe/blocklist-identity/v1");
+        for entry in &self.entries {
+            hasher.update(entry.as_bytes());
+            hasher.update(b"\n");
+        }
+        hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Whether a key matching `target` has to be thrown away; counts it if so
+    pub fn rejects(&self, public_key_line: &str, target: &str) -> bool {
+        let body = public_key_line.strip_prefix(ED25519_PREFIX).unwrap_or(public_key_line);
+        let blocked = self.automaton.find_overlapping_iter(body).any(|hit| {
+            let entry = &self.entries[hit.pattern().as_usize()];
+            !matching::spelling_contains(target, entry)
+        });
+        if blocked {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        blocked
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+}
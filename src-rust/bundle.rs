@@ -0,0 +1,169 @@
+//! `--bundle`: everything about a new key in one `.tar.gz`, for handing it to
+//! another admin or to escrow. The tar writer is the minimal ustar subset needed
+//! for a flat archive of regular files.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use zeroize::Zeroizing;
+
+const BLOCK: usize = 512;
+
+/// One file in the archive
+pub struct Entry {
+    pub name: String,
+    /// Permission bits, e.g. 0o600 for the private key
+    pub mode: u32,
+    pub data: Zeroizing<Vec<u8>>,
+}
+
+impl Entry {
+    pub fn new(name: impl Into<String>, mode: u32, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            mode,
+            data: Zeroizing::new(data.into()),
+        }
+    }
+}
+
+/// Write `entries` as a gzipped tar to `path`. The archive holds the private key,
+/// so the file is owner-only like the key itself, and a failed write removes it.
+pub fn write(path: &str, entries: &[Entry]) -> Result<(), Box<dyn std::error::Error>> {
+    let pending = crate::cleanup::Pending::new(path, true);
+    let file = crate::permissions::create_private(pending.path())?;
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    let mut archive = GzEncoder::new(file, Compression::default());
+    for entry in entries {
+        archive.write_all(&header(&entry.name, entry.mode, entry.data.len() as u64, mtime)?)?;
+        archive.write_all(&entry.data)?;
+        let padding = (BLOCK - entry.data.len() % BLOCK) % BLOCK;
+        archive.write_all(&[0u8; BLOCK][..padding])?;
+    }
+    // End of archive: two zero blocks
+    archive.write_all(&[0u8; 2 * BLOCK])?;
+    archive.finish()?.sync_all()?;
+    pending.keep();
+    Ok(())
+}
+
+/// ustar header for a regular file owned by root
+fn header(name: &str, mode: u32, size: u64, mtime: u64) -> io::Result<[u8; BLOCK]> {
+    if name.len() > 100 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{}: name too long for tar", name)));
+    }
+    let mut block = [0u8; BLOCK];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut block[100..108], mode as u64);
+    octal(&mut block[108..116], 0);
+    octal(&mut block[116..124], 0);
+    octal(&mut block[124..136], size);
+    octal(&mut block[136..148], mtime);
+    block[156] = b'0';
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[265..269].copy_from_slice(b"root");
+    block[297..301].copy_from_slice(b"root");
+
+    // The checksum is computed with its own field set to spaces
+    block[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = block.iter().map(|&b| b as u32).sum();
+    octal(&mut block[148..155], checksum as u64);
+    block[155] = b' ';
+    Ok(block)
+}
+
+/// Zero-padded octal filling all but the last byte of `field`, then NUL
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[field.len() - 1] = 0;
+}
+
+/// SSHFP records (RFC 4255/7479) for an Ed25519 host key: algorithm 4, with
+/// SHA-1 and SHA-256 fingerprints of the key blob
+pub fn sshfp_records(owner: &str, public_key_line: &str) -> String {
+    let blob = public_key_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|body| BASE64.decode(body).ok())
+        .unwrap_or_default();
+    let hex = |digest: &[u8]| digest.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    format!(
+        "{} IN SSHFP 4 1 {}\n{} IN SSHFP 4 2 {}\n",
+        owner,
+        hex(&Sha1::digest(&blob)),
+        owner,
+        hex(&Sha256::digest(&blob))
+    )
+}
+
+/// Owner name for the SSHFP records: the first concrete `--known-hosts` name
+/// (without brackets or port), else `@` for the zone apex
+pub fn sshfp_owner(hosts: &[String]) -> String {
+    hosts
+        .iter()
+        .filter(|host| !host.contains(['*', '?', '!']))
+        .map(|host| match host.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((name, _port)) => name,
+            None => host.as_str(),
+        })
+        .find(|name| name.parse::<std::net::IpAddr>().is_err())
+        .map_or_else(|| "@".to_string(), |name| format!("{}.", name.trim_end_matches('.')))
+}
+
+/// The README-of-record: what the key is, what each file is for, and how to
+/// install it. Lists `entries` as they will appear in the archive.
+pub fn readme(public_key: &str, fingerprint: &str, target: &str, encrypted: bool, entries: &[Entry]) -> String {
+    let mut text = format!(
+        "ssh-keygen-deluxe key bundle\n\
+         ============================\n\n\
+         Created:     {}\n\
+         Tool:        ssh-keygen-deluxe {}\n\
+         Key type:    ssh-ed25519\n\
+         Fingerprint: {}\n\
+         Target:      {}\n\
+         Public key:  {}\n\n\
+         Files\n-----\n",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        env!("CARGO_PKG_VERSION"),
+        fingerprint,
+        target,
+        public_key.trim()
+    );
+    for entry in entries {
+        text.push_str(&format!("{:o}  {}  {}\n", entry.mode, entry.name, describe(&entry.name)));
+    }
+    text.push_str(&format!(
+        "\nThe private key is {}.\n\
+         Extract with `tar -xpzf` to keep the modes above, and check the fingerprint\n\
+         with `ssh-keygen -lf <public key file>` before use.\n\n\
+         Install\n-------\n\
+         User key:  append the .authorized_keys line to ~/.ssh/authorized_keys on the server.\n\
+         Host key:  copy the private key to /etc/ssh/ (mode 600), point HostKey at it, and\n\
+         \x20          publish the .sshfp records in the host's DNS zone.\n",
+        if encrypted { "encrypted" } else { "NOT encrypted: store this archive like the key itself" }
+    ));
+    text
+}
+
+fn describe(name: &str) -> &'static str {
+    if name.ends_with(".pub") {
+        "public key"
+    } else if name.ends_with(".authorized_keys") {
+        "authorized_keys line"
+    } else if name.ends_with(".sshfp") {
+        "SSHFP DNS records (RFC 4255)"
+    } else if name.ends_with(".meta.json") {
+        "provenance metadata"
+    } else {
+        "private key"
+    }
+}
@@ -0,0 +1,303 @@
+//! Campaign mode (`--targets-file`): search for many targets at once, one
+//! matcher pass per candidate key, dropping each target once it is satisfied.
+//! With `--targets -` targets keep arriving on stdin while the search runs, and
+//! with `--control` targets can be added or removed through its socket.
+//! `--any-target` turns it around: the first key to match any target is the
+//! only one kept.
+
+use crate::matching::MultiMatcher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+const OUT_OPTION: &str = "add out=FILE";
+/// The most a `priority=N` can be
+pub const MAX_PRIORITY: u32 = 100;
+
+/// One line of a targets file
+#[derive(Debug, Clone)]
+pub struct TargetSpec {
+    pub target: String,
+    pub case_sensitive: bool,
+    /// Private key file; the public key goes next to it with `.pub`
+    pub output: String,
+    /// `priority=N`: with `--until-priority`, the run ends once every target
+    /// with at least that priority is found
+    pub priority: u32,
+}
+
+/// Parse a targets file: one target per line, optionally followed by
+/// `ignore-case`/`case-sensitive`, `out=FILE` and `priority=N`. Blank lines and `#` comments
+/// are skipped. `case_sensitive` is the default for lines that do not say.
+pub fn parse_targets(text: &str, case_sensitive: bool) -> Result<Vec<TargetSpec>, String> {
+    let mut specs: Vec<TargetSpec> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let spec = match parse_line(line, case_sensitive) {
+            Ok(Some(spec)) => spec,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("line {}: {}", number + 1, e)),
+        };
+        check_unique(&specs, &spec, OUT_OPTION).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        specs.push(spec);
+    }
+    if specs.is_empty() {
+        return Err("no targets found".to_string());
+    }
+    Ok(specs)
+}
+
+/// Several targets given on the command line, each saved to its default file
+pub fn from_targets(targets: &[&str], case_sensitive: bool) -> Result<Vec<TargetSpec>, String> {
+    let mut specs: Vec<TargetSpec> = Vec::new();
+    for target in targets {
+        let spec = TargetSpec {
+            target: target.to_string(),
+            case_sensitive,
+            output: default_output(target),
+            priority: 0,
+        };
+        check_unique(&specs, &spec, "list the targets in a --targets-file with out=FILE")?;
+        specs.push(spec);
+    }
+    Ok(specs)
+}
+
+/// A new target must not repeat a target or output file already in the campaign.
+/// `fix` tells the user how to pick another output file.
+fn check_unique(specs: &[TargetSpec], spec: &TargetSpec, fix: &str) -> Result<(), String> {
+    if specs.iter().any(|other| other.target == spec.target) {
+        return Err(format!("duplicate target \"{}\"", spec.target));
+    }
+    if specs.iter().any(|other| other.output == spec.output) {
+        return Err(format!(
+            "output file {} is already used by another target; {}",
+            spec.output, fix
+        ));
+    }
+    Ok(())
+}
+
+/// One targets line; None for blank lines and comments
+pub fn parse_line(line: &str, case_sensitive: bool) -> Result<Option<TargetSpec>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut fields = line.split_whitespace();
+    let target = fields.next().expect("line is not empty").to_string();
+    let mut spec = TargetSpec {
+        output: default_output(&target),
+        target,
+        case_sensitive,
+        priority: 0,
+    };
+    for option in fields {
+        match option {
+            "ignore-case" | "-i" => spec.case_sensitive = false,
+            "case-sensitive" => spec.case_sensitive = true,
+            _ => {
+                if let Some(path) = option.strip_prefix("out=").filter(|path| !path.is_empty()) {
+                    spec.output = path.to_string();
+                } else if let Some(priority) = option.strip_prefix("priority=") {
+                    spec.priority = match priority.parse() {
+                        Ok(priority) if priority <= MAX_PRIORITY => priority,
+                        _ => return Err(format!("priority is a number from 0 to {}, not \"{}\"", MAX_PRIORITY, priority)),
+                    };
+                } else {
+                    return Err(format!("unknown option \"{}\"", option));
+                }
+            }
+        }
+    }
+    Ok(Some(spec))
+}
+
+/// `id_ed25519_<target>`, with characters that do not belong in file names replaced
+fn default_output(target: &str) -> String {
+    let safe: String = target
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("id_ed25519_{}", safe)
+}
+
+/// Shared state of a campaign. Claims and new targets are rare, so a mutex is
+/// fine there; workers only poll the generation counter to notice a rebuilt matcher.
+#[derive(Debug)]
+pub struct Campaign {
+    targets: Mutex<Targets>,
+    matcher: RwLock<Arc<MultiMatcher>>,
+    generation: AtomicU64,
+    /// `--until-priority`: done once every target of at least this priority is found
+    until_priority: Option<u32>,
+    /// `--any-target`: done once any one target is found
+    first_only: bool,
+}
+
+#[derive(Debug)]
+struct Targets {
+    specs: Vec<TargetSpec>,
+    satisfied: Vec<bool>,
+    /// Dropped through `--control`; ids stay put so workers' hits remain valid
+    removed: Vec<bool>,
+    /// No more targets will be added (always true for a targets file)
+    closed: bool,
+}
+
+impl Campaign {
+    /// A campaign over a fixed list of targets
+    pub fn new(specs: Vec<TargetSpec>) -> Self {
+        Self::with_targets(specs, true)
+    }
+
+    /// A campaign whose targets arrive later through `add`, until `close`
+    pub fn open() -> Self {
+        Self::with_targets(Vec::new(), false)
+    }
+
+    fn with_targets(specs: Vec<TargetSpec>, closed: bool) -> Self {
+        let targets = Targets {
+            satisfied: vec![false; specs.len()],
+            removed: vec![false; specs.len()],
+            specs,
+            closed,
+        };
+        Self {
+            matcher: RwLock::new(Arc::new(build_matcher(&targets))),
+            targets: Mutex::new(targets),
+            generation: AtomicU64::new(0),
+            until_priority: None,
+            first_only: false,
+        }
+    }
+
+    /// End the campaign once every target of `priority` or more is found,
+    /// without waiting for the others
+    pub fn until_priority(mut self, priority: u32) -> Self {
+        self.until_priority = Some(priority);
+        self
+    }
+
+    /// End the campaign at the first key matching any target, and satisfy no
+    /// other target after that
+    pub fn first_only(mut self) -> Self {
+        self.first_only = true;
+        self
+    }
+
+    pub fn is_first_only(&self) -> bool {
+        self.first_only
+    }
+
+    /// Snapshot of every target so far that was not removed, in the order they were added
+    pub fn specs(&self) -> Vec<TargetSpec> {
+        self.targets.lock().unwrap().live().cloned().collect()
+    }
+
+    pub fn spec(&self, id: usize) -> TargetSpec {
+        self.targets.lock().unwrap().specs[id].clone()
+    }
+
+    /// Bumped every time the matcher is rebuilt
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// The current matcher together with its generation
+    pub fn matcher(&self) -> (u64, Arc<MultiMatcher>) {
+        let matcher = self.matcher.read().unwrap();
+        (self.generation(), matcher.clone())
+    }
+
+    /// Add a target while the search runs
+    pub fn add(&self, spec: TargetSpec) -> Result<(), String> {
+        let mut targets = self.targets.lock().unwrap();
+        let live: Vec<TargetSpec> = targets.live().cloned().collect();
+        check_unique(&live, &spec, OUT_OPTION)?;
+        targets.specs.push(spec);
+        targets.satisfied.push(false);
+        targets.removed.push(false);
+        self.rebuild(&targets);
+        Ok(())
+    }
+
+    /// Stop looking for a target not found yet
+    pub fn remove(&self, target: &str) -> Result<(), String> {
+        let mut targets = self.targets.lock().unwrap();
+        let Some(id) = (0..targets.specs.len()).find(|&id| !targets.removed[id] && targets.specs[id].target == target)
+        else {
+            return Err(format!("no target \"{}\" in this search", target));
+        };
+        if targets.satisfied[id] {
+            return Err(format!("target \"{}\" was already found", target));
+        }
+        targets.removed[id] = true;
+        self.rebuild(&targets);
+        Ok(())
+    }
+
+    /// No more targets will be added
+    pub fn close(&self) {
+        self.targets.lock().unwrap().closed = true;
+    }
+
+    /// Mark a target satisfied. Returns false if another worker got there first.
+    pub fn claim(&self, id: usize) -> bool {
+        let mut targets = self.targets.lock().unwrap();
+        if targets.satisfied[id] || targets.removed[id] || (self.first_only && targets.satisfied.contains(&true)) {
+            return false;
+        }
+        targets.satisfied[id] = true;
+        // Stop spending matcher time on it
+        self.rebuild(&targets);
+        true
+    }
+
+    /// Every target (of `--until-priority` or more) is found and no more can
+    /// arrive, or with `--any-target` one is
+    pub fn is_complete(&self) -> bool {
+        let targets = self.targets.lock().unwrap();
+        if self.first_only && targets.satisfied.contains(&true) {
+            return true;
+        }
+        let minimum = self.until_priority.unwrap_or(0);
+        targets.closed
+            && (0..targets.specs.len())
+                .all(|id| targets.satisfied[id] || targets.removed[id] || targets.specs[id].priority < minimum)
+    }
+
+    /// The `--until-priority` the campaign stops at
+    pub fn stop_priority(&self) -> Option<u32> {
+        self.until_priority
+    }
+
+    /// Targets not found yet
+    pub fn unsatisfied(&self) -> Vec<TargetSpec> {
+        let targets = self.targets.lock().unwrap();
+        (0..targets.specs.len())
+            .filter(|&id| !targets.satisfied[id] && !targets.removed[id])
+            .map(|id| targets.specs[id].clone())
+            .collect()
+    }
+
+    fn rebuild(&self, targets: &Targets) {
+        *self.matcher.write().unwrap() = Arc::new(build_matcher(targets));
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+}
+
+impl Targets {
+    fn live(&self) -> impl Iterator<Item = &TargetSpec> {
+        self.specs.iter().zip(&self.removed).filter(|(_, removed)| !**removed).map(|(spec, _)| spec)
+    }
+}
+
+fn build_matcher(targets: &Targets) -> MultiMatcher {
+    MultiMatcher::new(
+        targets
+            .specs
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !targets.satisfied[*id] && !targets.removed[*id])
+            .map(|(id, spec)| (id, spec.target.as_str(), spec.case_sensitive)),
+    )
+}
@@ -0,0 +1,161 @@
+//! `--checkpoint FILE`: the exact position of a seeded search, saved while it
+//! runs, so an interrupted search resumes where it stopped. The position
+//! lists every index handed out but not yet checked, so resuming neither
+//! re-tests a candidate nor skips one.
+
+use crate::seedstream::Position;
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// 2 added the settings that decide which matches are accepted
+const VERSION: u64 = 2;
+
+/// What the checkpoint is a position in, and what the search accepts as a
+/// match; resuming a different search from it would skip candidates that were
+/// never tested against that search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    /// `KeyStream::identity()`
+    pub stream: String,
+    pub key_type: String,
+    pub target: String,
+    pub case_sensitive: bool,
+    pub position: String,
+    /// First index of the stream, which attempts are counted from
+    pub start: u64,
+    /// `--min-count`
+    pub min_count: u64,
+    /// `Blocklist::identity()`, or "none"
+    pub blocklist: String,
+    /// `Screen::identity()`, or "none"
+    pub screen: String,
+    /// Whether `target` is an `--expr`
+    pub expr: bool,
+    /// `--fingerprint-format`, which `fp_contains` in an `--expr` reads
+    pub fingerprint_format: String,
+    /// `--prefer-exact-case` and its budget, or "off"
+    pub prefer_exact_case: String,
+}
+
+impl Search {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "stream": self.stream,
+            "key_type": self.key_type,
+            "target": self.target,
+            "case_sensitive": self.case_sensitive,
+            "position": self.position,
+            "start": self.start,
+            "min_count": self.min_count,
+            "blocklist": self.blocklist,
+            "screen": self.screen,
+            "expr": self.expr,
+            "fingerprint_format": self.fingerprint_format,
+            "prefer_exact_case": self.prefer_exact_case,
+        })
+    }
+
+    /// Sessions recorded before the acceptance settings were saved read as
+    /// the defaults
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let setting = |field: &str, default: &str| value[field].as_str().unwrap_or(default).to_string();
+        Some(Search {
+            stream: value["stream"].as_str()?.to_string(),
+            key_type: value["key_type"].as_str()?.to_string(),
+            target: value["target"].as_str()?.to_string(),
+            case_sensitive: value["case_sensitive"].as_bool()?,
+            position: value["position"].as_str()?.to_string(),
+            start: value["start"].as_u64()?,
+            min_count: value["min_count"].as_u64().unwrap_or(1),
+            blocklist: setting("blocklist", "none"),
+            screen: setting("screen", "none"),
+            expr: value["expr"].as_bool().unwrap_or(false),
+            fingerprint_format: setting("fingerprint_format", "Sha256"),
+            prefer_exact_case: setting("prefer_exact_case", "off"),
+        })
+    }
+}
+
+/// Ranges as `[start, end]` pairs
+pub fn ranges_to_json(ranges: &[Range<u64>]) -> Value {
+    json!(ranges.iter().map(|range| [range.start, range.end]).collect::<Vec<_>>())
+}
+
+/// Ranges saved by `ranges_to_json`; None unless each lies below `next`
+pub fn ranges_from_json(value: &Value, next: u64) -> Option<Vec<Range<u64>>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|range| match (range[0].as_u64(), range[1].as_u64()) {
+            (Some(start), Some(end)) if start <= end && end <= next => Some(start..end),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The position saved in `path`, or None if there is no checkpoint yet
+pub fn load(path: &Path, search: &Search) -> Result<Option<Position>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let invalid = || format!("{} is not a checkpoint file", path.display());
+    let document: Value = serde_json::from_str(&text).map_err(|_| invalid())?;
+    if document["version"].as_u64() != Some(VERSION) {
+        return Err(format!("{}: unsupported checkpoint version {}", path.display(), document["version"]));
+    }
+
+    let saved = &document["search"];
+    let expected = search.to_json();
+    for (field, label) in [
+        ("stream", "seed stream"),
+        ("key_type", "key type"),
+        ("target", "target"),
+        ("case_sensitive", "case sensitivity"),
+        ("position", "match position"),
+        ("start", "start index"),
+        ("min_count", "--min-count"),
+        ("blocklist", "blocklist"),
+        ("screen", "--screen word list"),
+        ("expr", "--expr setting"),
+        ("fingerprint_format", "--fingerprint-format"),
+        ("prefer_exact_case", "--prefer-exact-case setting"),
+    ] {
+        if saved[field] != expected[field] {
+            return Err(format!(
+                "{} was saved by a search with a different {} ({} rather than {}); delete it or choose another --checkpoint file",
+                path.display(),
+                label,
+                saved[field],
+                expected[field]
+            ));
+        }
+    }
+
+    let next = document["next"].as_u64().ok_or_else(invalid)?;
+    let pending = ranges_from_json(&document["pending"], next).ok_or_else(invalid)?;
+    Ok(Some(Position { pending, next }))
+}
+
+/// Replace the checkpoint in `path`; a crash mid-write leaves the previous one
+pub fn save(path: &Path, search: &Search, position: &Position) -> Result<(), Box<dyn std::error::Error>> {
+    let document = json!({
+        "version": VERSION,
+        "search": search.to_json(),
+        "next": position.next,
+        "pending": ranges_to_json(&position.pending),
+    });
+    crate::cleanup::replace(path, format!("{:#}\n", document).as_bytes(), false)
+}
+
+/// A search that ended with a match has nothing left to resume
+pub fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,118 @@
+//! Files that only exist while the run writes them: a temporary renamed over
+//! its destination once complete, or an archive that is useless half written.
+//! Each one is registered for as long as it exists. Dropping its guard without
+//! `keep` (an error return) removes it, and so does the panic hook, because
+//! release builds abort on panic without running destructors.
+//!
+//! Secret files are overwritten with zeros before they are removed. On SSDs and
+//! journaling or copy-on-write filesystems that is best effort, but it keeps
+//! the bytes out of whatever reuses the file's blocks first.
+
+use crate::permissions;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Every file a live `Pending` guards, and whether it holds secret material
+static PENDING: Mutex<Vec<(PathBuf, bool)>> = Mutex::new(Vec::new());
+
+/// Remove every pending file before a panic aborts the process
+#[cfg(panic = "abort")]
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // A panic while this thread held the list must not deadlock here
+        let pending = match PENDING.try_lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(std::sync::TryLockError::Poisoned(poisoned)) => std::mem::take(&mut *poisoned.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+        };
+        for (path, secret) in &pending {
+            discard(path, *secret);
+        }
+        previous(info);
+    }));
+}
+
+/// With unwinding, the guards' destructors run on a panic and clean up themselves
+#[cfg(not(panic = "abort"))]
+pub fn install_panic_hook() {}
+
+/// A file being written that is removed again unless `keep` is called
+#[derive(Debug)]
+pub struct Pending {
+    path: PathBuf,
+    secret: bool,
+    kept: bool,
+}
+
+impl Pending {
+    pub fn new(path: impl Into<PathBuf>, secret: bool) -> Self {
+        let path = path.into();
+        lock().push((path.clone(), secret));
+        Pending { path, secret, kept: false }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file is complete (or renamed away); leave it
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if !self.kept {
+            discard(&self.path, self.secret);
+        }
+        let mut pending = lock();
+        if let Some(index) = pending.iter().position(|(path, _)| *path == self.path) {
+            pending.swap_remove(index);
+        }
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<(PathBuf, bool)>> {
+    PENDING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Overwrite a secret file with zeros, then remove it; a file that is already
+/// gone is fine
+fn discard(path: &Path, secret: bool) {
+    if secret {
+        if let (Ok(metadata), Ok(mut file)) = (fs::metadata(path), fs::OpenOptions::new().write(true).open(path)) {
+            let zeros = [0u8; 4096];
+            let mut left = metadata.len();
+            while left > 0 {
+                let chunk = left.min(zeros.len() as u64) as usize;
+                if file.write_all(&zeros[..chunk]).is_err() {
+                    break;
+                }
+                left -= chunk as u64;
+            }
+            let _ = file.sync_all();
+        }
+    }
+    let _ = fs::remove_file(path);
+}
+
+/// Write `contents` to `<path>.tmp` and rename it over `path`, so an error or a
+/// crash never leaves `path` half written. Secret contents are owner-only from
+/// the start.
+pub fn replace(path: &Path, contents: &[u8], secret: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let pending = Pending::new(temporary, secret);
+    if secret {
+        permissions::write_private(pending.path(), contents)?;
+    } else {
+        fs::write(pending.path(), contents)?;
+    }
+    fs::rename(pending.path(), path)?;
+    pending.keep();
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! `ssh-keygen compare`: rank existing public keys by how good they look, to
+//! pick the winner after a `--count` or `--continuous` session. Each key is
+//! scored on its target (where it sits, and whether the case is exact), the
+//! dictionary words it shows and its longest run of one character.
+
+use crate::error::Error;
+use crate::install::key_identity;
+use crate::matching::{Matcher, Position, ED25519_PREFIX};
+use aho_corasick::AhoCorasick;
+use std::fs;
+use std::path::Path;
+
+const PACKAGED_WORDS: &str = include_str!("words.txt");
+/// Shorter words turn up in almost every key
+const MIN_WORD_LEN: usize = 4;
+
+/// Options for `ssh-keygen compare`
+pub struct CompareOptions<'a> {
+    pub targets: Vec<&'a str>,
+    pub case_sensitive: bool,
+    /// Replaces the packaged dictionary
+    pub words: Option<&'a str>,
+    /// Public key or authorized_keys files
+    pub files: Vec<&'a str>,
+}
+
+/// One key's score and what it is made of
+struct Scored {
+    label: String,
+    score: u64,
+    target: String,
+    words: Vec<String>,
+    run: String,
+}
+
+/// Score every key in the files and print them best first
+pub fn run(options: &CompareOptions) -> Result<(), Error> {
+    let words = load_words(options.words)?;
+    let dictionary = AhoCorasick::builder()
+        .ascii_case_insensitive(true)
+        .build(&words)
+        .map_err(|e| Error::config(format!("--words: {}", e)))?;
+
+    let mut scored = Vec::new();
+    for file in &options.files {
+        let text = fs::read_to_string(file).map_err(|e| Error::config(format!("{}: cannot read: {}", file, e)))?;
+        let lines: Vec<(usize, String)> = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim_start().starts_with('#'))
+            .filter_map(|(number, line)| key_identity(line).map(|identity| (number, identity)))
+            .collect();
+        let name = Path::new(file).file_name().map_or_else(|| file.to_string(), |name| name.to_string_lossy().into_owned());
+        for (number, identity) in &lines {
+            let label = if lines.len() == 1 { name.clone() } else { format!("{}:{}", name, number + 1) };
+            scored.push(score(label, identity, options, &words, &dictionary));
+        }
+    }
+    if scored.is_empty() {
+        return Err(Error::config("no public keys found to compare"));
+    }
+    // Ties keep the order the keys were given in
+    scored.sort_by_key(|key| std::cmp::Reverse(key.score));
+
+    let width = |column: fn(&Scored) -> usize, title: &str| scored.iter().map(column).max().unwrap_or(0).max(title.len());
+    let target_width = width(|key| key.target.len(), "Target");
+    let words_width = width(|key| key.words.join(",").len(), "Words");
+    let run_width = width(|key| key.run.len(), "Run");
+    println!(
+        "{:>4}  {:>5}  {:<target_width$}  {:<words_width$}  {:<run_width$}  Key",
+        "Rank", "Score", "Target", "Words", "Run"
+    );
+    for (rank, key) in scored.iter().enumerate() {
+        println!(
+            "{:>4}  {:>5}  {:<target_width$}  {:<words_width$}  {:<run_width$}  {}",
+            rank + 1,
+            key.score,
+            if key.target.is_empty() { "-" } else { &key.target },
+            if key.words.is_empty() { "-".to_string() } else { key.words.join(",") },
+            if key.run.is_empty() { "-" } else { &key.run },
+            key.label
+        );
+    }
+    Ok(())
+}
+
+/// Points: 100 for each target found, plus 50 right after the fixed prefix or
+/// 30 at the very end and 10 in exactly the case given; the square of each
+/// dictionary word's length; the square of the longest run of 3 or more
+fn score(label: String, identity: &str, options: &CompareOptions, words: &[String], dictionary: &AhoCorasick) -> Scored {
+    let body = identity.strip_prefix(ED25519_PREFIX).unwrap_or(identity);
+    let mut points = 0;
+
+    let mut targets = Vec::new();
+    for target in &options.targets {
+        let found = |position| Matcher::at(target, options.case_sensitive, position).is_match(identity.as_bytes());
+        if !found(Position::Anywhere) {
+            continue;
+        }
+        points += 100;
+        let place = if found(Position::AtStart) {
+            points += 50;
+            " at start"
+        } else if found(Position::AtEnd) {
+            points += 30;
+            " at end"
+        } else {
+            ""
+        };
+        let exact = !options.case_sensitive && Matcher::new(target, true).is_match(identity.as_bytes());
+        if exact {
+            points += 10;
+        }
+        targets.push(format!("{}{}{}", target, place, if exact { " (exact case)" } else { "" }));
+    }
+
+    let mut found_words: Vec<String> = Vec::new();
+    for hit in dictionary.find_overlapping_iter(body) {
+        let word = &words[hit.pattern().as_usize()];
+        if !found_words.contains(word) {
+            points += (word.len() * word.len()) as u64;
+            found_words.push(word.clone());
+        }
+    }
+
+    let run = longest_run(body);
+    let run = if run.len() >= 3 {
+        points += (run.len() * run.len()) as u64;
+        run.to_string()
+    } else {
+        String::new()
+    };
+
+    Scored {
+        label,
+        score: points,
+        target: targets.join(", "),
+        words: found_words,
+        run,
+    }
+}
+
+/// The longest stretch of one repeated character (the first, on a tie)
+fn longest_run(text: &str) -> &str {
+    let bytes = text.as_bytes();
+    let (mut best, mut start) = (0..0, 0);
+    for end in 1..=bytes.len() {
+        if end == bytes.len() || bytes[end] != bytes[start] {
+            if end - start > best.len() {
+                best = start..end;
+            }
+            start = end;
+        }
+    }
+    &text[best]
+}
+
+/// The packaged dictionary, or the words in `file`; lowercased, four letters or more
+fn load_words(file: Option<&str>) -> Result<Vec<String>, Error> {
+    let text = match file {
+        Some(path) => fs::read_to_string(path).map_err(|e| Error::config(format!("--words {}: {}", path, e)))?,
+        None => PACKAGED_WORDS.to_string(),
+    };
+    let mut words: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && line.len() >= MIN_WORD_LEN && line.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(str::to_ascii_lowercase)
+        .collect();
+    words.sort();
+    words.dedup();
+    if words.is_empty() {
+        return Err(Error::config("--words: no words of four letters or more"));
+    }
+    Ok(words)
+}
@@ -0,0 +1,146 @@
+//! Control socket (`--control SOCKET`) for a running search, and the client
+//! side used by `ssh-keygen ctl`. One request line per connection, one
+//! response line back: "ok: ..." or "error: ...".
+//!
+//! Anyone who can connect can add targets that write key files, so the socket
+//! is restricted to its owner.
+
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+
+/// What the socket accepts, for error messages and `ctl --help`
+pub const USAGE: &str = "add TARGET [ignore-case] [out=FILE] [priority=N] | remove TARGET | threads N | status";
+
+/// A client that stops sending must not hold up the next one for long
+#[cfg(unix)]
+const CLIENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// One request
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    /// A targets-file line: TARGET [ignore-case] [out=FILE] [priority=N]
+    Add(String),
+    Remove(String),
+    Threads(usize),
+    Status,
+}
+
+impl Request {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (verb, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match (verb, rest.is_empty()) {
+            ("add", false) => Ok(Request::Add(rest.to_string())),
+            ("remove", false) if !rest.contains(char::is_whitespace) => Ok(Request::Remove(rest.to_string())),
+            ("threads", false) => match rest.parse::<usize>() {
+                Ok(count) if count > 0 => Ok(Request::Threads(count)),
+                _ => Err(format!("threads needs a worker count of at least 1, not \"{}\"", rest)),
+            },
+            ("status", true) => Ok(Request::Status),
+            _ => Err(format!("unknown request \"{}\"; expected {}", line, USAGE)),
+        }
+    }
+}
+
+/// The listening socket; the socket file is removed when this drops
+pub struct Server {
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    path: String,
+}
+
+impl Server {
+    /// Listen on `path`. A socket file left behind by a search that did not
+    /// exit cleanly is replaced; one a live search still answers on is not.
+    #[cfg(unix)]
+    pub fn bind(path: &str) -> Result<Self, String> {
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} exists and is not a socket", path));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!("another search is already listening on {}", path));
+            }
+            std::fs::remove_file(path).map_err(|e| format!("cannot replace stale socket {}: {}", path, e))?;
+        }
+        // Owner-only from the moment it exists, not just after the chmod below;
+        // this runs before any worker thread could create files of its own
+        // SAFETY: umask only swaps the process file-creation mask
+        let previous = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(path);
+        unsafe { libc::umask(previous) };
+        let listener = listener.map_err(|e| format!("cannot listen on {}: {}", path, e))?;
+        let server = Self {
+            listener,
+            path: path.to_string(),
+        };
+        crate::permissions::restrict_to_owner(path.as_ref())
+            .map_err(|e| format!("cannot restrict {} to its owner: {}", path, e))?;
+        Ok(server)
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_path: &str) -> Result<Self, String> {
+        Err("--control needs Unix domain sockets, which this platform does not have".to_string())
+    }
+
+    /// Answer requests on a background thread for as long as the process runs
+    #[cfg(unix)]
+    pub fn serve(&self, handle: impl Fn(Request) -> Result<String, String> + Send + 'static) -> Result<(), String> {
+        let listener = self.listener.try_clone().map_err(|e| format!("cannot share control socket: {}", e))?;
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+                let mut line = String::new();
+                let mut reader = BufReader::new(&stream);
+                if reader.read_line(&mut line).is_err() {
+                    continue;
+                }
+                let response = match Request::parse(&line).and_then(&handle) {
+                    Ok(message) => format!("ok: {}\n", message),
+                    Err(e) => format!("error: {}\n", e),
+                };
+                // A client that hung up misses its answer; the request was still applied
+                let _ = (&stream).write_all(response.as_bytes());
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn serve(&self, _handle: impl Fn(Request) -> Result<String, String> + Send + 'static) -> Result<(), String> {
+        Err("--control needs Unix domain sockets, which this platform does not have".to_string())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// `ssh-keygen ctl`: send one request and return the response, Err(message)
+/// for an "error: ..." response
+#[cfg(unix)]
+pub fn send(path: &str, request: &str) -> std::io::Result<Result<String, String>> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.write_all(format!("{}\n", request).as_bytes())?;
+    let mut response = String::new();
+    BufReader::new(&stream).read_line(&mut response)?;
+    let response = response.trim_end();
+    Ok(match response.strip_prefix("error: ") {
+        Some(message) => Err(message.to_string()),
+        None => Ok(response.strip_prefix("ok: ").unwrap_or(response).to_string()),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &str, _request: &str) -> std::io::Result<Result<String, String>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "control sockets need Unix domain sockets",
+    ))
+}
@@ -0,0 +1,113 @@
+use crate::cleanup;
+use crate::error::Error;
+use crate::formats::{self, KeyFormat};
+use crate::passphrase::{self, PassphraseSource};
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// What to do with the passphrase of the converted key
+pub enum NewPassphrase {
+    /// Re-encrypt with the passphrase the input was read with (if any)
+    Keep,
+    Remove,
+    Set(PassphraseSource),
+}
+
+/// Options for `ssh-keygen convert`
+pub struct ConvertOptions<'a> {
+    pub input: &'a str,
+    /// Defaults to rewriting the input in place, or `<input>.<ext>` for another format
+    pub output: Option<&'a str>,
+    /// Defaults to the input's format
+    pub format: Option<KeyFormat>,
+    pub comment: Option<&'a str>,
+    /// Passphrase of the input key, asked for only when it is encrypted
+    pub passphrase: PassphraseSource,
+    pub new_passphrase: NewPassphrase,
+    /// `--cipher`/`--kdf-rounds`, if given; only for encrypted OpenSSH output
+    pub openssh_encryption: Option<formats::OpenSshEncryption>,
+}
+
+/// Rewrite an existing private key in another format, passphrase or comment
+pub fn run(options: &ConvertOptions) -> Result<(), Error> {
+    let input = options.input;
+    let text = Zeroizing::new(
+        fs::read_to_string(input).map_err(|e| Error::config(format!("{}: cannot read private key: {}", input, e)))?,
+    );
+    let encrypted = formats::is_encrypted(&text).map_err(|e| Error::config(format!("{}: {}", input, e)))?;
+    let old_passphrase = if encrypted {
+        Some(passphrase::resolve_existing(&options.passphrase, input).map_err(Error::config)?)
+    } else {
+        None
+    };
+    let decoded = formats::decode(&text, old_passphrase.as_ref().map(|p| p.expose()))
+        .map_err(|e| Error::config(format!("{}: {}", input, e)))?;
+
+    let format = options.format.unwrap_or(decoded.format);
+    let comment = options.comment.unwrap_or(&decoded.comment);
+    if !format.has_comment() && !comment.is_empty() {
+        eprintln!("Warning: {} files have no comment field; dropping \"{}\"", format.name(), comment);
+    }
+
+    let new_passphrase = match &options.new_passphrase {
+        NewPassphrase::Keep => old_passphrase,
+        NewPassphrase::Remove => None,
+        NewPassphrase::Set(source) => passphrase::resolve(source).map_err(Error::config)?,
+    };
+
+    let output = match options.output {
+        Some(output) => output.to_string(),
+        None => default_output(input, decoded.format, format),
+    };
+    if output != input && Path::new(&output).exists() {
+        return Err(Error::config(format!(
+            "{} already exists; choose another file with --output",
+            output
+        )));
+    }
+
+    if options.openssh_encryption.is_some() && (format != KeyFormat::OpenSsh || new_passphrase.is_none()) {
+        return Err(Error::config("--cipher and --kdf-rounds only apply to passphrase-protected OpenSSH keys"));
+    }
+    let openssh_encryption = options.openssh_encryption.unwrap_or_default();
+
+    let encoded = formats::encode_with(
+        &decoded.signing_key,
+        comment,
+        format,
+        new_passphrase.as_ref().map(|p| p.expose()),
+        &openssh_encryption,
+    )
+    .map_err(|e| Error::io("encoding private key", e))?;
+    write_private_key(&output, &encoded).map_err(|e| Error::io(format!("writing {}", output), e))?;
+
+    println!(
+        "Converted {} ({}{}) to {} ({}{})",
+        input,
+        decoded.format.name(),
+        if encrypted { ", encrypted" } else { "" },
+        output,
+        format.name(),
+        if new_passphrase.is_some() { ", encrypted" } else { "" }
+    );
+    if new_passphrase.is_some() && format == KeyFormat::OpenSsh {
+        println!("Private key encrypted with {}", openssh_encryption.describe());
+    }
+    Ok(())
+}
+
+/// `id_ed25519.ppk` -> `id_ed25519.pem` and so on; same format rewrites in place
+fn default_output(input: &str, from: KeyFormat, to: KeyFormat) -> String {
+    if from == to {
+        return input.to_string();
+    }
+    let base = input.strip_suffix(from.extension()).filter(|base| !base.is_empty()).unwrap_or(input);
+    format!("{}{}", base, to.extension())
+}
+
+/// Write next to the destination and rename, so an in-place rewrite never
+/// leaves a truncated key behind
+fn write_private_key(path: &str, contents: &str) -> Result<(), Box<dyn std::error::Error>> {
+    cleanup::replace(path.as_ref(), contents.as_bytes(), true)
+}
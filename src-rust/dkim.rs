@@ -0,0 +1,53 @@
+//! `--emit dkim`: the key as an RFC 8463 Ed25519 DKIM key, in the files
+//! `opendkim-genkey` writes: `<selector>.private` and `<selector>.txt`.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+
+/// Where the DKIM key is published: `<selector>._domainkey.<domain>`
+#[derive(Debug, Clone)]
+pub struct Selector {
+    selector: String,
+    domain: String,
+}
+
+impl Selector {
+    pub fn new(selector: &str, domain: &str) -> Result<Self, String> {
+        let domain = domain.trim_end_matches('.');
+        if selector.split('.').any(|label| !is_dns_label(label)) {
+            return Err(format!("--selector {}: not a valid DNS name", selector));
+        }
+        if domain.split('.').any(|label| !is_dns_label(label)) {
+            return Err(format!("--domain {}: not a valid DNS name", domain));
+        }
+        Ok(Self {
+            selector: selector.to_string(),
+            domain: domain.to_ascii_lowercase(),
+        })
+    }
+
+    /// Private key (PEM) and TXT record files, in the current directory
+    pub fn output_paths(&self) -> (String, String) {
+        (format!("{}.private", self.selector), format!("{}.txt", self.selector))
+    }
+
+    /// Zone-file line publishing the key; `p=` is the raw 32-byte public key
+    pub fn txt_record(&self, signing_key: &SigningKey) -> String {
+        format!(
+            "{}._domainkey.{}. IN TXT \"v=DKIM1; k=ed25519; p={}\"\n",
+            self.selector,
+            self.domain,
+            BASE64.encode(signing_key.verifying_key().to_bytes())
+        )
+    }
+}
+
+/// Letters, digits, underscores and inner hyphens, at most 63 characters
+fn is_dns_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+}
@@ -0,0 +1,233 @@
+//! `ssh-keygen doctor`: what this machine and build mean for a search. It
+//! reports the CPU features and curve25519-dalek backend, the RNG and its
+//! startup health test, the default worker count, core-dump and debugger
+//! status, the terminal, and a short measured rate, then flags anything that
+//! will cost speed or put a key at risk.
+
+use crate::basepoint;
+use crate::bench;
+use crate::entropy;
+use crate::error::Error;
+use crate::hardening;
+use crate::power;
+use crate::thermal;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+/// Options for `ssh-keygen doctor`
+pub struct DoctorOptions {
+    /// How long to measure the default worker count; None skips it
+    pub duration: Option<Duration>,
+}
+
+/// Optional cargo features and whether this build has them
+const FEATURES: [(&str, bool); 10] = [
+    ("uploaders", cfg!(feature = "uploaders")),
+    ("notify", cfg!(feature = "notify")),
+    ("email", cfg!(feature = "email")),
+    ("encrypt", cfg!(feature = "encrypt")),
+    ("bundle", cfg!(feature = "bundle")),
+    ("history", cfg!(feature = "history")),
+    ("paper", cfg!(feature = "paper")),
+    ("self-update", cfg!(feature = "self-update")),
+    ("mnemonic", cfg!(feature = "mnemonic")),
+    ("large-tables", cfg!(feature = "large-tables")),
+];
+
+/// Print the report; `measure` runs that many workers for the given time and
+/// returns keys per second
+pub fn run(options: &DoctorOptions, measure: impl Fn(usize, Duration) -> Result<f64, Error>) -> Result<(), Error> {
+    let mut findings = Vec::new();
+
+    let features: Vec<&str> = FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    println!(
+        "Build:      {} {}, features: {}",
+        env!("CARGO_PKG_VERSION"),
+        if cfg!(debug_assertions) { "debug" } else { "release" },
+        if features.is_empty() { "none (minimal build)".to_string() } else { features.join(", ") }
+    );
+    if cfg!(debug_assertions) {
+        findings.push("this is a debug build, several times slower than a release build; use cargo build --release".to_string());
+    }
+
+    let physical = bench::default_workers();
+    let logical = num_cpus::get();
+    let cpu_features = cpu_features();
+    println!(
+        "CPU:        {} physical, {} logical{}; {} {}",
+        physical,
+        logical,
+        if logical > physical { " (SMT)" } else { "" },
+        std::env::consts::ARCH,
+        if cpu_features.is_empty() { "(no relevant features detected)".to_string() } else { cpu_features.join(", ") }
+    );
+    println!("Backend:    curve25519-dalek {}, basepoint table {}", dalek_backend(), basepoint::choice().table.describe());
+    if std::env::consts::ARCH == "x86_64" && !cpu_features.contains(&"AVX2") {
+        findings.push("no AVX2: curve25519-dalek runs its portable code, expect a lower rate than on comparable CPUs".to_string());
+    }
+
+    println!("Workers:    {} by default (one per physical core; --threads overrides), no CPU pinning", physical);
+    if let Some((allowed, online)) = affinity() {
+        if allowed < online {
+            findings.push(format!(
+                "this process may only use {} of {} online CPUs (taskset, cgroup or container limit)",
+                allowed, online
+            ));
+        }
+    }
+    if let Some(governor) = governor() {
+        println!("Governor:   {}", governor);
+        if governor == "powersave" {
+            findings.push("the CPU frequency governor is powersave; searches run slower than under performance or schedutil".to_string());
+        }
+    }
+    if let Some(temperature) = thermal::cpu_temperature() {
+        println!("CPU temp:   {:.0} °C", temperature);
+    }
+    if power::on_battery() == Some(true) {
+        findings.push("running on battery: long searches drain it and may be throttled (--pause-on-battery)".to_string());
+    }
+
+    match entropy::health_check(&[], false) {
+        Ok(reports) => {
+            let report = &reports[0];
+            println!(
+                "RNG:        {} by default, startup health test passed ({} bytes, chi-square {:.1})",
+                report.source, report.bytes, report.chi_square
+            );
+        }
+        Err(e) => {
+            println!("RNG:        startup health test FAILED: {}", e);
+            findings.push(format!("the OS random number generator failed its health test ({}); do not generate keys here", e));
+        }
+    }
+
+    match hardening::core_dump_limit() {
+        Some(0) => println!("Core dumps: off"),
+        Some(limit) => {
+            println!(
+                "Core dumps: on (limit {}){}",
+                if limit == u64::MAX { "unlimited".to_string() } else { format!("{} bytes", limit) },
+                core_pattern().map_or(String::new(), |pattern| format!(", core_pattern {}", pattern))
+            );
+            findings.push("core dumps are on: a crash could write key material to disk; --hardened turns them off".to_string());
+        }
+        None => println!("Core dumps: not applicable"),
+    }
+    if hardening::debugger_present() {
+        findings.push("a debugger is attached to this process; it can read every key (--hardened refuses to run)".to_string());
+    }
+
+    let (stdout, stderr) = (std::io::stdout().is_terminal(), std::io::stderr().is_terminal());
+    let term = std::env::var("TERM").unwrap_or_default();
+    println!(
+        "Terminal:   stdout {}, stderr {}{}",
+        if stdout { "is a terminal" } else { "is not a terminal" },
+        if stderr { "is a terminal" } else { "is not a terminal" },
+        if term.is_empty() { String::new() } else { format!(", TERM={}", term) }
+    );
+    if stdout && term == "dumb" {
+        findings.push("TERM=dumb: the progress line redraws may show as separate lines; --ci prints log-friendly output".to_string());
+    }
+
+    if let Some(duration) = options.duration {
+        println!("Measuring {} workers for {}s...", physical, duration.as_secs_f64());
+        let rate = measure(physical, duration)?;
+        println!("Rate:       {:.0} keys/s", rate);
+    }
+
+    if findings.is_empty() {
+        println!("No problems found");
+    } else {
+        println!("Findings:");
+        for finding in &findings {
+            println!("  ! {}", finding);
+        }
+    }
+    Ok(())
+}
+
+/// The CPU features curve25519-dalek, SHA-512 and the entropy sources use
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        for (name, present) in [
+            ("AVX2", std::arch::is_x86_feature_detected!("avx2")),
+            ("AVX-512 IFMA", std::arch::is_x86_feature_detected!("avx512ifma")),
+            ("BMI2", std::arch::is_x86_feature_detected!("bmi2")),
+            ("ADX", std::arch::is_x86_feature_detected!("adx")),
+            ("SHA", std::arch::is_x86_feature_detected!("sha")),
+            ("RDRAND", std::arch::is_x86_feature_detected!("rdrand")),
+            ("RDSEED", std::arch::is_x86_feature_detected!("rdseed")),
+        ] {
+            if present {
+                features.push(name);
+            }
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        for (name, present) in [
+            ("NEON", std::arch::is_aarch64_feature_detected!("neon")),
+            ("SHA2", std::arch::is_aarch64_feature_detected!("sha2")),
+            ("SHA3", std::arch::is_aarch64_feature_detected!("sha3")),
+        ] {
+            if present {
+                features.push(name);
+            }
+        }
+    }
+    features
+}
+
+/// curve25519-dalek picks its backend at build time, and on x86_64 the AVX2
+/// code path at run time; there is no NEON backend
+fn dalek_backend() -> &'static str {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        return "simd backend (AVX2)";
+    }
+    if cfg!(target_pointer_width = "64") {
+        "serial backend (64-bit)"
+    } else {
+        "serial backend (32-bit)"
+    }
+}
+
+/// CPUs this process may run on and CPUs online, where Linux can tell
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn affinity() -> Option<(usize, usize)> {
+    // SAFETY: cpu_set_t is plain data, and sched_getaffinity fills at most its size
+    let allowed = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        libc::CPU_COUNT(&set) as usize
+    };
+    // SAFETY: sysconf has no preconditions
+    let online = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    (online > 0).then_some((allowed, online as usize))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn affinity() -> Option<(usize, usize)> {
+    None
+}
+
+/// The cpufreq governor of the first CPU, on Linux
+fn governor() -> Option<String> {
+    std::fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+        .ok()
+        .map(|governor| governor.trim().to_string())
+        .filter(|governor| !governor.is_empty())
+}
+
+/// Where Linux writes core dumps (a path, or `|program` for a crash handler)
+fn core_pattern() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/core_pattern")
+        .ok()
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+}
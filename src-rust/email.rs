@@ -0,0 +1,133 @@
+//! `--notify-email ADDRESS`: mail the outcome, public key, fingerprint and
+//! stats when the run ends, for places where SMTP is the only way out.
+//!
+//! The SMTP settings come from the environment, or from `--smtp-config FILE`
+//! holding the same `NAME=value` lines (`#` starts a comment); the file wins
+//! over the environment for anything set in both:
+//!
+//! - `SMTP_HOST` (required) and `SMTP_PORT` (587, 465 or 25 by default, as below)
+//! - `SMTP_SECURITY`: `starttls` (default), `tls` or `none`
+//! - `SMTP_USERNAME` and `SMTP_PASSWORD`, when the server wants a login
+//! - `SMTP_FROM`: the sender, by default `ssh-keygen@<this host>`
+
+use crate::secret::SecretString;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const VARIABLES: [&str; 6] = ["SMTP_HOST", "SMTP_PORT", "SMTP_SECURITY", "SMTP_USERNAME", "SMTP_PASSWORD", "SMTP_FROM"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Security {
+    /// Plain connection upgraded with STARTTLS, which the server must offer
+    StartTls,
+    /// TLS from the first byte (SMTPS)
+    Tls,
+    /// Unencrypted, for a relay on a trusted network
+    None,
+}
+
+/// Where and how the mail goes
+#[derive(Debug, Clone)]
+pub struct Settings {
+    host: String,
+    port: u16,
+    security: Security,
+    credentials: Option<(String, SecretString)>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl Settings {
+    /// Read the settings for `recipients`, checking everything before the search starts
+    pub fn load(recipients: &[String], file: Option<&str>) -> Result<Self, String> {
+        let mut values: HashMap<&str, String> = VARIABLES
+            .iter()
+            .filter_map(|&name| std::env::var(name).ok().filter(|value| !value.is_empty()).map(|value| (name, value)))
+            .collect();
+        if let Some(path) = file {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("--smtp-config {}: {}", path, e))?;
+            for (number, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("--smtp-config {} line {}: expected NAME=value", path, number + 1))?;
+                let name = VARIABLES
+                    .iter()
+                    .find(|&&known| known == name.trim())
+                    .ok_or_else(|| format!("--smtp-config {} line {}: unknown setting \"{}\"", path, number + 1, name.trim()))?;
+                values.insert(name, value.trim().trim_matches('"').to_string());
+            }
+        }
+
+        let host = values.remove("SMTP_HOST").ok_or("--notify-email needs SMTP_HOST (in the environment or --smtp-config)")?;
+        let security = match values.get("SMTP_SECURITY").map(|value| value.to_ascii_lowercase()).as_deref() {
+            None | Some("starttls") => Security::StartTls,
+            Some("tls") => Security::Tls,
+            Some("none") => Security::None,
+            Some(other) => return Err(format!("SMTP_SECURITY is starttls, tls or none, not \"{}\"", other)),
+        };
+        let port = match values.get("SMTP_PORT") {
+            Some(port) => port.parse().map_err(|_| format!("SMTP_PORT \"{}\" is not a port number", port))?,
+            None => match security {
+                Security::StartTls => 587,
+                Security::Tls => 465,
+                Security::None => 25,
+            },
+        };
+        let credentials = match (values.remove("SMTP_USERNAME"), values.remove("SMTP_PASSWORD")) {
+            (Some(user), Some(password)) => Some((user, SecretString::new(password))),
+            (None, None) => None,
+            _ => return Err("SMTP_USERNAME and SMTP_PASSWORD go together".to_string()),
+        };
+        let from = match values.get("SMTP_FROM") {
+            Some(from) => from.clone(),
+            None => format!("ssh-keygen@{}", gethostname::gethostname().to_string_lossy()),
+        };
+        let mailbox = |address: &str| -> Result<Mailbox, String> {
+            address.parse().map_err(|e| format!("\"{}\" is not an email address: {}", address, e))
+        };
+        Ok(Settings {
+            host,
+            port,
+            security,
+            credentials,
+            from: mailbox(&from)?,
+            to: recipients.iter().map(|address| mailbox(address)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The recipients, for messages
+    pub fn recipients(&self) -> String {
+        self.to.iter().map(|mailbox| mailbox.email.to_string()).collect::<Vec<_>>().join(", ")
+    }
+
+    pub fn send(&self, subject: &str, body: String) -> Result<(), String> {
+        let mut message = Message::builder().from(self.from.clone()).subject(subject).header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message.body(body).map_err(|e| e.to_string())?;
+
+        let builder = match self.security {
+            Security::StartTls => SmtpTransport::starttls_relay(&self.host).map_err(|e| e.to_string())?,
+            Security::Tls => SmtpTransport::relay(&self.host).map_err(|e| e.to_string())?,
+            Security::None => SmtpTransport::builder_dangerous(&self.host),
+        };
+        let mut builder = builder.port(self.port).timeout(Some(TIMEOUT));
+        if let Some((user, password)) = &self.credentials {
+            builder = builder.credentials(Credentials::new(user.clone(), password.expose().to_string()));
+        }
+        builder
+            .build()
+            .send(&message)
+            .map(|_| ())
+            .map_err(|e| format!("{}:{}: {}", self.host, self.port, e))
+    }
+}
@@ -0,0 +1,58 @@
+use age::armor::{ArmoredWriter, Format};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Parse a single `--encrypt-to` value: an age X25519 recipient (`age1...`),
+/// an SSH public key, or a file listing one recipient per line (like `age -R`)
+pub fn parse_recipients(
+    spec: &str,
+) -> Result<Vec<Box<dyn age::Recipient + Send>>, Box<dyn std::error::Error>> {
+    if Path::new(spec).is_file() {
+        let text = fs::read_to_string(spec)?;
+        let mut recipients = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            recipients.push(parse_recipient(line)?);
+        }
+        if recipients.is_empty() {
+            return Err(format!("no recipients found in {}", spec).into());
+        }
+        return Ok(recipients);
+    }
+
+    Ok(vec![parse_recipient(spec)?])
+}
+
+fn parse_recipient(value: &str) -> Result<Box<dyn age::Recipient + Send>, Box<dyn std::error::Error>> {
+    if value.starts_with("age1") {
+        let recipient: age::x25519::Recipient = value
+            .parse()
+            .map_err(|e| format!("invalid age recipient {:?}: {}", value, e))?;
+        return Ok(Box::new(recipient));
+    }
+
+    let recipient: age::ssh::Recipient = value
+        .parse()
+        .map_err(|e| format!("invalid recipient {:?}: {:?}", value, e))?;
+    Ok(Box::new(recipient))
+}
+
+/// Encrypt `plaintext` to all recipients as an ASCII-armored age file
+pub fn encrypt_armored(
+    plaintext: &[u8],
+    recipients: &[Box<dyn age::Recipient + Send>],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let encryptor = age::Encryptor::with_recipients(
+        recipients.iter().map(|recipient| recipient.as_ref() as &dyn age::Recipient),
+    )?;
+
+    let mut output = Vec::new();
+    let armor = ArmoredWriter::wrap_output(&mut output, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?.finish()?;
+    Ok(output)
+}
@@ -0,0 +1,447 @@
+use rand::rngs::OsRng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Bytes of OsRng output checked at every startup
+const QUICK_SAMPLE: usize = 4096;
+/// Bytes checked per source with --paranoid-rng
+const PARANOID_SAMPLE: usize = 1 << 20;
+/// Bytes taken from each additional source when building the pool
+const SOURCE_BYTES: usize = 64;
+/// Upper bound on what is read from a user-supplied entropy file
+const FILE_LIMIT: u64 = 1 << 20;
+
+/// `--rng` choices; `--entropy-source` selects the hardware mixer instead
+pub const RNGS: [&str; 4] = ["os", "chacha", "partitioned", "seeded:SEED"];
+
+/// Printed before a search with `--rng seeded`
+pub const SEEDED_WARNING: &str = "\
+WARNING: --rng seeded is for testing only. Every worker's candidates follow from
+         the seed; never use the keys it finds.";
+
+/// Repetition count cutoff: 1 + ceil(20 / H) with a conservative H = 2 bits per
+/// byte (SP 800-90B 4.4.1), so a healthy source essentially never trips it
+const REPETITION_CUTOFF: usize = 11;
+/// Chi-square cutoff for the byte histogram (255 degrees of freedom, ~6 sigma)
+const CHI_SQUARE_CUTOFF: f64 = 400.0;
+
+/// An additional entropy source selected with `--entropy-source`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// RDSEED, falling back to RDRAND, on x86_64 CPUs
+    Cpu,
+    /// The kernel's hardware RNG device
+    HwRng,
+    /// A user-supplied file (e.g. output of a hardware token or dice rolls)
+    File(String),
+}
+
+impl Source {
+    pub fn parse(spec: &str) -> Self {
+        match spec {
+            "rdrand" | "rdseed" | "cpu" => Source::Cpu,
+            "hwrng" => Source::HwRng,
+            path => Source::File(path.to_string()),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Source::Cpu => "rdseed/rdrand".to_string(),
+            Source::HwRng => "/dev/hwrng".to_string(),
+            Source::File(path) => path.clone(),
+        }
+    }
+
+    /// Read `len` bytes (files are read whole and hashed down instead)
+    fn read(&self, len: usize) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+        let mut buf = Zeroizing::new(vec![0u8; len]);
+        match self {
+            Source::Cpu => read_cpu(&mut buf)?,
+            Source::HwRng => std::fs::File::open("/dev/hwrng")
+                .and_then(|mut f| f.read_exact(&mut buf))
+                .map_err(|e| format!("cannot read /dev/hwrng: {}", e))?,
+            Source::File(path) => {
+                let mut contents = Zeroizing::new(Vec::new());
+                std::fs::File::open(path)
+                    .and_then(|f| f.take(FILE_LIMIT).read_to_end(&mut contents))
+                    .map_err(|e| format!("cannot read entropy file {}: {}", path, e))?;
+                if contents.is_empty() {
+                    return Err(format!("entropy file {} is empty", path).into());
+                }
+                return Ok(contents);
+            }
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn read_cpu(buf: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::arch::x86_64::{_rdrand64_step, _rdseed64_step};
+
+    #[target_feature(enable = "rdseed")]
+    unsafe fn rdseed(out: &mut u64) -> bool {
+        _rdseed64_step(out) == 1
+    }
+    #[target_feature(enable = "rdrand")]
+    unsafe fn rdrand(out: &mut u64) -> bool {
+        _rdrand64_step(out) == 1
+    }
+
+    let step: unsafe fn(&mut u64) -> bool = if is_x86_feature_detected!("rdseed") {
+        rdseed
+    } else if is_x86_feature_detected!("rdrand") {
+        rdrand
+    } else {
+        return Err("this CPU supports neither RDSEED nor RDRAND".into());
+    };
+
+    for chunk in buf.chunks_mut(8) {
+        let mut value = 0u64;
+        let mut tries = 0;
+        // Both instructions may transiently fail when the DRNG is drained
+        // SAFETY: the required CPU feature was detected above
+        while !unsafe { step(&mut value) } {
+            tries += 1;
+            if tries == 1000 {
+                return Err("RDSEED/RDRAND kept failing".into());
+            }
+            std::hint::spin_loop();
+        }
+        chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn read_cpu(_buf: &mut [u8]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("rdseed/rdrand are only available on x86_64".into())
+}
+
+/// Outcome of the startup tests for one source
+#[derive(Debug)]
+pub struct HealthReport {
+    pub source: String,
+    pub bytes: usize,
+    pub longest_run: usize,
+    pub chi_square: f64,
+}
+
+/// Run the startup health tests on a sample: the SP 800-90B repetition count
+/// test, a byte-histogram chi-square test and a duplicate 32-byte block check
+fn check_sample(source: String, sample: &[u8]) -> Result<HealthReport, Box<dyn std::error::Error>> {
+    let mut longest_run = 1;
+    let mut run = 1;
+    for pair in sample.windows(2) {
+        run = if pair[0] == pair[1] { run + 1 } else { 1 };
+        longest_run = longest_run.max(run);
+    }
+    if longest_run >= REPETITION_CUTOFF {
+        return Err(format!("{}: {} identical bytes in a row", source, longest_run).into());
+    }
+
+    let mut counts = [0u64; 256];
+    sample.iter().for_each(|&b| counts[b as usize] += 1);
+    let expected = sample.len() as f64 / 256.0;
+    let chi_square: f64 = counts
+        .iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum();
+    if chi_square > CHI_SQUARE_CUTOFF {
+        return Err(format!("{}: byte distribution is skewed (chi-square {:.1})", source, chi_square).into());
+    }
+
+    let mut blocks: Vec<&[u8]> = sample.chunks_exact(32).collect();
+    blocks.sort_unstable();
+    if blocks.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(format!("{}: repeated 32-byte block", source).into());
+    }
+
+    Ok(HealthReport {
+        source,
+        bytes: sample.len(),
+        longest_run,
+        chi_square,
+    })
+}
+
+/// Health-check OsRng and every extra source. Paranoid mode uses a much
+/// larger sample; any failure aborts key generation.
+pub fn health_check(
+    sources: &[Source],
+    paranoid: bool,
+) -> Result<Vec<HealthReport>, Box<dyn std::error::Error>> {
+    let sample_len = if paranoid { PARANOID_SAMPLE } else { QUICK_SAMPLE };
+    let mut sample = Zeroizing::new(vec![0u8; sample_len]);
+    OsRng.try_fill_bytes(&mut sample)?;
+    let mut reports = vec![check_sample("OsRng".to_string(), &sample)?];
+
+    for source in sources {
+        // Files are hashed into the pool, so they need not look uniform
+        // (dice rolls, typed noise); only the live generators are tested
+        if matches!(source, Source::File(_)) {
+            continue;
+        }
+        let sample = source.read(sample_len)?;
+        reports.push(check_sample(source.name(), &sample)?);
+    }
+    Ok(reports)
+}
+
+/// Extra entropy gathered once at startup and mixed into every worker seed
+pub struct EntropyPool {
+    digest: Zeroizing<[u8; 32]>,
+    names: Vec<String>,
+}
+
+impl EntropyPool {
+    pub fn gather(sources: &[Source]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut hasher = Sha256::new();
+        for source in sources {
+            let bytes = source.read(SOURCE_BYTES)?;
+            hasher.update((bytes.len() as u64).to_be_bytes());
+            hasher.update(&bytes[..]);
+        }
+        Ok(Self {
+            digest: Zeroizing::new(hasher.finalize().into()),
+            names: sources.iter().map(Source::name).collect(),
+        })
+    }
+}
+
+impl std::fmt::Debug for EntropyPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntropyPool").field("sources", &self.names).finish_non_exhaustive()
+    }
+}
+
+/// Where worker candidate seeds come from. Workers only see the `WorkerRng`
+/// handed to each of them, so a new source needs no change to worker code.
+pub trait RngSource: Send + Sync + std::fmt::Debug {
+    /// The generator for one worker
+    fn worker_rng(&self, worker: usize) -> WorkerRng;
+
+    /// RNG backend for metadata and logs
+    fn describe(&self) -> String;
+}
+
+/// `--rng os` (the default): every candidate straight from the OS
+#[derive(Debug)]
+pub struct Os;
+
+impl RngSource for Os {
+    fn worker_rng(&self, _worker: usize) -> WorkerRng {
+        WorkerRng::Os
+    }
+
+    fn describe(&self) -> String {
+        "OsRng".to_string()
+    }
+}
+
+/// `--rng chacha`: a ChaCha20 stream per worker, seeded once from the OS,
+/// which saves a system call per candidate
+#[derive(Debug)]
+pub struct ChaCha;
+
+impl RngSource for ChaCha {
+    fn worker_rng(&self, worker: usize) -> WorkerRng {
+        let os_seed = os_seed();
+        chacha(&[b"ssh-keygen-deluxe/chacha-worker/v1", os_seed.as_ref(), &(worker as u64).to_be_bytes()])
+    }
+
+    fn describe(&self) -> String {
+        "ChaCha20 per worker (seeded from OsRng)".to_string()
+    }
+}
+
+/// `--rng seeded:SEED`, TEST ONLY: worker N's ChaCha20 stream follows from the
+/// seed and N alone, so a run (or one shard of it) can be replayed
+#[derive(Debug)]
+pub struct Seeded(pub u64);
+
+impl RngSource for Seeded {
+    fn worker_rng(&self, worker: usize) -> WorkerRng {
+        chacha(&[b"ssh-keygen-deluxe/seeded-test-rng/v1", &self.0.to_be_bytes(), &(worker as u64).to_be_bytes()])
+    }
+
+    fn describe(&self) -> String {
+        format!("seeded test RNG (seed {})", self.0)
+    }
+}
+
+/// `--rng partitioned`: candidates that provably never repeat within a run.
+/// One master seed is drawn from the OS at startup; each worker gets a stream
+/// number of its own (in the order workers start) and its Kth candidate seed is
+///
+/// ```text
+/// L || R = stream (8 bytes, big-endian) || K (8 bytes, big-endian) || 16 zero bytes
+/// 4 rounds of (L, R) <- (R, L xor F(round, R)), where
+///     F(round, x) = SHA-256("ssh-keygen-deluxe/partitioned-rng/v1" || master || round || x)[..16]
+/// seed = L || R
+/// ```
+///
+/// A Feistel network is invertible whatever its round function, so distinct
+/// (stream, K) pairs always give distinct seeds; with SHA-256 as the round
+/// function, four rounds make the seeds indistinguishable from random to anyone
+/// without the master seed (Luby-Rackoff). The master seed itself is never shown,
+/// as it would reveal every candidate's private key; `seed_id` names it instead.
+pub struct Partitioned {
+    master: Zeroizing<[u8; 32]>,
+    /// The next stream number to hand out
+    streams: AtomicU64,
+}
+
+impl Partitioned {
+    pub fn from_os() -> Self {
+        Self {
+            master: os_seed(),
+            streams: AtomicU64::new(0),
+        }
+    }
+
+    /// The first 8 bytes of SHA-256("ssh-keygen-deluxe/partitioned-seed-id/v1" || master), in hex
+    pub fn seed_id(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"ssh-keygen-deluxe/partitioned-seed-id/v1")
+            .chain_update(self.master.as_ref())
+            .finalize();
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl std::fmt::Debug for Partitioned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Partitioned").field("seed_id", &self.seed_id()).finish_non_exhaustive()
+    }
+}
+
+impl RngSource for Partitioned {
+    /// A fresh stream on every call, so even a restarted worker never replays one
+    fn worker_rng(&self, _worker: usize) -> WorkerRng {
+        let round_key = Sha256::new()
+            .chain_update(b"ssh-keygen-deluxe/partitioned-rng/v1")
+            .chain_update(self.master.as_ref());
+        WorkerRng::Partitioned(Box::new(Permutation {
+            round_key,
+            stream: self.streams.fetch_add(1, Ordering::Relaxed),
+            next: 0,
+        }))
+    }
+
+    fn describe(&self) -> String {
+        format!("partitioned (keyed permutation of worker stream and candidate number, master seed ID {})", self.seed_id())
+    }
+}
+
+/// One worker's stream of `Partitioned` seeds
+pub struct Permutation {
+    /// SHA-256 already fed the domain and the master seed
+    round_key: Sha256,
+    stream: u64,
+    next: u64,
+}
+
+impl Permutation {
+    const ROUNDS: u8 = 4;
+
+    fn seed(&mut self) -> Zeroizing<[u8; 32]> {
+        let mut block = Zeroizing::new([0u8; 32]);
+        block[..8].copy_from_slice(&self.stream.to_be_bytes());
+        block[8..16].copy_from_slice(&self.next.to_be_bytes());
+        // 2^64 candidates per worker is out of reach; wrapping would only repeat
+        self.next = self.next.wrapping_add(1);
+
+        let (mut left, mut right) = ([0u8; 16], [0u8; 16]);
+        left.copy_from_slice(&block[..16]);
+        right.copy_from_slice(&block[16..]);
+        for round in 0..Self::ROUNDS {
+            let mix = self.round_key.clone().chain_update([round]).chain_update(right).finalize();
+            let mut next_right = left;
+            next_right.iter_mut().zip(&mix[..16]).for_each(|(byte, mask)| *byte ^= mask);
+            left = right;
+            right = next_right;
+        }
+        block[..16].copy_from_slice(&left);
+        block[16..].copy_from_slice(&right);
+        left.zeroize();
+        right.zeroize();
+        block
+    }
+}
+
+/// `--entropy-source`: a ChaCha20 stream per worker seeded from
+/// SHA-256(OsRng || pool || worker id)
+impl RngSource for EntropyPool {
+    fn worker_rng(&self, worker: usize) -> WorkerRng {
+        let os_seed = os_seed();
+        chacha(&[
+            b"ssh-keygen-deluxe/worker-seed/v1",
+            os_seed.as_ref(),
+            self.digest.as_ref(),
+            &(worker as u64).to_be_bytes(),
+        ])
+    }
+
+    /// e.g. "ChaCha20(OsRng + /dev/hwrng)"
+    fn describe(&self) -> String {
+        format!("ChaCha20(OsRng + {})", self.names.join(" + "))
+    }
+}
+
+/// The `--rng` source named by `spec`
+pub fn parse_rng(spec: &str) -> Result<std::sync::Arc<dyn RngSource>, String> {
+    match spec.split_once(':') {
+        None if spec == "os" => Ok(std::sync::Arc::new(Os)),
+        None if spec == "chacha" => Ok(std::sync::Arc::new(ChaCha)),
+        None if spec == "partitioned" => Ok(std::sync::Arc::new(Partitioned::from_os())),
+        Some(("seeded", seed)) => match seed.parse() {
+            Ok(seed) => Ok(std::sync::Arc::new(Seeded(seed))),
+            Err(_) => Err(format!("--rng seeded needs a whole-number seed, not \"{}\"", seed)),
+        },
+        _ => Err(format!("unknown RNG \"{}\"; expected one of {}", spec, RNGS.join(", "))),
+    }
+}
+
+fn os_seed() -> Zeroizing<[u8; 32]> {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(seed.as_mut());
+    seed
+}
+
+/// A ChaCha20 generator keyed with the SHA-256 of `parts`
+fn chacha(parts: &[&[u8]]) -> WorkerRng {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let seed = Zeroizing::new(<[u8; 32]>::from(hasher.finalize()));
+    WorkerRng::ChaCha(Box::new(ChaCha20Rng::from_seed(*seed)))
+}
+
+/// One worker's seed generator: OsRng itself, a ChaCha20 stream, or a
+/// `Partitioned` stream handing out one 32-byte seed per candidate
+pub enum WorkerRng {
+    Os,
+    ChaCha(Box<ChaCha20Rng>),
+    Partitioned(Box<Permutation>),
+}
+
+impl WorkerRng {
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            WorkerRng::Os => OsRng.fill_bytes(dest),
+            WorkerRng::ChaCha(rng) => rng.fill_bytes(dest),
+            WorkerRng::Partitioned(stream) => {
+                for chunk in dest.chunks_mut(32) {
+                    chunk.copy_from_slice(&stream.seed()[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
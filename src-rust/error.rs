@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+/// Exit codes; documented in the README so wrappers can script around them
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_NOT_FOUND: i32 = 2;
+pub const EXIT_INTERRUPTED: i32 = 3;
+pub const EXIT_INVALID_CONFIG: i32 = 4;
+pub const EXIT_IO: i32 = 5;
+
+/// Everything that can end a run early, grouped by what a caller can do about it
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// `--timeout` expired before any key matched
+    #[error("no match found within {}s ({} attempts)", .elapsed.as_secs(), crate::output::count(*.attempts))]
+    Timeout { elapsed: Duration, attempts: u64 },
+
+    /// `verify --target`: the key does not contain the target
+    #[error("{0}")]
+    TargetNotFound(String),
+
+    /// Ctrl-C before any key matched
+    #[error("search interrupted by user")]
+    Interrupted,
+
+    /// Bad flags or inputs, caught before the search starts
+    #[error("{0}")]
+    Config(String),
+
+    /// Writing files or talking to the agent, an API, Vault or a password manager
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        source: Box<dyn std::error::Error>,
+    },
+
+    /// RNG health test or key self-check failure; never ship the key
+    #[error("{0}")]
+    Integrity(String),
+
+    /// `check-rotation`: keys past their recorded expiry
+    #[error("{0}")]
+    RotationDue(String),
+}
+
+impl Error {
+    pub fn config(message: impl std::fmt::Display) -> Self {
+        Error::Config(message.to_string())
+    }
+
+    pub fn io(context: impl Into<String>, source: impl Into<Box<dyn std::error::Error>>) -> Self {
+        Error::Io {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Timeout { .. } | Error::TargetNotFound(_) => EXIT_NOT_FOUND,
+            Error::Interrupted => EXIT_INTERRUPTED,
+            Error::Config(_) => EXIT_INVALID_CONFIG,
+            Error::Io { .. } => EXIT_IO,
+            Error::Integrity(_) | Error::RotationDue(_) => EXIT_FAILURE,
+        }
+    }
+}
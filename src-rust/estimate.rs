@@ -0,0 +1,106 @@
+//! `ssh-keygen estimate`: what a target costs before committing to it. The
+//! odds come from the same difficulty model the search prints, the rate from a
+//! short `bench`-style measurement on this machine (or `--rate`), and
+//! `--table` lays out every length from 1 to 10 so a realistic one can be picked.
+
+use crate::error::Error;
+use crate::matching::{self, approximate, Position};
+use std::time::Duration;
+
+/// Letters that appear nowhere in the fixed key prefix in either case, and
+/// whose first is possible right after it, so each row of the table is a
+/// target of that length any key could have anywhere
+const SAMPLE: &str = "KMPQRVWXYO";
+
+/// Where the keys-per-second figure comes from
+pub enum Rate {
+    Given(f64),
+    /// Run the search for `duration` with this many workers
+    Measure { workers: usize, duration: Duration },
+}
+
+/// Options for `ssh-keygen estimate`
+pub struct EstimateOptions<'a> {
+    pub target: Option<&'a str>,
+    pub case_sensitive: bool,
+    pub position: Position,
+    pub table: bool,
+    pub rate: Rate,
+}
+
+/// Print the expected attempts and time for the target and, with `table`, for
+/// every length from 1 to 10. `measure` runs that many workers for the given
+/// time and returns keys per second.
+pub fn run(options: &EstimateOptions, measure: impl Fn(usize, Duration) -> Result<f64, Error>) -> Result<(), Error> {
+    // A target that can never match fails before the measurement
+    let target_odds = match options.target {
+        Some(target) => Some(matching::match_odds(target, options.case_sensitive, options.position).map_err(Error::config)?),
+        None => None,
+    };
+    let rate = match options.rate {
+        Rate::Given(rate) => rate,
+        Rate::Measure { workers, duration } => {
+            println!("Measuring {} workers for {}s...", workers, duration.as_secs_f64());
+            measure(workers, duration)?
+        }
+    };
+    let mode = format!(
+        "{}, {}",
+        match options.position {
+            Position::Anywhere => "anywhere",
+            Position::AtStart => "--at-start",
+            Position::AtEnd => "--suffix",
+        },
+        if options.case_sensitive { "case-sensitive" } else { "case-insensitive" }
+    );
+    println!("Rate: {:.0} keys/s", rate);
+
+    if let (Some(target), Some(odds)) = (options.target, target_odds) {
+        println!("\"{}\" ({}): about 1 in {} keys", target, mode, approximate(odds));
+        println!(
+            "  expected {}; 50% of searches within {}, 90% within {}, 99% within {}",
+            duration(odds / rate),
+            duration(attempts_for(0.5, odds) / rate),
+            duration(attempts_for(0.9, odds) / rate),
+            duration(attempts_for(0.99, odds) / rate)
+        );
+    }
+    if options.table {
+        println!("Targets of each length ({}; letters, e.g. {}):", mode, &SAMPLE[..4]);
+        println!("{:>6}  {:>12}  {:>12}  {:>12}", "Length", "1 in N keys", "Expected", "90% within");
+        for length in 1..=SAMPLE.len() {
+            let sample = &SAMPLE[..length];
+            match matching::match_odds(sample, options.case_sensitive, options.position) {
+                Ok(odds) => println!(
+                    "{:>6}  {:>12}  {:>12}  {:>12}",
+                    length,
+                    approximate(odds),
+                    duration(odds / rate),
+                    duration(attempts_for(0.9, odds) / rate)
+                ),
+                Err(e) => println!("{:>6}  {}", length, e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Attempts within which a share `chance` of searches find a match
+fn attempts_for(chance: f64, odds: f64) -> f64 {
+    (-chance).ln_1p() / (-1.0 / odds).ln_1p()
+}
+
+/// e.g. "42s", "17m05s", "3h12m", "4.2 days", "6.1 years", "31415 years"
+pub fn duration(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    match whole {
+        0..=59 => format!("{}s", whole),
+        60..=3599 => format!("{}m{:02}s", whole / 60, whole % 60),
+        3600..=172_799 => format!("{}h{:02}m", whole / 3600, whole % 3600 / 60),
+        172_800..=63_071_999 => format!("{:.1} days", seconds / 86_400.0),
+        _ => match seconds / 31_557_600.0 {
+            years if years < 1000.0 => format!("{:.1} years", years),
+            years => format!("{} years", approximate(years)),
+        },
+    }
+}
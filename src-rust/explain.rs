@@ -0,0 +1,212 @@
+//! `ssh-keygen explain TARGET`: where in an Ed25519 public key line a target
+//! can appear, shown on an example key. A key line is a fixed 37-character
+//! prefix, then one character that is always A-P, then 42 free characters.
+//! Anchored targets have one place to be; unanchored ones may also overlap the
+//! prefix. Each character's odds come from the same model as `estimate`, and
+//! a character that can never appear is listed with the reason. A `[...]`
+//! class counts as one character that may be any of its members.
+
+use crate::error::Error;
+use crate::keygen::{Ed25519, KeyGenerator};
+use crate::matching::{self, Position, BASE64_CHARS, ED25519_PREFIX, FIRST_VARIABLE_CHARS, LINE_LEN, VARIABLE_CHARS};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// Options for `ssh-keygen explain`
+pub struct ExplainOptions<'a> {
+    pub target: &'a str,
+    pub case_sensitive: bool,
+    pub position: Position,
+}
+
+/// Print the key layout, an example key with the target in place, where it can
+/// match and what each character costs there
+pub fn run(options: &ExplainOptions) -> Result<(), Error> {
+    let target = matching::target_classes(options.target, options.case_sensitive).map_err(Error::config)?;
+    if target.is_empty() {
+        return Err(Error::config("target cannot be empty"));
+    }
+    let prefix = ED25519_PREFIX.len();
+    println!(
+        "\"{}\" ({}, {})",
+        options.target,
+        matching::position_name(options.position),
+        if options.case_sensitive { "case-sensitive" } else { "case-insensitive" }
+    );
+    println!();
+    println!("An Ed25519 public key line has {} characters:", LINE_LEN);
+    println!("  {:<7}  {:<38}  the key type, then the base64 of the key's type name and length", format!("1-{}", prefix), ED25519_PREFIX);
+    println!("  {:<7}  {:<38}  two zero bits of the length byte (0x20), then the top four bits of the key", prefix + 1, "A-P only");
+    println!(
+        "  {:<7}  {:<38}  the rest of the key; 51 bytes need no padding, so no '=' and any last character",
+        format!("{}-{}", prefix + 2, LINE_LEN),
+        "any of the 64"
+    );
+
+    // Zero-based offsets of the line where the target could start
+    let starts: Vec<usize> = match options.position {
+        Position::AtStart => vec![prefix],
+        Position::AtEnd => vec![LINE_LEN.saturating_sub(target.len())],
+        Position::Anywhere => (0..=LINE_LEN.saturating_sub(target.len())).collect(),
+    };
+    let chance = |start: usize| -> f64 {
+        target
+            .iter()
+            .enumerate()
+            .map(|(offset, class)| matching::class_chance(start + offset, class, options.case_sensitive))
+            .product()
+    };
+    let fits = target.len() <= room(options.position);
+    let possible: Vec<usize> = starts.iter().copied().filter(|start| fits && chance(*start) > 0.0).collect();
+    // Shown at the first place clear of the prefix and its A-P character, where
+    // there is one, and there too when the target fits nowhere
+    let clear = (prefix + 1).min(LINE_LEN.saturating_sub(target.len()));
+    let shown = possible
+        .iter()
+        .copied()
+        .find(|start| *start > prefix)
+        .or(possible.first().copied())
+        .unwrap_or(if options.position == Position::Anywhere { clear } else { starts[0] });
+
+    println!();
+    if possible.is_empty() {
+        println!("Example key, with where the target would have to be marked:");
+    } else {
+        println!("Example key with the target in place:");
+    }
+    let mut line = example_line();
+    if !possible.is_empty() {
+        for (offset, class) in target.iter().enumerate() {
+            line[shown + offset] = written(shown + offset, class, options.case_sensitive);
+        }
+    }
+    println!("  {}", String::from_utf8_lossy(&line));
+    let marks: String = (0..LINE_LEN)
+        .map(|index| match index {
+            _ if (shown..shown + target.len()).contains(&index) => '^',
+            _ if index < prefix => '-',
+            _ if index == prefix => '!',
+            _ => ' ',
+        })
+        .collect();
+    println!("  {}", marks.trim_end());
+    println!("  - fixed   ! A-P only   ^ target");
+
+    println!();
+    if !fits {
+        println!("Where it can match: nowhere; it is longer than the {} characters it would have to fit in", room(options.position));
+    } else {
+        println!("Where it can match:");
+        for line in places(&possible, target.len(), &chance) {
+            println!("  {}", line);
+        }
+        if possible.is_empty() {
+            println!("  nowhere");
+        }
+    }
+
+    println!();
+    println!("Per character, at columns {}-{}:", shown + 1, shown + target.len());
+    let labels: Vec<String> = target.iter().map(|class| matching::class_label(class)).collect();
+    let width = labels.iter().map(String::len).max().unwrap_or(1);
+    for (offset, (class, label)) in target.iter().zip(&labels).enumerate() {
+        let index = shown + offset;
+        let chance = matching::class_chance(index, class, options.case_sensitive);
+        let cost = if index >= LINE_LEN {
+            "past the end of the key".to_string()
+        } else if chance > 0.0 {
+            format!("1 in {:.0}", 1.0 / chance)
+        } else {
+            format!("never: {}", why_not(index, class, options.case_sensitive))
+        };
+        println!("  {:<width$}  column {:<3} {}", label, index + 1, cost);
+    }
+
+    println!();
+    match matching::match_odds(options.target, options.case_sensitive, options.position) {
+        Ok(odds) => println!("About 1 in {} keys match", matching::approximate(odds)),
+        Err(e) => println!("It can never match: {}", e),
+    }
+    Ok(())
+}
+
+/// A real public key line, so the example looks like what the search reads
+fn example_line() -> Vec<u8> {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(seed.as_mut());
+    Ed25519.public_text(&seed).expect("every seed gives an Ed25519 key").into_bytes()
+}
+
+/// A member of `class` as it can appear at `index`: a case-insensitive target
+/// takes whichever case fits
+fn written(index: usize, class: &[u8], case_sensitive: bool) -> u8 {
+    let fits = |c: &&u8| matching::char_chance(index, **c, case_sensitive) > 0.0;
+    let c = *class.iter().find(fits).unwrap_or(&class[0]);
+    if case_sensitive || matching::char_chance(index, c, true) > 0.0 {
+        return c;
+    }
+    if c.is_ascii_lowercase() {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    }
+}
+
+/// The characters an anchored target has to fit in
+fn room(position: Position) -> usize {
+    match position {
+        Position::Anywhere => LINE_LEN,
+        Position::AtStart | Position::AtEnd => VARIABLE_CHARS,
+    }
+}
+
+/// The possible starting columns, as runs within the prefix, across it and after it
+fn places(possible: &[usize], len: usize, chance: &dyn Fn(usize) -> f64) -> Vec<String> {
+    let prefix = ED25519_PREFIX.len();
+    let kind = |start: usize| {
+        if chance(start) >= 1.0 {
+            "inside the fixed prefix, so every key matches"
+        } else if start <= prefix {
+            "overlapping the fixed prefix or its A-P character"
+        } else {
+            "in the free characters"
+        }
+    };
+    let mut runs: Vec<(usize, usize, &str)> = Vec::new();
+    for &start in possible {
+        match runs.last_mut() {
+            Some((_, last, last_kind)) if *last + 1 == start && *last_kind == kind(start) => *last = start,
+            _ => runs.push((start, start, kind(start))),
+        }
+    }
+    runs.iter()
+        .map(|(first, last, kind)| {
+            let columns = |start: usize| format!("{}-{}", start + 1, start + len);
+            if first == last {
+                format!("columns {}: {}", columns(*first), kind)
+            } else {
+                format!("{} places, columns {} to {}: {}", last - first + 1, columns(*first), columns(*last), kind)
+            }
+        })
+        .collect()
+}
+
+/// Why no member of `class` appears at `index` of a key line
+fn why_not(index: usize, class: &[u8], case_sensitive: bool) -> String {
+    let base64 = |c: &u8| BASE64_CHARS.contains(c) || (!case_sensitive && BASE64_CHARS.contains(&c.to_ascii_uppercase()));
+    if !class.iter().any(base64) {
+        return match class {
+            [c] => format!("'{}' is not a base64 character; keys use only A-Z, a-z, 0-9, + and /", *c as char),
+            _ => format!("none of {} is a base64 character; keys use only A-Z, a-z, 0-9, + and /", matching::class_label(class)),
+        };
+    }
+    if let Some(fixed) = ED25519_PREFIX.as_bytes().get(index) {
+        return format!("this column is always '{}' in the fixed prefix", *fixed as char);
+    }
+    let hint = match class.iter().find(|c| case_sensitive && FIRST_VARIABLE_CHARS.contains(&c.to_ascii_uppercase())) {
+        Some(c) => format!(" (with -i, '{}' also matches '{}')", *c as char, c.to_ascii_uppercase() as char),
+        None => String::new(),
+    };
+    format!("this column is always A-P{}", hint)
+}
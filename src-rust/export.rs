@@ -0,0 +1,112 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use zeroize::Zeroizing;
+
+/// Password managers accepted by `--export`
+pub const MANAGERS: [&str; 2] = ["1password", "bitwarden"];
+
+/// Key material and metadata handed to a password manager
+pub struct ExportItem<'a> {
+    pub title: &'a str,
+    pub private_key_pem: &'a str,
+    pub public_key: &'a str,
+    pub fingerprint: &'a str,
+    pub target: &'a str,
+}
+
+/// Create an SSH key item in the given password manager. Secrets are only
+/// ever passed on stdin so they never show up in process listings.
+pub fn export(
+    manager: &str,
+    item: &ExportItem,
+    vault: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let notes = format!(
+        "Vanity target: {}\nFingerprint: {}\nGenerated by ssh-keygen {}",
+        item.target,
+        item.fingerprint,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    match manager {
+        "1password" => {
+            let mut template = serde_json::json!({
+                "title": item.title,
+                "category": "SSH_KEY",
+                "fields": [
+                    {
+                        "id": "private_key",
+                        "type": "SSHKEY",
+                        "label": "private key",
+                        "value": item.private_key_pem,
+                    },
+                    {
+                        "id": "notesPlain",
+                        "type": "STRING",
+                        "purpose": "NOTES",
+                        "label": "notesPlain",
+                        "value": notes,
+                    },
+                ],
+            });
+            let mut args = vec!["item", "create", "--format", "json"];
+            if let Some(vault) = vault {
+                args.extend(["--vault", vault]);
+            }
+            args.push("-");
+            let payload = Zeroizing::new(template.to_string());
+            crate::secret::wipe_json(&mut template);
+            run_with_stdin("op", &args, payload.as_bytes())
+        }
+        "bitwarden" => {
+            // Item type 5 is "SSH key"; `bw create item` expects base64-encoded JSON
+            let mut template = serde_json::json!({
+                "type": 5,
+                "name": item.title,
+                "notes": notes,
+                "sshKey": {
+                    "privateKey": item.private_key_pem,
+                    "publicKey": item.public_key.trim(),
+                    "keyFingerprint": item.fingerprint,
+                },
+            });
+            if let Some(vault) = vault {
+                template["organizationId"] = serde_json::Value::String(vault.to_string());
+            }
+            let encoded = Zeroizing::new(BASE64.encode(Zeroizing::new(template.to_string()).as_bytes()));
+            crate::secret::wipe_json(&mut template);
+            run_with_stdin("bw", &["create", "item"], encoded.as_bytes())
+        }
+        other => Err(format!("unsupported password manager: {}", other).into()),
+    }
+}
+
+/// Run a CLI with the payload on stdin, surfacing its stderr on failure
+fn run_with_stdin(
+    program: &str,
+    args: &[&str],
+    payload: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| format!("failed to open {} stdin", program))?
+        .write_all(payload)?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} exited with {}: {}", program, output.status, stderr.trim()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,228 @@
+//! `--expr`: a small boolean language over the basic matchers, e.g.
+//! `prefix("yeg") && (contains("42") || fp_contains("dead")) && !contains("xxx")`
+//!
+//! ```text
+//! expr    := and ("||" and)*
+//! and     := unary ("&&" unary)*
+//! unary   := "!" unary | "(" expr ")" | NAME "(" STRING ")"
+//! ```
+
+use crate::fingerprint::FingerprintFormat;
+use crate::matching::{self, Matcher, Position};
+use std::cell::OnceCell;
+
+/// Primitive tests, as named in expressions
+pub const FUNCTIONS: [&str; 4] = ["contains", "prefix", "suffix", "fp_contains"];
+
+/// A parsed expression, evaluated against every candidate key
+#[derive(Debug)]
+pub enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Test(Box<Test>),
+}
+
+#[derive(Debug)]
+pub struct Test {
+    function: &'static str,
+    argument: String,
+    case_sensitive: bool,
+    fingerprint_format: FingerprintFormat,
+    matcher: Matcher,
+}
+
+impl Expr {
+    /// Parse `text`; `case_sensitive` applies to every test in it, and
+    /// `fingerprint_format` to every `fp_contains`
+    pub fn parse(text: &str, case_sensitive: bool, fingerprint_format: FingerprintFormat) -> Result<Self, String> {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            case_sensitive,
+            fingerprint_format,
+        };
+        let expr = parser.or()?;
+        parser.skip_space();
+        if parser.pos < text.len() {
+            return Err(parser.error("expected && or ||"));
+        }
+        Ok(expr)
+    }
+
+    /// Whether a `<type> <base64>` public key line satisfies the expression
+    pub fn is_match(&self, public_key_line: &str) -> bool {
+        self.eval(public_key_line, &OnceCell::new())
+    }
+
+    /// The fingerprint is only computed if a `fp_contains` test is reached
+    fn eval(&self, line: &str, fingerprint: &OnceCell<String>) -> bool {
+        match self {
+            Expr::Or(terms) => terms.iter().any(|term| term.eval(line, fingerprint)),
+            Expr::And(terms) => terms.iter().all(|term| term.eval(line, fingerprint)),
+            Expr::Not(inner) => !inner.eval(line, fingerprint),
+            Expr::Test(test) if test.function == "fp_contains" => test
+                .matcher
+                .is_match(fingerprint.get_or_init(|| test.fingerprint_format.render(line)).as_bytes()),
+            Expr::Test(test) => test.matcher.is_match(line.as_bytes()),
+        }
+    }
+
+    /// Check anchored tests against what keys can contain there. A test that can
+    /// never pass is an error where the key needs it, and a warning under `!`.
+    pub fn check(&self) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        self.check_polarity(false, &mut warnings)?;
+        Ok(warnings)
+    }
+
+    fn check_polarity(&self, negated: bool, warnings: &mut Vec<String>) -> Result<(), String> {
+        match self {
+            Expr::Or(terms) | Expr::And(terms) => {
+                terms.iter().try_for_each(|term| term.check_polarity(negated, warnings))
+            }
+            Expr::Not(inner) => inner.check_polarity(!negated, warnings),
+            Expr::Test(test) => {
+                let position = match test.function {
+                    "prefix" => Position::AtStart,
+                    "suffix" => Position::AtEnd,
+                    _ => return Ok(()),
+                };
+                match matching::anchored_odds(&test.argument, test.case_sensitive, position) {
+                    Ok(_) => Ok(()),
+                    Err(e) if negated => {
+                        warnings.push(format!("!{}(\"{}\") is always true: {}", test.function, test.argument, e));
+                        Ok(())
+                    }
+                    Err(e) => Err(format!("{}(\"{}\"): {}", test.function, test.argument, e)),
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    case_sensitive: bool,
+    fingerprint_format: FingerprintFormat,
+}
+
+impl Parser<'_> {
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.and()?];
+        while self.eat("||") {
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.pop().unwrap() } else { Expr::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.unary()?];
+        while self.eat("&&") {
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.pop().unwrap() } else { Expr::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.or()?;
+            if !self.eat(")") {
+                return Err(self.error("expected )"));
+            }
+            return Ok(inner);
+        }
+
+        self.skip_space();
+        let start = self.pos;
+        let name_len = self.rest().find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(self.rest().len());
+        let name = &self.text[start..start + name_len];
+        let Some(function) = FUNCTIONS.iter().find(|function| **function == name) else {
+            return Err(self.error(&format!("expected one of {}, ( or !", FUNCTIONS.join(", "))));
+        };
+        self.pos += name_len;
+        if !self.eat("(") {
+            return Err(self.error("expected ("));
+        }
+        let argument = self.string()?;
+        if argument.is_empty() {
+            return Err(self.error("empty string"));
+        }
+        if *function == "fp_contains" {
+            if let Err(e) = self.fingerprint_format.check(&argument, self.case_sensitive) {
+                return Err(format!("fp_contains(\"{}\"): {}", argument, e));
+            }
+        }
+        matching::target_classes(&argument, self.case_sensitive)?;
+        if !self.eat(")") {
+            return Err(self.error("expected )"));
+        }
+
+        let position = match *function {
+            "prefix" => Position::AtStart,
+            "suffix" => Position::AtEnd,
+            _ => Position::Anywhere,
+        };
+        Ok(Expr::Test(Box::new(Test {
+            function,
+            matcher: Matcher::at(&argument, self.case_sensitive, position),
+            argument,
+            case_sensitive: self.case_sensitive,
+            fingerprint_format: self.fingerprint_format,
+        })))
+    }
+
+    /// A double-quoted string; `\"` and `\\` are the only escapes
+    fn string(&mut self) -> Result<String, String> {
+        if !self.eat("\"") {
+            return Err(self.error("expected a \"quoted\" string"));
+        }
+        let mut value = String::new();
+        let text = self.text;
+        let mut chars = text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                    _ => {
+                        self.pos += offset;
+                        return Err(self.error("only \\\" and \\\\ can be escaped"));
+                    }
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_space(&mut self) {
+        self.pos = self.text.len() - self.rest().trim_start().len();
+    }
+
+    /// Consume `token` (after any whitespace) if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at column {}", message, self.text[..self.pos].chars().count() + 1)
+    }
+}
@@ -0,0 +1,235 @@
+use crate::error::Error;
+use crate::formats;
+use crate::passphrase::{self, PassphraseSource};
+use base64::engine::general_purpose::STANDARD_NO_PAD as BASE64_NO_PAD;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use sha2::Sha256;
+use ssh_key::public::KeyData;
+use ssh_key::{HashAlg, PublicKey};
+use zeroize::Zeroizing;
+
+/// Which rendering of a key's fingerprint `fp_contains` searches. Platforms differ in
+/// what they show: OpenSSH prints base64 SHA256, some web UIs hex, and older tools
+/// the bubble-babble form of SHA-1 (`ssh-keygen -B`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FingerprintFormat {
+    Sha256,
+    Sha256Hex,
+    BubbleBabble,
+}
+
+impl FingerprintFormat {
+    pub const NAMES: [&'static str; 3] = ["sha256", "hex", "bubblebabble"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(FingerprintFormat::Sha256),
+            "hex" => Some(FingerprintFormat::Sha256Hex),
+            "bubblebabble" => Some(FingerprintFormat::BubbleBabble),
+            _ => None,
+        }
+    }
+
+    /// The fingerprint body of a `<type> <base64>` public key line, without any
+    /// `SHA256:` label
+    pub fn render(self, public_key_line: &str) -> String {
+        let blob = public_key_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|body| BASE64.decode(body).ok())
+            .unwrap_or_default();
+        match self {
+            FingerprintFormat::Sha256 => BASE64_NO_PAD.encode(Sha256::digest(&blob)),
+            FingerprintFormat::Sha256Hex => Sha256::digest(&blob).iter().map(|b| format!("{:02x}", b)).collect(),
+            FingerprintFormat::BubbleBabble => bubble_babble(&Sha1::digest(&blob)),
+        }
+    }
+
+    /// Reject text that can never appear in this rendering
+    pub fn check(self, text: &str, case_sensitive: bool) -> Result<(), String> {
+        let (name, length, allowed): (_, usize, fn(char) -> bool) = match self {
+            FingerprintFormat::Sha256 => ("base64 SHA256", 43, |c| c.is_ascii_alphanumeric() || c == '+' || c == '/'),
+            FingerprintFormat::Sha256Hex => ("hex SHA256", 64, |c| c.is_ascii_digit() || ('a'..='f').contains(&c)),
+            FingerprintFormat::BubbleBabble => ("bubble-babble", 59, |c| BUBBLE_VOWELS.contains(c) || BUBBLE_CONSONANTS.contains(c) || c == '-'),
+        };
+        for c in text.chars() {
+            // Hex and bubble-babble are lowercase only; case-insensitive matching folds
+            let folded = if case_sensitive { c } else { c.to_ascii_lowercase() };
+            if !allowed(folded) {
+                return Err(format!("'{}' never appears in {} fingerprints", c, name));
+            }
+        }
+        if text.len() > length {
+            return Err(format!("{} fingerprints are only {} characters long", name, length));
+        }
+        Ok(())
+    }
+}
+
+const BUBBLE_VOWELS: &str = "aeiouy";
+const BUBBLE_CONSONANTS: &str = "bcdfghklmnprstvzx";
+
+/// Bubble-babble encoding as OpenSSH implements it: `xesef-...-xux`
+fn bubble_babble(data: &[u8]) -> String {
+    let vowel = |i: usize| BUBBLE_VOWELS.as_bytes()[i] as char;
+    let consonant = |i: usize| BUBBLE_CONSONANTS.as_bytes()[i] as char;
+    let rounds = data.len() / 2 + 1;
+    let mut seed = 1usize;
+    let mut out = String::from("x");
+    for i in 0..rounds {
+        if i + 1 < rounds || !data.len().is_multiple_of(2) {
+            let byte = data[2 * i] as usize;
+            out.push(vowel((((byte >> 6) & 3) + seed) % 6));
+            out.push(consonant((byte >> 2) & 15));
+            out.push(vowel(((byte & 3) + seed / 6) % 6));
+            if i + 1 < rounds {
+                let next = data[2 * i + 1] as usize;
+                out.push(consonant((next >> 4) & 15));
+                out.push('-');
+                out.push(consonant(next & 15));
+                seed = (seed * 5 + byte * 7 + next) % 36;
+            }
+        } else {
+            out.push(vowel(seed % 6));
+            out.push(consonant(16));
+            out.push(vowel(seed / 6));
+        }
+    }
+    out.push('x');
+    out
+}
+
+/// Options for `ssh-keygen fingerprint`
+pub struct FingerprintOptions<'a> {
+    /// Public key, authorized_keys-style line, or private key in any supported format
+    pub file: &'a str,
+    pub json: bool,
+    /// Only needed for encrypted PKCS#8, which does not store the public key in the clear
+    pub passphrase: PassphraseSource,
+}
+
+/// Print every common fingerprint representation of a key
+pub fn run(options: &FingerprintOptions) -> Result<(), Error> {
+    let public = load_public_key(options.file, &options.passphrase)?;
+
+    let sha256 = public.fingerprint(HashAlg::Sha256);
+    let sha256_hex: String = sha256.as_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    let blob = public.to_bytes().map_err(|e| Error::io("encoding public key", e))?;
+    let bubble_babble = bubble_babble(&Sha1::digest(&blob));
+    let md5 = md5(&public)?;
+    let randomart = randomart(&public);
+    let bits = key_bits(public.key_data());
+
+    if options.json {
+        let document = serde_json::json!({
+            "file": options.file,
+            "key_type": public.algorithm().as_str(),
+            "bits": bits,
+            "comment": public.comment(),
+            "sha256": sha256.to_string(),
+            "sha256_hex": sha256_hex,
+            "md5": md5,
+            "sha1_bubblebabble": bubble_babble,
+            "randomart": randomart,
+        });
+        let text = serde_json::to_string_pretty(&document).map_err(|e| Error::io("encoding JSON", e))?;
+        println!("{}", text);
+    } else {
+        println!(
+            "{} {} {} ({})",
+            bits.map_or_else(|| "?".to_string(), |bits| bits.to_string()),
+            sha256,
+            if public.comment().is_empty() { "no comment" } else { public.comment() },
+            type_label(public.key_data())
+        );
+        println!("{}", md5);
+        println!("SHA256 (hex): {}", sha256_hex);
+        println!("SHA1 (bubble-babble): {}", bubble_babble);
+        println!("{}", randomart);
+    }
+    Ok(())
+}
+
+/// Legacy `MD5:aa:bb:...` fingerprint of the public key blob
+pub fn md5(public: &PublicKey) -> Result<String, Error> {
+    let blob = public
+        .to_bytes()
+        .map_err(|e| Error::io("encoding public key", e))?;
+    let digest = Md5::digest(&blob);
+    let hex: Vec<String> = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("MD5:{}", hex.join(":")))
+}
+
+/// The SHA256 randomart `ssh-keygen -lv` draws
+pub fn randomart(public: &PublicKey) -> String {
+    public.fingerprint(HashAlg::Sha256).to_randomart(&randomart_header(public.key_data()))
+}
+
+/// Read the public key from a `.pub`/authorized_keys line or a private key file
+fn load_public_key(path: &str, source: &PassphraseSource) -> Result<PublicKey, Error> {
+    let text = Zeroizing::new(
+        std::fs::read_to_string(path).map_err(|e| Error::config(format!("{}: cannot read: {}", path, e)))?,
+    );
+
+    if formats::KeyFormat::detect(&text).is_none() {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or_else(|| Error::config(format!("{}: no key found", path)))?;
+        return parse_public_line(line).map_err(|e| Error::config(format!("{}: {}", path, e)));
+    }
+
+    match formats::public_key(&text).map_err(|e| Error::config(format!("{}: {}", path, e)))? {
+        Some(public) => Ok(public),
+        None => {
+            let passphrase = passphrase::resolve_existing(source, path).map_err(Error::config)?;
+            let decoded = formats::decode(&text, Some(passphrase.expose()))
+                .map_err(|e| Error::config(format!("{}: {}", path, e)))?;
+            formats::openssh_private_key(&decoded.signing_key, &decoded.comment)
+                .map(|private| private.public_key().clone())
+                .map_err(|e| Error::io("encoding public key", e))
+        }
+    }
+}
+
+/// Accept plain public key lines and authorized_keys lines with an options prefix
+fn parse_public_line(line: &str) -> Result<PublicKey, ssh_key::Error> {
+    PublicKey::from_openssh(line).or_else(|e| {
+        let start = crate::install::key_identity(line)
+            .and_then(|identity| line.find(&identity))
+            .ok_or(e)?;
+        PublicKey::from_openssh(&line[start..])
+    })
+}
+
+fn key_bits(key: &KeyData) -> Option<usize> {
+    match key {
+        KeyData::Ed25519(_) | KeyData::SkEd25519(_) => Some(256),
+        KeyData::Rsa(rsa) => rsa.n.as_positive_bytes().map(|n| {
+            n.len() * 8 - n.first().map_or(0, |b| b.leading_zeros() as usize)
+        }),
+        _ => None,
+    }
+}
+
+/// Key type as OpenSSH shows it in fingerprints and randomart
+fn type_label(key: &KeyData) -> String {
+    match key {
+        KeyData::Ed25519(_) => "ED25519".to_string(),
+        KeyData::SkEd25519(_) => "ED25519-SK".to_string(),
+        KeyData::Rsa(_) => "RSA".to_string(),
+        KeyData::Dsa(_) => "DSA".to_string(),
+        other => other.algorithm().as_str().to_uppercase(),
+    }
+}
+
+fn randomart_header(key: &KeyData) -> String {
+    match key_bits(key) {
+        Some(bits) => format!("[{} {}]", type_label(key), bits),
+        None => format!("[{}]", type_label(key)),
+    }
+}
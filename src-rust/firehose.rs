@@ -0,0 +1,94 @@
+//! `--emit-candidates`: stream every generated key to another program instead of
+//! matching in-process, one `ssh-ed25519 ...` line per candidate.
+
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Printed before candidates are emitted with their seeds
+pub const SEED_WARNING: &str = "\
+WARNING: --emit-seeds writes the private seed of every candidate. Whatever reads
+         the stream holds the private keys; never send it over a network or to disk.";
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Where candidate lines go. Workers write whole batches under the lock, so lines
+/// from different workers never interleave.
+pub struct Firehose {
+    sink: Mutex<Box<dyn Write + Send>>,
+    with_seeds: bool,
+    /// First write error; the reader going away ends the run
+    error: Mutex<Option<io::Error>>,
+}
+
+impl Firehose {
+    /// `-` for stdout, otherwise the path of a listening Unix socket
+    pub fn open(destination: &str, with_seeds: bool) -> Result<Self, String> {
+        let sink: Box<dyn Write + Send> = if destination == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(connect(destination)?)
+        };
+        Ok(Self {
+            sink: Mutex::new(sink),
+            with_seeds,
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Append one candidate line to a worker's batch: `[SEED_HEX ]PUBLIC_KEY`
+    pub fn push(&self, batch: &mut Vec<u8>, seed: &[u8; 32], public_key_line: &str) {
+        if self.with_seeds {
+            for byte in seed.iter() {
+                batch.push(HEX[(byte >> 4) as usize]);
+                batch.push(HEX[(byte & 0x0f) as usize]);
+            }
+            batch.push(b' ');
+        }
+        batch.extend_from_slice(public_key_line.as_bytes());
+        batch.push(b'\n');
+    }
+
+    /// Write a batch of lines. Returns false once the sink has failed, which
+    /// means the search should stop.
+    pub fn write(&self, batch: &[u8]) -> bool {
+        let mut sink = self.sink.lock().unwrap();
+        if self.error.lock().unwrap().is_some() {
+            return false;
+        }
+        match sink.write_all(batch).and_then(|()| sink.flush()) {
+            Ok(()) => true,
+            Err(e) => {
+                *self.error.lock().unwrap() = Some(e);
+                false
+            }
+        }
+    }
+
+    /// How the stream ended: Ok(true) if the reader went away, an error if
+    /// writing failed for any other reason
+    pub fn finish(&self) -> Result<bool, io::Error> {
+        match self.error.lock().unwrap().take() {
+            None => Ok(false),
+            Some(e) if matches!(e.kind(), io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset) => Ok(true),
+            Some(e) => Err(e),
+        }
+    }
+}
+
+impl std::fmt::Debug for Firehose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Firehose")
+            .field("with_seeds", &self.with_seeds)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &str) -> Result<std::os::unix::net::UnixStream, String> {
+    std::os::unix::net::UnixStream::connect(path).map_err(|e| format!("cannot connect to Unix socket: {}", e))
+}
+
+#[cfg(not(unix))]
+fn connect(_path: &str) -> Result<std::fs::File, String> {
+    Err("Unix sockets are only supported on Unix; use - for stdout".to_string())
+}
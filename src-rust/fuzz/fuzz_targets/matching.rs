@@ -0,0 +1,33 @@
+#![no_main]
+
+// The main crate is binary-only, so pull the module in by path
+#[path = "../../matching.rs"]
+mod matching;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&[u8], &[u8])| {
+    let (haystack, needle) = input;
+    let needle = &needle[..needle.len().min(16)];
+
+    let expected = needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window.eq_ignore_ascii_case(needle));
+    assert_eq!(matching::contains_ignore_case(haystack, needle), expected);
+
+    // `[...]` classes stand for any of their members; a malformed template is
+    // matched as written
+    if let Ok(target) = std::str::from_utf8(needle) {
+        let classes = matching::target_classes(target, false)
+            .unwrap_or_else(|_| needle.iter().map(|c| vec![c.to_ascii_lowercase()]).collect());
+        let expected = classes.is_empty()
+            || haystack.windows(classes.len()).any(|window| {
+                window
+                    .iter()
+                    .zip(&classes)
+                    .all(|(c, class)| class.contains(&c.to_ascii_lowercase()))
+            });
+        assert_eq!(matching::Matcher::new(target, false).is_match(haystack), expected);
+    }
+});
@@ -0,0 +1,463 @@
+//! `ssh-keygen-gui`: a desktop window for people who would rather not use the
+//! command line. It enters a target, shows its difficulty and expected time,
+//! graphs the live rate and lists the keys found. All the work is done by
+//! `ssh-keygen --rpc-stdio`, run as a child process, so the window shares the
+//! search, the checks on every match and the key files with the CLI. The
+//! backend is the `ssh-keygen` binary next to this one, or `$SSH_KEYGEN`.
+
+use eframe::egui;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+
+/// Rate samples kept for the graph, one per second
+const GRAPH_SECONDS: usize = 120;
+
+fn main() -> eframe::Result {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("ssh-keygen")
+            .with_inner_size([760.0, 600.0])
+            .with_min_inner_size([520.0, 420.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "ssh-keygen-gui",
+        options,
+        Box::new(|cc| Ok(Box::new(App::new(cc.egui_ctx.clone())))),
+    )
+}
+
+/// `ssh-keygen --rpc-stdio` and the messages it sends
+struct Backend {
+    child: Child,
+    /// Taken on drop: closing it cancels any search and ends the backend
+    stdin: Option<ChildStdin>,
+    messages: mpsc::Receiver<Value>,
+    next_id: u64,
+}
+
+impl Backend {
+    fn spawn(ctx: egui::Context) -> Result<Self, String> {
+        let program = std::env::var_os("SSH_KEYGEN").map(PathBuf::from).unwrap_or_else(|| {
+            let name = format!("ssh-keygen{}", std::env::consts::EXE_SUFFIX);
+            std::env::current_exe().map(|exe| exe.with_file_name(&name)).unwrap_or_else(|_| name.into())
+        });
+        let mut child = Command::new(&program)
+            .arg("--rpc-stdio")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot start {}: {} (set SSH_KEYGEN to the ssh-keygen binary)", program.display(), e))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let Ok(message) = serde_json::from_str(&line) else {
+                    continue;
+                };
+                if sender.send(message).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+            // Dropping the sender tells the window the backend is gone
+            ctx.request_repaint();
+        });
+        let mut backend = Backend {
+            stdin: child.stdin.take(),
+            child,
+            messages,
+            next_id: 1,
+        };
+        backend.request("subscribe", json!({ "progress": true }))?;
+        Ok(backend)
+    }
+
+    /// Send a request; returns its id
+    fn request(&mut self, method: &str, params: Value) -> Result<u64, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let line = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let stdin = self.stdin.as_mut().ok_or("the backend is closed")?;
+        writeln!(stdin, "{}", line)
+            .and_then(|()| stdin.flush())
+            .map_err(|e| format!("the backend stopped: {}", e))?;
+        Ok(id)
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    Anywhere,
+    Start,
+    End,
+}
+
+impl Position {
+    const ALL: [Position; 3] = [Position::Anywhere, Position::Start, Position::End];
+
+    fn label(self) -> &'static str {
+        match self {
+            Position::Anywhere => "anywhere",
+            Position::Start => "at the start",
+            Position::End => "at the end",
+        }
+    }
+
+    /// As `--rpc-stdio` spells it
+    fn param(self) -> &'static str {
+        match self {
+            Position::Anywhere => "anywhere",
+            Position::Start => "start",
+            Position::End => "end",
+        }
+    }
+}
+
+/// A key the backend found and wrote
+struct Found {
+    target: String,
+    public_key: String,
+    fingerprint: String,
+    files: String,
+    attempts: u64,
+}
+
+struct App {
+    backend: Result<Backend, String>,
+    target: String,
+    ignore_case: bool,
+    position: Position,
+    /// 0 lets the backend pick (one per physical core)
+    threads: u32,
+    output: String,
+
+    /// The query the estimate is for, the id it was asked with, and the answer
+    estimated: Option<(String, bool, Position)>,
+    estimate_id: Option<u64>,
+    estimate: Option<Result<(f64, String), String>>,
+
+    start_id: Option<u64>,
+    /// The running search's id and target
+    running: Option<(u64, String)>,
+    /// Keys per second over each second of the current or last search
+    rates: Vec<f64>,
+    last_sample: Option<(u64, f64)>,
+    status: String,
+    results: Vec<Found>,
+}
+
+impl App {
+    fn new(ctx: egui::Context) -> Self {
+        App {
+            backend: Backend::spawn(ctx),
+            target: String::new(),
+            ignore_case: false,
+            position: Position::Anywhere,
+            threads: 0,
+            output: "id_ed25519".to_string(),
+            estimated: None,
+            estimate_id: None,
+            estimate: None,
+            start_id: None,
+            running: None,
+            rates: Vec::new(),
+            last_sample: None,
+            status: String::new(),
+            results: Vec::new(),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Option<u64> {
+        let backend = self.backend.as_mut().ok()?;
+        match backend.request(method, params) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                self.backend = Err(e);
+                None
+            }
+        }
+    }
+
+    /// Everything the backend sent since the last frame
+    fn receive(&mut self) {
+        let mut messages = Vec::new();
+        if let Ok(backend) = &self.backend {
+            loop {
+                match backend.messages.try_recv() {
+                    Ok(message) => messages.push(message),
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        self.backend = Err("the ssh-keygen backend exited".to_string());
+                        self.running = None;
+                        break;
+                    }
+                }
+            }
+        }
+        for message in messages {
+            self.handle(message);
+        }
+    }
+
+    fn handle(&mut self, message: Value) {
+        let error = message["error"]["message"].as_str().map(str::to_string);
+        let id = message["id"].as_u64();
+        if id.is_some() && id == self.estimate_id {
+            self.estimate = Some(match error {
+                Some(error) => Err(error),
+                None => Ok((
+                    message["result"]["odds"].as_f64().unwrap_or(f64::NAN),
+                    message["result"]["approximate"].as_str().unwrap_or_default().to_string(),
+                )),
+            });
+            return;
+        }
+        if id.is_some() && id == self.start_id {
+            self.start_id = None;
+            match (error, message["result"]["search"].as_u64()) {
+                (Some(error), _) => self.status = error,
+                (None, Some(search)) => {
+                    self.running = Some((search, self.target.clone()));
+                    self.rates.clear();
+                    self.last_sample = Some((0, 0.0));
+                    self.status = format!("Searching for {}...", self.target);
+                }
+                (None, None) => {}
+            }
+            return;
+        }
+        let params = &message["params"];
+        match message["method"].as_str() {
+            Some("progress") => {
+                let (attempts, elapsed) = (params["attempts"].as_u64().unwrap_or(0), params["elapsed"].as_f64().unwrap_or(0.0));
+                if let Some((last_attempts, last_elapsed)) = self.last_sample {
+                    if elapsed > last_elapsed {
+                        self.rates.push(attempts.saturating_sub(last_attempts) as f64 / (elapsed - last_elapsed));
+                        if self.rates.len() > GRAPH_SECONDS {
+                            self.rates.remove(0);
+                        }
+                    }
+                }
+                self.last_sample = Some((attempts, elapsed));
+            }
+            Some("match") => self.results.push(Found {
+                target: self.running.as_ref().map(|(_, target)| target.clone()).unwrap_or_default(),
+                public_key: params["public_key"].as_str().unwrap_or_default().to_string(),
+                fingerprint: params["fingerprint"].as_str().unwrap_or_default().to_string(),
+                files: format!(
+                    "{} and {}",
+                    params["private_key_file"].as_str().unwrap_or_default(),
+                    params["public_key_file"].as_str().unwrap_or_default()
+                ),
+                attempts: params["attempts"].as_u64().unwrap_or(0),
+            }),
+            Some("finished") => {
+                self.running = None;
+                self.status = match params["outcome"].as_str() {
+                    Some("found") => "Found a match".to_string(),
+                    Some("cancelled") => "Cancelled".to_string(),
+                    _ => format!("Failed: {}", params["error"].as_str().unwrap_or("unknown error")),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Ask for the odds again whenever the query changes
+    fn refresh_estimate(&mut self) {
+        let query = (self.target.clone(), self.ignore_case, self.position);
+        if self.estimated.as_ref() == Some(&query) {
+            return;
+        }
+        self.estimated = Some(query);
+        self.estimate = None;
+        self.estimate_id = None;
+        if !self.target.is_empty() {
+            let params = json!({ "target": self.target, "ignore_case": self.ignore_case, "position": self.position.param() });
+            self.estimate_id = self.request("estimate", params);
+        }
+    }
+
+    fn start(&mut self) {
+        let mut params = json!({
+            "target": self.target,
+            "ignore_case": self.ignore_case,
+            "position": self.position.param(),
+            "output": self.output,
+        });
+        if self.threads > 0 {
+            params["threads"] = json!(self.threads);
+        }
+        self.start_id = self.request("start", params);
+    }
+
+    fn search_form(&mut self, ui: &mut egui::Ui) {
+        let idle = self.running.is_none() && self.start_id.is_none();
+        egui::Grid::new("search").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+            ui.label("Target");
+            ui.add_enabled(idle, egui::TextEdit::singleline(&mut self.target).hint_text("e.g. yegor"));
+            ui.end_row();
+
+            ui.label("Match");
+            ui.add_enabled_ui(idle, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("position")
+                        .selected_text(self.position.label())
+                        .show_ui(ui, |ui| {
+                            for position in Position::ALL {
+                                ui.selectable_value(&mut self.position, position, position.label());
+                            }
+                        });
+                    ui.checkbox(&mut self.ignore_case, "ignore case");
+                });
+            });
+            ui.end_row();
+
+            ui.label("Workers");
+            ui.add_enabled_ui(idle, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.threads).range(0..=4096));
+                    ui.label(if self.threads == 0 { "(one per physical core)" } else { "" });
+                });
+            });
+            ui.end_row();
+
+            ui.label("Key file");
+            ui.add_enabled(idle, egui::TextEdit::singleline(&mut self.output));
+            ui.end_row();
+        });
+
+        ui.add_space(6.0);
+        ui.label(self.difficulty());
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            let ready = idle && self.backend.is_ok() && matches!(self.estimate, Some(Ok(_))) && !self.output.is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Start")).clicked() {
+                self.start();
+            }
+            if ui.add_enabled(self.running.is_some(), egui::Button::new("Cancel")).clicked() {
+                self.request("cancel", json!({}));
+            }
+            ui.label(&self.status);
+        });
+    }
+
+    /// "About 1 in 2.8e7 keys; expected 1m07s at 412,000 keys/s"
+    fn difficulty(&self) -> String {
+        match &self.estimate {
+            None if self.target.is_empty() => "Enter a target to see how long it takes".to_string(),
+            None => "Estimating...".to_string(),
+            Some(Err(error)) => error.clone(),
+            Some(Ok((odds, approximate))) => match self.recent_rate() {
+                Some(rate) => format!(
+                    "About 1 in {} keys; expected {} at {:.0} keys/s",
+                    approximate,
+                    duration(odds / rate),
+                    rate
+                ),
+                None => format!("About 1 in {} keys; run a search to measure this machine's rate", approximate),
+            },
+        }
+    }
+
+    /// The average of the last few seconds of the current or last search
+    fn recent_rate(&self) -> Option<f64> {
+        let recent = &self.rates[self.rates.len().saturating_sub(5)..];
+        (!recent.is_empty()).then(|| recent.iter().sum::<f64>() / recent.len() as f64).filter(|rate| *rate > 0.0)
+    }
+
+    fn rate_graph(&self, ui: &mut egui::Ui) {
+        let peak = self.rates.iter().copied().fold(0.0, f64::max);
+        ui.label(match self.rates.last() {
+            Some(rate) => format!("Rate: {:.0} keys/s (peak {:.0})", rate, peak),
+            None => "Rate: no samples yet".to_string(),
+        });
+        let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), 120.0), egui::Sense::hover());
+        let rect = response.rect;
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+        if self.rates.len() >= 2 && peak > 0.0 {
+            let step = rect.width() / (GRAPH_SECONDS - 1) as f32;
+            let points = self
+                .rates
+                .iter()
+                .enumerate()
+                .map(|(second, rate)| {
+                    let x = rect.right() - (self.rates.len() - 1 - second) as f32 * step;
+                    let y = rect.bottom() - (rate / peak) as f32 * (rect.height() - 8.0);
+                    egui::pos2(x, y)
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, visuals.selection.bg_fill)));
+        }
+    }
+
+    fn results_list(&mut self, ui: &mut egui::Ui) {
+        if self.results.is_empty() {
+            ui.label("Keys found appear here.");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("results").num_columns(5).striped(true).show(ui, |ui| {
+                for heading in ["Target", "Fingerprint", "Files", "Attempts", ""] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+                for found in self.results.iter().rev() {
+                    ui.label(&found.target);
+                    ui.monospace(&found.fingerprint);
+                    ui.label(&found.files);
+                    ui.label(found.attempts.to_string());
+                    if ui.button("Copy public key").clicked() {
+                        ui.ctx().copy_text(found.public_key.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.receive();
+        self.refresh_estimate();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Err(error) = &self.backend {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+                ui.separator();
+            }
+            self.search_form(ui);
+            ui.separator();
+            self.rate_graph(ui);
+            ui.separator();
+            self.results_list(ui);
+        });
+    }
+}
+
+/// As `ssh-keygen estimate` prints it: "42s", "17m05s", "3h12m", "4.2 days", "6.1 years"
+fn duration(seconds: f64) -> String {
+    if !seconds.is_finite() {
+        return "forever".to_string();
+    }
+    let whole = seconds.round() as u64;
+    match whole {
+        0..=59 => format!("{}s", whole),
+        60..=3599 => format!("{}m{:02}s", whole / 60, whole % 60),
+        3600..=172_799 => format!("{}h{:02}m", whole / 3600, whole % 3600 / 60),
+        172_800..=63_071_999 => format!("{:.1} days", seconds / 86_400.0),
+        _ => format!("{:.1} years", seconds / 31_557_600.0),
+    }
+}
@@ -0,0 +1,192 @@
+use std::io;
+use std::ops::Deref;
+
+/// Process-wide protections for `--hardened`: no core dumps or crash dumps,
+/// and refuse to run while a debugger is attached.
+pub fn apply() -> Result<(), Box<dyn std::error::Error>> {
+    disable_core_dumps()?;
+    if debugger_present() {
+        return Err("a debugger is attached; refusing to generate keys in hardened mode".into());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn disable_core_dumps() -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: setrlimit only reads the provided struct
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Also blocks ptrace attach from same-uid processes and /proc/<pid>/mem reads
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        // SAFETY: PR_SET_DUMPABLE takes a plain integer argument
+        if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    // Deny future debugger attachment; the kernel kills us if one is already attached
+    #[cfg(target_os = "macos")]
+    {
+        // SAFETY: PT_DENY_ATTACH ignores the pid/addr/data arguments
+        unsafe {
+            libc::ptrace(libc::PT_DENY_ATTACH, 0, std::ptr::null_mut(), 0);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn disable_core_dumps() -> io::Result<()> {
+    use windows_sys::Win32::System::Diagnostics::Debug::{
+        SetErrorMode, SEM_FAILCRITICALERRORS, SEM_NOGPFAULTERRORBOX,
+    };
+    // Suppress Windows Error Reporting crash dialogs and the dumps they collect
+    // SAFETY: SetErrorMode only updates process flags
+    unsafe {
+        SetErrorMode(SEM_FAILCRITICALERRORS | SEM_NOGPFAULTERRORBOX);
+    }
+    Ok(())
+}
+
+/// The soft core file size limit; 0 means no core dumps, `u64::MAX` unlimited
+#[cfg(unix)]
+pub fn core_dump_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes the provided struct
+    if unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) } != 0 {
+        return None;
+    }
+    // rlim_t is 32 bits on some targets
+    #[allow(clippy::unnecessary_cast)]
+    Some(if limit.rlim_cur == libc::RLIM_INFINITY { u64::MAX } else { limit.rlim_cur as u64 })
+}
+
+#[cfg(not(unix))]
+pub fn core_dump_limit() -> Option<u64> {
+    None
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn debugger_present() -> bool {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("TracerPid:"))
+                .map(|pid| pid.trim() != "0")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+pub fn debugger_present() -> bool {
+    // SAFETY: IsDebuggerPresent has no preconditions
+    unsafe { windows_sys::Win32::System::Diagnostics::Debug::IsDebuggerPresent() != 0 }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+pub fn debugger_present() -> bool {
+    // macOS is covered by PT_DENY_ATTACH in disable_core_dumps()
+    false
+}
+
+/// Heap box whose pages can be pinned in RAM so the contents never reach swap
+pub struct SecureBox<T> {
+    inner: Box<T>,
+    locked: bool,
+}
+
+impl<T> SecureBox<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Box::new(value),
+            locked: false,
+        }
+    }
+
+    /// Lock the pages backing the value (mlock / VirtualLock)
+    pub fn lock_in_memory(&mut self) -> io::Result<()> {
+        if !self.locked {
+            lock_region(self.ptr(), std::mem::size_of::<T>())?;
+            self.locked = true;
+        }
+        Ok(())
+    }
+
+    fn ptr(&self) -> *const u8 {
+        &*self.inner as *const T as *const u8
+    }
+}
+
+impl<T> Deref for SecureBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> std::fmt::Debug for SecureBox<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The boxed value is secret by definition; never let Debug output leak it
+        f.write_str("SecureBox([REDACTED])")
+    }
+}
+
+impl<T> Drop for SecureBox<T> {
+    fn drop(&mut self) {
+        if self.locked {
+            // The value's own Drop (e.g. SigningKey zeroization) runs when the
+            // box is freed; unlocking first is fine since the pages stay mapped
+            let _ = unlock_region(self.ptr(), std::mem::size_of::<T>());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn lock_region(ptr: *const u8, len: usize) -> io::Result<()> {
+    // SAFETY: the region belongs to a live allocation owned by the caller
+    if unsafe { libc::mlock(ptr as *const libc::c_void, len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn unlock_region(ptr: *const u8, len: usize) -> io::Result<()> {
+    // SAFETY: the region was previously locked by lock_region
+    if unsafe { libc::munlock(ptr as *const libc::c_void, len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn lock_region(ptr: *const u8, len: usize) -> io::Result<()> {
+    // SAFETY: the region belongs to a live allocation owned by the caller
+    if unsafe { windows_sys::Win32::System::Memory::VirtualLock(ptr as *const _, len) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn unlock_region(ptr: *const u8, len: usize) -> io::Result<()> {
+    // SAFETY: the region was previously locked by lock_region
+    if unsafe { windows_sys::Win32::System::Memory::VirtualUnlock(ptr as *const _, len) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+//! Run history: a summary of every search in a local SQLite database, listed
+//! by `ssh-keygen history`. It makes machines and settings comparable over
+//! time, and the rate past runs reached gives new searches a time estimate.
+
+use crate::estimate::duration;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+
+/// Runs shorter than this are mostly startup and say little about the rate
+const MIN_SECONDS_FOR_RATE: f64 = 5.0;
+/// Past runs considered for an estimate
+const RATE_SAMPLES: usize = 10;
+
+/// One finished search
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// Local start time, RFC 3339
+    pub started: String,
+    pub machine: String,
+    /// e.g. "8 physical / 16 logical"
+    pub cpus: String,
+    pub workers: usize,
+    /// "search", "campaign", "stream" or "emit-candidates"
+    pub mode: String,
+    pub target: String,
+    pub attempts: u64,
+    pub seconds: f64,
+    /// Average keys per second
+    pub rate: f64,
+    /// "found", "timeout", "interrupted", "found 2 of 3", ...
+    pub outcome: String,
+}
+
+/// Which runs `history` lists
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub machine: Option<String>,
+    pub target: Option<String>,
+    /// Newest runs to list; None for all
+    pub limit: Option<usize>,
+}
+
+/// `$XDG_DATA_HOME/ssh-keygen-deluxe/history.db`, or under `~/.local/share`
+pub fn default_path() -> Option<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| crate::install::home_dir().map(|home| home.join(".local").join("share")))?;
+    Some(data.join("ssh-keygen-deluxe").join("history.db"))
+}
+
+/// This machine's name and CPU description, as recorded
+pub fn machine() -> (String, String) {
+    let name = gethostname::gethostname().to_string_lossy().into_owned();
+    let cpus = format!("{} physical / {} logical", num_cpus::get_physical(), num_cpus::get());
+    (name, cpus)
+}
+
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    /// Open the database, creating it (and its directory) on first use
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY,
+                started TEXT NOT NULL,
+                machine TEXT NOT NULL,
+                cpus TEXT NOT NULL,
+                workers INTEGER NOT NULL,
+                mode TEXT NOT NULL,
+                target TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                seconds REAL NOT NULL,
+                rate REAL NOT NULL,
+                outcome TEXT NOT NULL
+            )",
+        )?;
+        // Targets end up in here, so keep it as private as the keys
+        crate::permissions::restrict_to_owner(path)?;
+        Ok(Self { connection })
+    }
+
+    pub fn record(&self, run: &Run) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT INTO runs (started, machine, cpus, workers, mode, target, attempts, seconds, rate, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                run.started,
+                run.machine,
+                run.cpus,
+                run.workers as i64,
+                run.mode,
+                run.target,
+                run.attempts as i64,
+                run.seconds,
+                run.rate,
+                run.outcome
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Matching runs, newest first
+    pub fn runs(&self, filter: &Filter) -> rusqlite::Result<Vec<Run>> {
+        let mut statement = self.connection.prepare(
+            "SELECT started, machine, cpus, workers, mode, target, attempts, seconds, rate, outcome FROM runs
+             WHERE (?1 IS NULL OR machine = ?1) AND (?2 IS NULL OR target = ?2)
+             ORDER BY id DESC LIMIT ?3",
+        )?;
+        // SQLite reads a negative LIMIT as no limit
+        let limit = filter.limit.map_or(-1, |limit| limit as i64);
+        let runs = statement.query_map(params![filter.machine, filter.target, limit], |row| {
+            Ok(Run {
+                started: row.get(0)?,
+                machine: row.get(1)?,
+                cpus: row.get(2)?,
+                workers: row.get::<_, i64>(3)? as usize,
+                mode: row.get(4)?,
+                target: row.get(5)?,
+                attempts: row.get::<_, i64>(6)? as u64,
+                seconds: row.get(7)?,
+                rate: row.get(8)?,
+                outcome: row.get(9)?,
+            })
+        })?;
+        runs.collect()
+    }
+
+    /// Median rate of the latest runs long enough to measure, on this machine
+    /// with this many workers; None until there are some
+    pub fn typical_rate(&self, machine: &str, workers: usize) -> rusqlite::Result<Option<f64>> {
+        let mut statement = self.connection.prepare(
+            "SELECT rate FROM runs WHERE machine = ?1 AND workers = ?2 AND seconds >= ?3 AND rate > 0
+             ORDER BY id DESC LIMIT ?4",
+        )?;
+        let mut rates = statement
+            .query_map(
+                params![machine, workers as i64, MIN_SECONDS_FOR_RATE, RATE_SAMPLES as i64],
+                |row| row.get::<_, f64>(0),
+            )?
+            .collect::<rusqlite::Result<Vec<f64>>>()?;
+        rates.sort_by(f64::total_cmp);
+        Ok(rates.get(rates.len() / 2).copied())
+    }
+}
+
+/// `ssh-keygen history`: one line per run
+pub fn print(runs: &[Run]) {
+    println!(
+        "{:<20} {:<16} {:>7} {:<15} {:<16} {:>14} {:>9} {:>12}  OUTCOME",
+        "STARTED", "MACHINE", "WORKERS", "MODE", "TARGET", "ATTEMPTS", "TIME", "KEYS/S"
+    );
+    for run in runs {
+        // Drop the UTC offset; every row from one machine shares it
+        let started = run.started.get(..19).unwrap_or(&run.started).replace('T', " ");
+        println!(
+            "{:<20} {:<16} {:>7} {:<15} {:<16} {:>14} {:>9} {:>12.0}  {}",
+            started,
+            run.machine,
+            run.workers,
+            run.mode,
+            run.target,
+            run.attempts,
+            duration(run.seconds),
+            run.rate,
+            run.outcome
+        );
+    }
+}
+
+/// `ssh-keygen history --summary`: rates per machine and worker count, from
+/// runs long enough to measure
+pub fn print_summary(runs: &[Run]) {
+    // (machine, cpus, workers) and the rates seen there
+    type Group<'a> = ((&'a str, &'a str, usize), Vec<f64>);
+    let mut groups: Vec<Group> = Vec::new();
+    for run in runs.iter().filter(|run| run.seconds >= MIN_SECONDS_FOR_RATE) {
+        let key = (run.machine.as_str(), run.cpus.as_str(), run.workers);
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, rates)) => rates.push(run.rate),
+            None => groups.push((key, vec![run.rate])),
+        }
+    }
+    println!(
+        "{:<16} {:<22} {:>7} {:>5} {:>14} {:>12}",
+        "MACHINE", "CPUS", "WORKERS", "RUNS", "MEDIAN KEYS/S", "BEST KEYS/S"
+    );
+    for ((machine, cpus, workers), mut rates) in groups {
+        rates.sort_by(f64::total_cmp);
+        println!(
+            "{:<16} {:<22} {:>7} {:>5} {:>14.0} {:>12.0}",
+            machine,
+            cpus,
+            workers,
+            rates.len(),
+            rates[rates.len() / 2],
+            rates[rates.len() - 1]
+        );
+    }
+}
+
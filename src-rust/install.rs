@@ -0,0 +1,164 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Remote script appending a key to authorized_keys. Keys are read from stdin
+/// (new key line, its "<type> <base64>" identity, then an optional identity to
+/// remove) so no quoting is needed.
+const REMOTE_INSTALL_SCRIPT: &str = r#"umask 077
+mkdir -p "$HOME/.ssh" || exit 1
+f="$HOME/.ssh/authorized_keys"
+touch "$f" || exit 1
+IFS= read -r line || exit 1
+IFS= read -r new || exit 1
+IFS= read -r old
+if [ -n "$old" ]; then
+    { grep -vF -- "$old" "$f" || [ $? -eq 1 ]; } > "$f.tmp" && mv "$f.tmp" "$f" || exit 1
+fi
+grep -qF -- "$new" "$f" || printf '%s\n' "$line" >> "$f" || exit 1
+chmod 600 "$f""#;
+
+/// The command line ssh hands to the remote shell. The script itself contains
+/// single quotes, so each is closed, escaped and reopened as `'\''`.
+fn remote_command() -> String {
+    format!("exec sh -c '{}'", REMOTE_INSTALL_SCRIPT.replace('\'', "'\\''"))
+}
+
+/// Reduce an OpenSSH public key line to "<type> <base64>", dropping any
+/// options prefix or comment, so it can be matched against existing entries
+pub fn key_identity(line: &str) -> Option<String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let pos = fields
+        .iter()
+        .position(|field| field.starts_with("ssh-") || field.starts_with("ecdsa-") || field.starts_with("sk-"))?;
+    let body = fields.get(pos + 1)?;
+    Some(format!("{} {}", fields[pos], body))
+}
+
+/// Resolve `--replace-key`, which may be a public key file or a literal key
+pub fn load_replaced_key(spec: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let text = if key_identity(spec).is_some() {
+        spec.to_string()
+    } else {
+        fs::read_to_string(spec).map_err(|e| format!("cannot read {}: {}", spec, e))?
+    };
+    key_identity(&text).ok_or_else(|| format!("no public key found in {}", spec).into())
+}
+
+/// Append the public key to `~/.ssh/authorized_keys` on a remote host using the
+/// local `ssh` client (so existing config, agents and ControlMaster sockets apply)
+pub fn install_remote(
+    destination: &str,
+    public_key: &str,
+    replaced_key: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let new_key = key_identity(public_key).ok_or("invalid public key")?;
+
+    let mut child = Command::new("ssh")
+        .arg("--")
+        .arg(destination)
+        .arg(remote_command())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run ssh: {}", e))?;
+
+    {
+        let mut stdin = child.stdin.take().ok_or("failed to open ssh stdin")?;
+        writeln!(stdin, "{}", public_key.trim())?;
+        writeln!(stdin, "{}", new_key)?;
+        writeln!(stdin, "{}", replaced_key.unwrap_or(""))?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("remote install on {} failed ({})", destination, status).into());
+    }
+    Ok(())
+}
+
+/// The current user's home directory
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Resolve (and create if needed) `~/.ssh`, returning the private and public
+/// key paths for `name`. Refuses to clobber an existing identity.
+pub fn local_key_paths(name: &str) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(|c: char| c == '/' || c == '\\' || c.is_whitespace())
+    {
+        return Err(format!("invalid key name for --install-local: {:?}", name).into());
+    }
+
+    let ssh_dir = home_dir().ok_or("cannot determine home directory")?.join(".ssh");
+    if !ssh_dir.exists() {
+        fs::create_dir_all(&ssh_dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&ssh_dir, fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    let private_path = ssh_dir.join(name);
+    let public_path = ssh_dir.join(format!("{}.pub", name));
+    for path in [&private_path, &public_path] {
+        if path.exists() {
+            return Err(format!("{} already exists; refusing to overwrite it", path.display()).into());
+        }
+    }
+    Ok((private_path, public_path))
+}
+
+/// Check whether an ssh_config already declares a `Host` entry with this alias
+fn has_host_entry(config: &str, alias: &str) -> bool {
+    config.lines().any(|line| {
+        let mut tokens = line.split_whitespace();
+        matches!(tokens.next(), Some(keyword) if keyword.eq_ignore_ascii_case("host"))
+            && tokens.any(|pattern| pattern == alias)
+    })
+}
+
+/// Append a `Host` block for the new identity to `~/.ssh/config`.
+/// Returns false (and leaves the file alone) if the alias is already defined.
+pub fn append_host_stanza(
+    name: &str,
+    host_name: Option<&str>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let config_path = home_dir().ok_or("cannot determine home directory")?.join(".ssh").join("config");
+    let existing = match fs::read_to_string(&config_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    if has_host_entry(&existing, name) {
+        return Ok(false);
+    }
+
+    let mut stanza = String::new();
+    if !existing.is_empty() {
+        stanza.push_str(if existing.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    stanza.push_str(&format!("Host {}\n", name));
+    if let Some(host_name) = host_name {
+        stanza.push_str(&format!("    HostName {}\n", host_name));
+    }
+    stanza.push_str(&format!("    IdentityFile ~/.ssh/{}\n", name));
+    stanza.push_str("    IdentitiesOnly yes\n");
+
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&config_path)?;
+    file.write_all(stanza.as_bytes())?;
+
+    #[cfg(unix)]
+    if existing.is_empty() {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&config_path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(true)
+}
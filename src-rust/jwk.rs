@@ -0,0 +1,44 @@
+//! `--emit jwk`: the key as RFC 8037 `OKP` JSON Web Keys, for JOSE/JWT signing
+//! configs that take a JWK rather than an OpenSSH key.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL;
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Private and public JWK files, named after the public key file
+pub fn output_paths(public_key_file: &str) -> (String, String) {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    (format!("{}.jwk", base), format!("{}.pub.jwk", base))
+}
+
+/// Public JWK: `kty`, `crv`, `x`, plus the RFC 7638 thumbprint as `kid`
+pub fn public_jwk(signing_key: &SigningKey) -> String {
+    let x = BASE64URL.encode(signing_key.verifying_key().to_bytes());
+    format!(
+        "{{\n  \"kty\": \"OKP\",\n  \"crv\": \"Ed25519\",\n  \"x\": \"{}\",\n  \"kid\": \"{}\",\n  \"alg\": \"EdDSA\",\n  \"use\": \"sig\"\n}}\n",
+        x,
+        thumbprint(&x)
+    )
+}
+
+/// Private JWK: the public members plus the seed as `d`. Built by hand so the
+/// seed only ever sits in zeroized buffers.
+pub fn private_jwk(signing_key: &SigningKey) -> Zeroizing<String> {
+    let seed = Zeroizing::new(signing_key.to_bytes());
+    let d = Zeroizing::new(BASE64URL.encode(seed.as_slice()));
+    let public = public_jwk(signing_key);
+    let mut jwk = Zeroizing::new(String::with_capacity(public.len() + d.len() + 16));
+    jwk.push_str(public.trim_end().strip_suffix("\n}").unwrap_or(&public));
+    jwk.push_str(",\n  \"d\": \"");
+    jwk.push_str(&d);
+    jwk.push_str("\"\n}\n");
+    jwk
+}
+
+/// RFC 7638 thumbprint: SHA-256 over the required members in lexical order
+fn thumbprint(x: &str) -> String {
+    let canonical = format!("{{\"crv\":\"Ed25519\",\"kty\":\"OKP\",\"x\":\"{}\"}}", x);
+    BASE64URL.encode(Sha256::digest(canonical.as_bytes()))
+}
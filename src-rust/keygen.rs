@@ -0,0 +1,100 @@
+//! Key generators: what a candidate is for each key type, the public key text
+//! targets are matched against, and the key kept once one matches. The
+//! workers, stats and save steps only talk to a `KeyGenerator`, so another
+//! algorithm is another implementation here rather than another branch in
+//! every worker loop.
+//!
+//! Every key type so far is derived from a 32-byte seed and kept as one.
+
+use crate::error::Error;
+use crate::{basepoint, entropy, formats, matching, x25519};
+use ed25519_dalek::SigningKey;
+use zeroize::Zeroizing;
+
+/// A candidate's seed, wiped when dropped
+pub type Seed = Zeroizing<[u8; 32]>;
+
+pub trait KeyGenerator: Send + Sync + std::fmt::Debug {
+    /// The key type as shown before the search, e.g. "ed25519"
+    fn name(&self) -> String;
+
+    /// Draw a candidate from the worker's RNG
+    fn candidate(&self, rng: &mut entropy::WorkerRng) -> Seed {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(seed.as_mut());
+        seed
+    }
+
+    /// The public key text targets are matched against - the expensive part
+    fn public_text(&self, seed: &[u8; 32]) -> Option<String>;
+
+    /// Encode a batch of candidates' public keys for `Matcher::scan`. Returns
+    /// false when this key type's text has no column layout, and every
+    /// candidate is then checked on its own.
+    fn encode_batch(&self, _seeds: &[Seed], _columns: &mut matching::KeyColumns) -> bool {
+        false
+    }
+
+    /// The key kept for a matching candidate
+    fn materialize(&self, seed: &[u8; 32]) -> SigningKey {
+        SigningKey::from_bytes(seed)
+    }
+
+    /// Write a found key to the key type's own files and read them back,
+    /// returning the private and public paths. None for SSH keys, which go
+    /// through the formats, agent, upload and export steps instead.
+    fn save(&self, _key: &SigningKey, _public_text: &str) -> Option<Result<(&'static str, &'static str), Error>> {
+        None
+    }
+}
+
+/// OpenSSH Ed25519 keys, matched by their `ssh-ed25519 AAAA...` line
+#[derive(Debug)]
+pub struct Ed25519;
+
+impl KeyGenerator for Ed25519 {
+    fn name(&self) -> String {
+        "ed25519".to_string()
+    }
+
+    fn public_text(&self, seed: &[u8; 32]) -> Option<String> {
+        let private_key = formats::openssh_private_key(&SigningKey::from_bytes(seed), "").ok()?;
+        private_key.public_key().to_openssh().ok()
+    }
+
+    /// Public keys come from the basepoint table, without a `SigningKey` each
+    fn encode_batch(&self, seeds: &[Seed], columns: &mut matching::KeyColumns) -> bool {
+        let table = &basepoint::choice().table;
+        columns.encode(seeds.iter().map(|seed| table.public_key(seed)));
+        true
+    }
+}
+
+/// `--x25519`: mesh VPN node keys, matched as the VPN shows the public key
+#[derive(Debug)]
+pub struct X25519(pub x25519::Layout);
+
+impl KeyGenerator for X25519 {
+    fn name(&self) -> String {
+        format!("X25519 ({})", self.0.name())
+    }
+
+    fn public_text(&self, seed: &[u8; 32]) -> Option<String> {
+        Some(self.0.public_key(seed))
+    }
+
+    fn save(&self, key: &SigningKey, public_text: &str) -> Option<Result<(&'static str, &'static str), Error>> {
+        let seed = Zeroizing::new(key.to_bytes());
+        Some(
+            self.0
+                .save(&seed, public_text)
+                .map_err(|e| Error::io("saving keys", e))
+                .and_then(|()| {
+                    self.0
+                        .verify_written()
+                        .map_err(|e| Error::Integrity(format!("written key files failed verification: {}", e)))
+                })
+                .map(|()| self.0.output_paths()),
+        )
+    }
+}
@@ -0,0 +1,57 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Magic prefix OpenSSH uses for hashed hostnames
+const HASH_MAGIC: &str = "|1|";
+
+/// Split a `--known-hosts` value into individual host patterns
+pub fn parse_hosts(spec: &str) -> Result<Vec<String>, String> {
+    let hosts: Vec<String> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect();
+    if hosts.is_empty() {
+        return Err("--known-hosts needs at least one hostname".to_string());
+    }
+    if let Some(bad) = hosts.iter().find(|host| host.contains(char::is_whitespace) || host.starts_with('|')) {
+        return Err(format!("invalid known_hosts hostname: {:?}", bad));
+    }
+    Ok(hosts)
+}
+
+/// Plain known_hosts line, all hostnames sharing a single entry
+pub fn plain_line(hosts: &[String], public_key: &str) -> String {
+    format!("{} {}\n", hosts.join(","), public_key.trim())
+}
+
+/// HashKnownHosts-style lines, one per hostname as `ssh-keygen -H` writes them
+pub fn hashed_lines(hosts: &[String], public_key: &str) -> String {
+    hosts
+        .iter()
+        .map(|host| format!("{} {}\n", hash_host(host), public_key.trim()))
+        .collect()
+}
+
+/// `|1|base64(salt)|base64(HMAC-SHA1(salt, host))` with a fresh 20-byte salt
+fn hash_host(host: &str) -> String {
+    let mut salt = [0u8; 20];
+    OsRng.fill_bytes(&mut salt);
+
+    let mut mac = Hmac::<Sha1>::new_from_slice(&salt).expect("HMAC accepts any key length");
+    mac.update(host.as_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    format!("{}{}|{}", HASH_MAGIC, BASE64.encode(salt), BASE64.encode(digest))
+}
+
+/// Paths of the plain and hashed known_hosts outputs next to the public key file
+pub fn output_paths(public_key_file: &str) -> (String, String) {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    (format!("{}.known_hosts", base), format!("{}.known_hosts.hashed", base))
+}
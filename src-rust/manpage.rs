@@ -0,0 +1,46 @@
+//! `ssh-keygen gen-manpage`: roff man pages generated from the same clap
+//! definitions the binary parses, so they never drift from `--help`. The pages
+//! are titled ssh-keygen-deluxe(1) and ssh-keygen-deluxe-<subcommand>(1), so
+//! installing them never shadows OpenSSH's own ssh-keygen(1).
+
+use crate::error::Error;
+use clap::Command;
+use std::path::Path;
+
+/// The man page title and file name prefix
+pub const NAME: &str = "ssh-keygen-deluxe";
+
+/// Print the main page, or with `dir` write it and one page per subcommand there
+pub fn run(command: Command, dir: Option<&str>) -> Result<(), Error> {
+    let mut command = command
+        .display_name(NAME)
+        .bin_name("ssh-keygen")
+        .disable_help_subcommand(true);
+    // Fills in each subcommand's display name (ssh-keygen-deluxe-verify) and usage
+    command.build();
+    let Some(dir) = dir else {
+        let mut stdout = std::io::stdout().lock();
+        return man(command)
+            .render(&mut stdout)
+            .map_err(|e| Error::io("writing the man page", e));
+    };
+    std::fs::create_dir_all(dir).map_err(|e| Error::io(format!("creating {}", dir), e))?;
+    write_pages(&command, Path::new(dir))
+}
+
+/// The page for `command` and every visible subcommand below it
+fn write_pages(command: &Command, dir: &Path) -> Result<(), Error> {
+    let path = man(command.clone())
+        .generate_to(dir)
+        .map_err(|e| Error::io(format!("writing man pages to {}", dir.display()), e))?;
+    println!("{}", path.display());
+    for subcommand in command.get_subcommands().filter(|subcommand| !subcommand.is_hide_set()) {
+        write_pages(subcommand, dir)?;
+    }
+    Ok(())
+}
+
+/// Every page footer names the tool and version, not just the main one
+fn man(command: Command) -> clap_mangen::Man {
+    clap_mangen::Man::new(command).source(format!("{} {}", NAME, env!("CARGO_PKG_VERSION")))
+}
@@ -0,0 +1,1128 @@
+use aho_corasick::AhoCorasick;
+use memchr::memmem;
+
+/// Fixed start of every Ed25519 public key line: the key type, then the base64 of
+/// the blob's length-prefixed type name and the key's 4-byte length (0x20)
+pub const ED25519_PREFIX: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI";
+
+/// The first character after the prefix carries the two zero low bits of the
+/// length byte and the top four bits of the key, so it is always one of these
+pub const FIRST_VARIABLE_CHARS: &[u8] = b"ABCDEFGHIJKLMNOP";
+
+/// Base64 characters after the prefix: 68 for the 51-byte blob, minus the 25 fixed
+/// ones. 51 bytes need no padding, so the last character can be any of the 64.
+pub const VARIABLE_CHARS: usize = 43;
+
+/// Length of every Ed25519 public key line without a comment
+pub const LINE_LEN: usize = ED25519_PREFIX.len() + VARIABLE_CHARS;
+
+pub const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Where in the key the target has to appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Position {
+    #[default]
+    Anywhere,
+    /// `--at-start`: right after `ED25519_PREFIX`, the first thing people read in a key
+    AtStart,
+    /// `--suffix`: the last characters of the base64 body
+    AtEnd,
+}
+
+/// The most spellings one character-class target may stand for
+pub const MAX_SPELLINGS: usize = 65_536;
+
+/// A target's characters, each as the set it may be: `y[e3]g` is `y`, then `e`
+/// or `3`, then `g`, and `[0-9]` is any digit. A target without brackets has one
+/// character per set. Case-insensitive sets are folded to lowercase.
+pub fn target_classes(target: &str, case_sensitive: bool) -> Result<Vec<Vec<u8>>, String> {
+    let fold = |c: u8| if case_sensitive { c } else { c.to_ascii_lowercase() };
+    let bytes = target.as_bytes();
+    let mut classes: Vec<Vec<u8>> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b']' => return Err(format!("\"{}\": ']' without a '[' before it", target)),
+            b'[' => {
+                let Some(len) = bytes[i + 1..].iter().position(|&c| c == b']') else {
+                    return Err(format!("\"{}\": '[' is never closed", target));
+                };
+                let inside = &bytes[i + 1..i + 1 + len];
+                if inside.is_empty() {
+                    return Err(format!("\"{}\": '[]' allows no character", target));
+                }
+                if inside.contains(&b'[') {
+                    return Err(format!("\"{}\": '[' inside a [...] class", target));
+                }
+                let mut class: Vec<u8> = Vec::new();
+                let mut j = 0;
+                while j < inside.len() {
+                    // `a-z` is a range; a '-' first or last is itself
+                    let range = j + 2 < inside.len() && inside[j + 1] == b'-';
+                    let (low, high) = (inside[j], if range { inside[j + 2] } else { inside[j] });
+                    if low > high {
+                        return Err(format!("\"{}\": range {}-{} runs backwards", target, low as char, high as char));
+                    }
+                    for c in (low..=high).map(fold) {
+                        if !class.contains(&c) {
+                            class.push(c);
+                        }
+                    }
+                    j += if range { 3 } else { 1 };
+                }
+                classes.push(class);
+                i += len + 2;
+            }
+            c => {
+                classes.push(vec![fold(c)]);
+                i += 1;
+            }
+        }
+    }
+    let spellings = classes.iter().try_fold(1usize, |count, class| count.checked_mul(class.len()));
+    if spellings.is_none_or(|count| count > MAX_SPELLINGS) {
+        return Err(format!(
+            "\"{}\" stands for more than {} spellings; use fewer or smaller [...] classes",
+            target, MAX_SPELLINGS
+        ));
+    }
+    Ok(classes)
+}
+
+/// Every spelling of a target given as classes, in order
+fn spellings(classes: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    classes.iter().fold(vec![Vec::new()], |spellings, class| {
+        spellings
+            .iter()
+            .flat_map(|spelling| {
+                class.iter().map(move |&c| {
+                    let mut longer = spelling.clone();
+                    longer.push(c);
+                    longer
+                })
+            })
+            .collect()
+    })
+}
+
+/// Classes for `target`, or its bytes as written if it is not a valid template.
+/// Such a target is refused by the odds checks before any search starts.
+fn classes_or_literal(target: &str, case_sensitive: bool) -> Vec<Vec<u8>> {
+    target_classes(target, case_sensitive).unwrap_or_else(|_| {
+        target
+            .bytes()
+            .map(|c| vec![if case_sensitive { c } else { c.to_ascii_lowercase() }])
+            .collect()
+    })
+}
+
+/// Whether some spelling of `target` contains `text`, ignoring case: `[a]b`
+/// contains `ab` and `[Dd]ickson` contains `dick`. Lets the blocklist and
+/// `--screen` pass words the target itself asks for.
+pub fn spelling_contains(target: &str, text: &str) -> bool {
+    let text = text.as_bytes();
+    !text.is_empty()
+        && classes_or_literal(target, false)
+            .windows(text.len())
+            .any(|window| window.iter().zip(text).all(|(class, c)| class.contains(&c.to_ascii_lowercase())))
+}
+
+/// A class as written in a target: `e`, or `[e3]` for several characters
+pub fn class_label(class: &[u8]) -> String {
+    match class {
+        [c] => (*c as char).to_string(),
+        _ => format!("[{}]", String::from_utf8_lossy(class)),
+    }
+}
+
+/// Target matcher built once per worker and reused for every candidate key
+#[derive(Debug)]
+pub struct Matcher {
+    /// The target's first spelling; the whole target unless it has classes
+    needle: Vec<u8>,
+    /// One set per target character, folded like `needle`
+    classes: Vec<Vec<u8>>,
+    case_sensitive: bool,
+    finder: memmem::Finder<'static>,
+    /// Every spelling of an unanchored character-class target in one automaton
+    spellings: Option<AhoCorasick>,
+    position: Position,
+    /// `--min-count`: non-overlapping occurrences required
+    min_count: usize,
+}
+
+impl Matcher {
+    pub fn new(target: &str, case_sensitive: bool) -> Self {
+        Self::at(target, case_sensitive, Position::Anywhere)
+    }
+
+    pub fn at(target: &str, case_sensitive: bool, position: Position) -> Self {
+        let classes = classes_or_literal(target, case_sensitive);
+        let needle: Vec<u8> = classes.iter().map(|class| class[0]).collect();
+        let spellings = (position == Position::Anywhere && classes.iter().any(|class| class.len() > 1)).then(|| {
+            AhoCorasick::builder()
+                .ascii_case_insensitive(!case_sensitive)
+                .build(spellings(&classes))
+                .expect("spelling automaton fits in memory")
+        });
+        Self {
+            finder: memmem::Finder::new(&needle).into_owned(),
+            needle,
+            classes,
+            case_sensitive,
+            spellings,
+            position,
+            min_count: 1,
+        }
+    }
+
+    /// Require the target at least `min_count` times, without overlaps
+    pub fn with_min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    /// Whether the target occurs in `haystack`, a `<type> <base64>` key line
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        let len = self.classes.len();
+        let window = match self.position {
+            Position::Anywhere if self.min_count > 1 => return self.count(haystack) >= self.min_count,
+            Position::Anywhere => {
+                return match &self.spellings {
+                    Some(spellings) => spellings.is_match(haystack),
+                    None if self.case_sensitive => self.finder.find(haystack).is_some(),
+                    None => contains_ignore_case(haystack, &self.needle),
+                }
+            }
+            Position::AtStart => haystack.get(ED25519_PREFIX.len()..ED25519_PREFIX.len() + len),
+            Position::AtEnd => haystack.len().checked_sub(len).map(|start| &haystack[start..]),
+        };
+        match window {
+            Some(window) => window
+                .iter()
+                .zip(&self.classes)
+                .all(|(&c, class)| class.contains(&fold(c, self.case_sensitive))),
+            None => false,
+        }
+    }
+
+    /// Non-overlapping occurrences in `haystack`, counting no further than `min_count`
+    fn count(&self, haystack: &[u8]) -> usize {
+        if let Some(spellings) = &self.spellings {
+            // Every spelling has the same length, so the leftmost one wins as it would for a literal
+            return spellings.find_iter(haystack).take(self.min_count).count();
+        }
+        if self.case_sensitive {
+            return self.finder.find_iter(haystack).take(self.min_count).count();
+        }
+        let mut count = 0;
+        let mut start = 0;
+        while count < self.min_count {
+            match find_ignore_case(&haystack[start..], &self.needle) {
+                Some(pos) => {
+                    count += 1;
+                    start += pos + self.needle.len().max(1);
+                    if start > haystack.len() {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        count
+    }
+}
+
+/// Candidates per batch, one bit each in a lane mask
+pub const BATCH: usize = 64;
+
+/// Fixed raw blob bytes ahead of the 32-byte key in the base64 group it starts:
+/// the low byte of the key length, 0x20. Its first character ends ED25519_PREFIX.
+const LENGTH_BYTE: u8 = 0x20;
+
+/// A batch of candidate public keys as structure-of-arrays: `chars[i][lane]` is
+/// the `i`th character after ED25519_PREFIX in candidate `lane`'s key line.
+/// Encoding and scanning walk whole columns of candidates, which the compiler
+/// vectorizes, instead of one key line at a time.
+pub struct KeyColumns {
+    chars: [[u8; BATCH]; VARIABLE_CHARS],
+    len: usize,
+}
+
+impl Default for KeyColumns {
+    fn default() -> Self {
+        Self {
+            chars: [[0; BATCH]; VARIABLE_CHARS],
+            len: 0,
+        }
+    }
+}
+
+impl KeyColumns {
+    /// Base64-encode up to BATCH raw Ed25519 public keys, one per lane
+    pub fn encode(&mut self, public_keys: impl IntoIterator<Item = [u8; 32]>) {
+        // bytes[j][lane]: byte j of the 33 encoded after the fixed groups
+        let mut bytes = [[0u8; BATCH]; 33];
+        bytes[0] = [LENGTH_BYTE; BATCH];
+        self.len = 0;
+        for (lane, key) in public_keys.into_iter().take(BATCH).enumerate() {
+            for (j, byte) in key.iter().enumerate() {
+                bytes[j + 1][lane] = *byte;
+            }
+            self.len = lane + 1;
+        }
+
+        for group in 0..11 {
+            let [b0, b1, b2] = [&bytes[3 * group], &bytes[3 * group + 1], &bytes[3 * group + 2]];
+            let mut sextets = [[0u8; BATCH]; 4];
+            for lane in 0..BATCH {
+                sextets[0][lane] = b0[lane] >> 2;
+                sextets[1][lane] = (b0[lane] & 0x03) << 4 | b1[lane] >> 4;
+                sextets[2][lane] = (b1[lane] & 0x0f) << 2 | b2[lane] >> 6;
+                sextets[3][lane] = b2[lane] & 0x3f;
+            }
+            for (k, column) in sextets.iter().enumerate() {
+                // The group's first character is the last one of the prefix
+                let Some(index) = (4 * group + k).checked_sub(1) else {
+                    continue;
+                };
+                for (out, &sextet) in self.chars[index].iter_mut().zip(column) {
+                    *out = base64_char(sextet);
+                }
+            }
+        }
+    }
+
+    /// The full key line of candidate `lane`
+    pub fn line(&self, lane: usize) -> [u8; LINE_LEN] {
+        let mut line = [0u8; LINE_LEN];
+        line[..ED25519_PREFIX.len()].copy_from_slice(ED25519_PREFIX.as_bytes());
+        for (index, column) in self.chars.iter().enumerate() {
+            line[ED25519_PREFIX.len() + index] = column[lane];
+        }
+        line
+    }
+
+    /// Lanes whose character at `index` equals `c` once folded
+    fn lanes_with(&self, index: usize, c: u8, case_sensitive: bool) -> u64 {
+        let mut equal = [false; BATCH];
+        for (lane, &candidate) in self.chars[index].iter().enumerate() {
+            equal[lane] = fold(candidate, case_sensitive) == c;
+        }
+        lane_mask(&equal)
+    }
+
+    /// Lanes whose character at `index` is in `class` once folded
+    fn lanes_in(&self, index: usize, class: &[u8], case_sensitive: bool) -> u64 {
+        class.iter().fold(0, |mask, &c| mask | self.lanes_with(index, c, case_sensitive))
+    }
+
+    /// Mask with a bit for every candidate in the batch
+    fn all_lanes(&self) -> u64 {
+        if self.len == BATCH {
+            u64::MAX
+        } else {
+            (1u64 << self.len) - 1
+        }
+    }
+}
+
+/// Base64 character for a sextet without a table lookup, so it vectorizes
+#[inline]
+fn base64_char(sextet: u8) -> u8 {
+    let mut c = sextet + b'A';
+    c += (sextet > 25) as u8 * (b'a' - b'Z' - 1);
+    c -= (sextet > 51) as u8 * (b'z' + 1 - b'0');
+    c -= (sextet > 61) as u8 * (b'9' + 1 - b'+');
+    c += (sextet > 62) as u8 * (b'/' - b'+' - 1);
+    c
+}
+
+/// ASCII lowercase for case-insensitive comparisons, branch-free
+#[inline]
+fn fold(c: u8, case_sensitive: bool) -> u8 {
+    c | ((!case_sensitive && c.wrapping_sub(b'A') < 26) as u8) << 5
+}
+
+fn lane_mask(lanes: &[bool; BATCH]) -> u64 {
+    lanes
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (lane, &set)| mask | (set as u64) << lane)
+}
+
+impl Matcher {
+    /// Lanes of `columns` whose key line matches, exactly as `is_match` would.
+    /// Anchored targets compare their columns across every lane at once; others
+    /// first look for lanes with the target's first character in any column (or
+    /// continuing a start in the fixed prefix) and check only those in full.
+    pub fn scan(&self, columns: &KeyColumns) -> u64 {
+        let all = columns.all_lanes();
+        if self.classes.is_empty() {
+            return all;
+        }
+        let anchored_column = match self.position {
+            Position::AtStart => Some(0),
+            Position::AtEnd => VARIABLE_CHARS.checked_sub(self.classes.len()),
+            Position::Anywhere => None,
+        };
+        let candidates = match anchored_column {
+            Some(column) if self.classes.len() <= VARIABLE_CHARS => {
+                return self.classes.iter().enumerate().fold(all, |mask, (offset, class)| {
+                    mask & columns.lanes_in(column + offset, class, self.case_sensitive)
+                });
+            }
+            None if self.position == Position::Anywhere => self.anywhere_candidates(columns),
+            // Anchored targets longer than the variable part reach into the prefix
+            _ => all,
+        };
+
+        let mut matches = 0;
+        let mut remaining = candidates;
+        while remaining != 0 {
+            let lane = remaining.trailing_zeros() as usize;
+            remaining &= remaining - 1;
+            if self.is_match(&columns.line(lane)) {
+                matches |= 1 << lane;
+            }
+        }
+        matches
+    }
+
+    /// Lanes that may contain the target: its first character in any column, or
+    /// its continuation in the first column after a start in the fixed prefix
+    fn anywhere_candidates(&self, columns: &KeyColumns) -> u64 {
+        let all = columns.all_lanes();
+        let mut candidates = 0;
+        for index in 0..VARIABLE_CHARS {
+            candidates |= columns.lanes_in(index, &self.classes[0], self.case_sensitive);
+        }
+        let prefix = ED25519_PREFIX.as_bytes();
+        for consumed in 1..=self.classes.len().min(prefix.len()) {
+            let tail = &prefix[prefix.len() - consumed..];
+            let same = tail
+                .iter()
+                .zip(&self.classes)
+                .all(|(&c, class)| class.contains(&fold(c, self.case_sensitive)));
+            if !same {
+                continue;
+            }
+            candidates |= match self.classes.get(consumed) {
+                Some(next) => columns.lanes_in(0, next, self.case_sensitive),
+                None => all,
+            };
+        }
+        // Anything wholly inside the prefix matches every key
+        if self.is_match(prefix) {
+            candidates = all;
+        }
+        candidates & all
+    }
+}
+
+/// On average one in how many keys has `target` at an anchored `position`, or
+/// why no key ever can. None for targets that may appear anywhere.
+pub fn anchored_odds(target: &str, case_sensitive: bool, position: Position) -> Result<Option<f64>, String> {
+    if position == Position::Anywhere {
+        return Ok(None);
+    }
+    let classes = target_classes(target, case_sensitive)?;
+    let first = match position {
+        Position::AtEnd => VARIABLE_CHARS.saturating_sub(classes.len()),
+        _ => 0,
+    };
+    if classes.len() > VARIABLE_CHARS {
+        return Err(format!(
+            "\"{}\" is longer than the {} characters after the fixed key prefix",
+            target, VARIABLE_CHARS
+        ));
+    }
+    let mut odds = 1.0;
+    for (offset, class) in classes.iter().enumerate() {
+        // Index among the variable characters after the prefix
+        let chance = class_chance(ED25519_PREFIX.len() + first + offset, class, case_sensitive);
+        if chance == 0.0 {
+            return Err(if !has_base64(class) {
+                not_base64(class, target)
+            } else {
+                format!(
+                    "\"{}\" can never match there: the first character after the fixed prefix is always A-P",
+                    target
+                )
+            });
+        }
+        odds /= chance;
+    }
+    Ok(Some(odds))
+}
+
+fn has_base64(class: &[u8]) -> bool {
+    class.iter().any(|c| BASE64_CHARS.contains(c))
+}
+
+fn not_base64(class: &[u8], target: &str) -> String {
+    match class {
+        [c] => format!("'{}' is not a base64 character, so \"{}\" can never match", *c as char, target),
+        _ => format!("none of {} is a base64 character, so \"{}\" can never match", class_label(class), target),
+    }
+}
+
+/// One in how many keys contains `target` at `position`, or why none can. For
+/// `Anywhere` the chances at every offset are summed, the fixed prefix included,
+/// which overstates short targets slightly but is close for the rare ones.
+pub fn match_odds(target: &str, case_sensitive: bool, position: Position) -> Result<f64, String> {
+    if let Some(odds) = anchored_odds(target, case_sensitive, position)? {
+        return Ok(odds);
+    }
+    let classes = target_classes(target, case_sensitive)?;
+    if classes.len() > LINE_LEN {
+        return Err(format!("\"{}\" is longer than the {}-character key", target, LINE_LEN));
+    }
+    let chance: f64 = (0..=LINE_LEN - classes.len())
+        .map(|start| {
+            classes
+                .iter()
+                .enumerate()
+                .map(|(offset, class)| class_chance(start + offset, class, case_sensitive))
+                .product::<f64>()
+        })
+        .sum();
+    if chance > 0.0 {
+        return Ok((1.0 / chance).max(1.0));
+    }
+    Err(match classes.iter().find(|class| !has_base64(class)) {
+        Some(class) => not_base64(class, target),
+        None => format!("\"{}\" can never match anywhere in a key", target),
+    })
+}
+
+/// Chance that character `index` of a key line is `c`
+pub fn char_chance(index: usize, c: u8, case_sensitive: bool) -> f64 {
+    class_chance(index, &[c], case_sensitive)
+}
+
+/// Chance that character `index` of a key line is any of `class`
+pub fn class_chance(index: usize, class: &[u8], case_sensitive: bool) -> f64 {
+    let same = |candidate: &u8| {
+        class
+            .iter()
+            .any(|c| if case_sensitive { candidate == c } else { candidate.eq_ignore_ascii_case(c) })
+    };
+    if let Some(fixed) = ED25519_PREFIX.as_bytes().get(index) {
+        return if same(fixed) { 1.0 } else { 0.0 };
+    }
+    let possible = if index == ED25519_PREFIX.len() { FIRST_VARIABLE_CHARS } else { BASE64_CHARS };
+    possible.iter().filter(|candidate| same(candidate)).count() as f64 / possible.len() as f64
+}
+
+/// Expected attempts past which a search likely runs for days
+const SLOW_ODDS: f64 = 1e12;
+
+/// A warning worth reading before the search starts: the target is in the fixed
+/// prefix, or rare enough to take days. The latter lists the odds under every
+/// position and case handling and what each character costs where it has to sit.
+pub fn difficulty_warning(target: &str, case_sensitive: bool, position: Position) -> Option<String> {
+    let in_prefix = Matcher::new(target, case_sensitive).is_match(ED25519_PREFIX.as_bytes());
+    if position == Position::Anywhere && in_prefix {
+        return Some(format!(
+            "\"{}\" is part of the \"{}\" prefix every key starts with, so every key matches",
+            target, ED25519_PREFIX
+        ));
+    }
+    let chosen = match_odds(target, case_sensitive, position).ok()?;
+    if chosen < SLOW_ODDS {
+        return None;
+    }
+
+    let mut variants = Vec::new();
+    for variant_position in [Position::Anywhere, Position::AtStart, Position::AtEnd] {
+        for variant_case in [true, false] {
+            if variant_case && !case_sensitive {
+                // Asking for exact case is never cheaper
+                continue;
+            }
+            variants.push((variant_position, variant_case, match_odds(target, variant_case, variant_position)));
+        }
+    }
+    let mut warning = format!(
+        "\"{}\" needs about {} attempts per match as searched ({}, {}), likely days of searching",
+        target,
+        approximate(chosen),
+        position_name(position),
+        if case_sensitive { "case-sensitive" } else { "case-insensitive" }
+    );
+    for (variant_position, variant_case, odds) in &variants {
+        warning.push_str(&format!(
+            "\n  {}, {}: {}",
+            position_name(*variant_position),
+            if *variant_case { "case-sensitive" } else { "case-insensitive" },
+            match odds {
+                Ok(odds) => format!("about 1 in {} keys", approximate(*odds)),
+                Err(e) => e.clone(),
+            }
+        ));
+    }
+
+    // What each character costs where the target has to sit; unanchored, away
+    // from the restricted first character
+    let classes = target_classes(target, case_sensitive).ok()?;
+    let first = match position {
+        Position::Anywhere => ED25519_PREFIX.len() + 1,
+        Position::AtStart => ED25519_PREFIX.len(),
+        Position::AtEnd => LINE_LEN - classes.len().min(VARIABLE_CHARS),
+    };
+    let costs: Vec<String> = classes
+        .iter()
+        .enumerate()
+        .map(|(offset, class)| {
+            let chance = class_chance(first + offset, class, case_sensitive);
+            if chance > 0.0 {
+                format!("{} 1/{:.0}", class_label(class), 1.0 / chance)
+            } else {
+                format!("{} never", class_label(class))
+            }
+        })
+        .collect();
+    warning.push_str(&format!("\n  per character: {}", costs.join(", ")));
+    Some(warning)
+}
+
+pub fn position_name(position: Position) -> &'static str {
+    match position {
+        Position::Anywhere => "anywhere",
+        Position::AtStart => "--at-start",
+        Position::AtEnd => "--suffix",
+    }
+}
+
+/// Chance that a search with one-in-`odds` keys matching finds one within
+/// `attempts` keys: 1 - (1 - 1/odds)^attempts
+pub fn chance_within(attempts: u64, odds: f64) -> f64 {
+    -((attempts as f64) * (-1.0 / odds).ln_1p()).exp_m1()
+}
+
+/// How a match after `attempts` compares with the `odds` the difficulty model
+/// gave for it, e.g. "0.4x the expected 1170 attempts; 33% of searches finish
+/// this early (you got lucky)"
+pub fn luck(attempts: u64, odds: f64) -> String {
+    let chance = chance_within(attempts, odds);
+    format!(
+        "{:.2}x the expected {} attempts; {:.1}% of searches finish this early ({})",
+        attempts as f64 / odds,
+        approximate(odds),
+        chance * 100.0,
+        // Half of all searches are done by about 0.69x the expectation
+        if chance < 0.5 { "you got lucky" } else { "unlucky this time" }
+    )
+}
+
+/// Large odds in scientific notation, small ones as whole numbers
+pub fn approximate(odds: f64) -> String {
+    if odds < 1e6 {
+        format!("{:.0}", odds)
+    } else {
+        format!("{:.1e}", odds)
+    }
+}
+
+/// Several targets checked in one pass over each candidate key. The automaton
+/// always folds ASCII case; hits for case-sensitive targets are re-checked exactly.
+/// A character-class target adds each of its spellings under the same id.
+#[derive(Debug)]
+pub struct MultiMatcher {
+    automaton: AhoCorasick,
+    /// Indexed by automaton pattern id
+    targets: Vec<MultiTarget>,
+}
+
+#[derive(Debug)]
+struct MultiTarget {
+    id: usize,
+    /// One spelling of the target
+    needle: Vec<u8>,
+    case_sensitive: bool,
+}
+
+impl MultiMatcher {
+    /// `targets` holds (id, target, case_sensitive); ids are reported back by `find_all`
+    pub fn new<'a>(targets: impl IntoIterator<Item = (usize, &'a str, bool)>) -> Self {
+        let targets: Vec<MultiTarget> = targets
+            .into_iter()
+            .flat_map(|(id, target, case_sensitive)| {
+                spellings(&classes_or_literal(target, case_sensitive))
+                    .into_iter()
+                    .map(move |needle| MultiTarget { id, needle, case_sensitive })
+            })
+            .collect();
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(targets.iter().map(|target| &target.needle))
+            .expect("target automaton fits in memory");
+        Self { automaton, targets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Ids of every target occurring in `haystack`, each at most once
+    pub fn find_all(&self, haystack: &[u8], found: &mut Vec<usize>) {
+        found.clear();
+        for hit in self.automaton.find_overlapping_iter(haystack) {
+            let target = &self.targets[hit.pattern().as_usize()];
+            if found.contains(&target.id) {
+                continue;
+            }
+            if target.case_sensitive && haystack[hit.range()] != target.needle[..] {
+                continue;
+            }
+            found.push(target.id);
+        }
+    }
+}
+
+/// ASCII case-insensitive substring search. Both sides are folded here, so the
+/// needle may be given in any case; non-ASCII bytes must match exactly.
+pub fn contains_ignore_case(haystack: &[u8], needle: &[u8]) -> bool {
+    find_ignore_case(haystack, needle).is_some()
+}
+
+/// Offset of the first case-insensitive occurrence of `needle` in `haystack`
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let Some((&first, rest)) = needle.split_first() else {
+        return Some(0);
+    };
+    if needle.len() > haystack.len() {
+        return None;
+    }
+
+    let lower = first.to_ascii_lowercase();
+    let upper = first.to_ascii_uppercase();
+    // Only positions where the whole needle still fits can start a match
+    let last_start = haystack.len() - needle.len();
+
+    let mut start = 0;
+    while start <= last_start {
+        // memchr finds the next candidate first byte; the rest is checked in place
+        let candidates = &haystack[start..=last_start];
+        let offset = if lower != upper {
+            memchr::memchr2(lower, upper, candidates)
+        } else {
+            memchr::memchr(lower, candidates)
+        }?;
+
+        let pos = start + offset;
+        if haystack[pos + 1..pos + needle.len()].eq_ignore_ascii_case(rest) {
+            return Some(pos);
+        }
+        start = pos + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn anchored_matchers_only_match_at_their_position() {
+        let key = format!("{}Hello+Hello", ED25519_PREFIX);
+        assert!(Matcher::at("Hello", true, Position::AtStart).is_match(key.as_bytes()));
+        assert!(Matcher::at("hELLO", false, Position::AtStart).is_match(key.as_bytes()));
+        assert!(!Matcher::at("ello", true, Position::AtStart).is_match(key.as_bytes()));
+        assert!(!Matcher::at("Hello+Hello+", true, Position::AtStart).is_match(key.as_bytes()));
+
+        assert!(Matcher::at("+Hello", true, Position::AtEnd).is_match(key.as_bytes()));
+        assert!(Matcher::at("hELLO", false, Position::AtEnd).is_match(key.as_bytes()));
+        assert!(!Matcher::at("Hell", true, Position::AtEnd).is_match(key.as_bytes()));
+        assert!(!Matcher::at(&format!("x{}", key), true, Position::AtEnd).is_match(key.as_bytes()));
+    }
+
+    #[test]
+    fn chance_within_follows_the_geometric_distribution() {
+        assert_eq!(chance_within(0, 100.0), 0.0);
+        assert_eq!(chance_within(1, 1.0), 1.0);
+        assert!((chance_within(1, 4.0) - 0.25).abs() < 1e-12);
+        assert!((chance_within(2, 4.0) - (1.0 - 0.75 * 0.75)).abs() < 1e-12);
+        // About 63% of searches are done by the expected count, for any odds
+        assert!((chance_within(1_000_000_000, 1e9) - (1.0 - (-1.0f64).exp())).abs() < 1e-6);
+        assert!(luck(100, 1000.0).starts_with("0.10x the expected 1000 attempts; 9.5% "));
+        assert!(luck(100, 1000.0).ends_with("(you got lucky)"));
+        assert!(luck(3000, 1000.0).ends_with("(unlucky this time)"));
+    }
+
+    #[test]
+    fn at_start_odds_follow_the_first_character_rules() {
+        let odds = |target: &str, case_sensitive| anchored_odds(target, case_sensitive, Position::AtStart);
+        assert_eq!(odds("A", true), Ok(Some(16.0)));
+        assert_eq!(odds("Ab", true), Ok(Some(16.0 * 64.0)));
+        // "a" cannot appear first, but "A" can; "b" or "B" later doubles the chances
+        assert_eq!(odds("ab", false), Ok(Some(16.0 * 32.0)));
+        assert!(odds("a", true).is_err());
+        assert!(odds("Q", false).is_err());
+        assert!(odds("1", false).is_err());
+        assert!(odds("A-", true).is_err());
+        assert!(odds(&"A".repeat(44), true).is_err());
+        assert_eq!(anchored_odds("a", true, Position::Anywhere), Ok(None));
+    }
+
+    #[test]
+    fn suffix_odds_allow_any_last_character() {
+        let odds = |target: &str, case_sensitive| anchored_odds(target, case_sensitive, Position::AtEnd);
+        for c in BASE64_CHARS {
+            assert_eq!(odds(&(*c as char).to_string(), true), Ok(Some(64.0)));
+        }
+        assert_eq!(odds("ab", false), Ok(Some(32.0 * 32.0)));
+        assert!(odds("a=", true).is_err());
+        // A suffix as long as the whole variable part starts where only A-P can be
+        assert!(odds(&"a".repeat(43), true).is_err());
+        assert_eq!(odds(&"A".repeat(43), true), Ok(Some(16.0 * 64f64.powi(42))));
+    }
+
+    #[test]
+    fn match_odds_count_every_offset_and_the_fixed_prefix() {
+        // "a" cannot come first after the prefix, leaving 41 offsets for "ab"
+        let odds = match_odds("ab", true, Position::Anywhere).unwrap();
+        assert!((odds - 4096.0 / 41.0).abs() < 1e-9, "{}", odds);
+        assert_eq!(match_odds("ssh-ed", true, Position::Anywhere), Ok(1.0));
+        assert_eq!(match_odds("Ab", true, Position::AtStart), Ok(16.0 * 64.0));
+        assert!(match_odds("a-b", false, Position::Anywhere).unwrap_err().contains("not a base64 character"));
+        assert!(match_odds(&"a".repeat(LINE_LEN + 1), false, Position::Anywhere).is_err());
+    }
+
+    #[test]
+    fn difficulty_warnings_cover_the_prefix_and_slow_targets() {
+        assert_eq!(difficulty_warning("ab", true, Position::Anywhere), None);
+        assert!(difficulty_warning("c3nZAC1", false, Position::Anywhere).unwrap().contains("every key matches"));
+        assert_eq!(difficulty_warning("c3nZAC1", true, Position::Anywhere), None);
+
+        let warning = difficulty_warning("abcdefghij", true, Position::Anywhere).unwrap();
+        assert!(warning.contains("likely days"), "{}", warning);
+        assert!(warning.contains("--at-start, case-sensitive: \"abcdefghij\" can never match there"), "{}", warning);
+        assert!(warning.contains("--at-start, case-insensitive: about 1 in"), "{}", warning);
+        assert!(warning.contains("per character: a 1/64, b 1/64"), "{}", warning);
+    }
+
+    #[test]
+    fn target_classes_read_brackets_and_ranges() {
+        let classes = |target: &str, case_sensitive| target_classes(target, case_sensitive);
+        assert_eq!(classes("ab", true), Ok(vec![vec![b'a'], vec![b'b']]));
+        assert_eq!(classes("y[e3]g", true), Ok(vec![vec![b'y'], vec![b'e', b'3'], vec![b'g']]));
+        assert_eq!(classes("[0-3]", true), Ok(vec![b"0123".to_vec()]));
+        // Folded and without repeats; a '-' at either end is itself
+        assert_eq!(classes("[Eee3]", false), Ok(vec![b"e3".to_vec()]));
+        assert_eq!(classes("[Eee3]", true), Ok(vec![b"Ee3".to_vec()]));
+        assert_eq!(classes("[-a-c+]", true), Ok(vec![b"-abc+".to_vec()]));
+        assert_eq!(classes("[a-]", true), Ok(vec![b"a-".to_vec()]));
+        assert_eq!(classes("", true), Ok(vec![]));
+
+        assert!(classes("a[b", true).unwrap_err().contains("never closed"));
+        assert!(classes("a]b", true).unwrap_err().contains("without a '['"));
+        assert!(classes("a[]", true).unwrap_err().contains("no character"));
+        assert!(classes("[a[b]]", true).unwrap_err().contains("inside"));
+        assert!(classes("[z-a]", true).unwrap_err().contains("backwards"));
+        assert!(classes(&"[0-9a-f]".repeat(4), true).is_ok());
+        assert!(classes(&"[0-9a-f]".repeat(5), true).unwrap_err().contains("spellings"));
+        assert_eq!(class_label(b"e3"), "[e3]");
+        assert_eq!(class_label(b"e"), "e");
+    }
+
+    #[test]
+    fn spelling_contains_looks_inside_classes() {
+        assert!(spelling_contains("[a]b", "ab"));
+        assert!(spelling_contains("[Dd]ickson", "DICK"));
+        assert!(spelling_contains("x[0-9]y", "x7"));
+        assert!(spelling_contains("hello", "ELL"));
+        assert!(!spelling_contains("[a]b", "ba"));
+        assert!(!spelling_contains("x[0-9]y", "xay"));
+        assert!(!spelling_contains("ab", "abc"));
+        // Not a template: the text as written, as before classes existed
+        assert!(spelling_contains("a[b", "a[b"));
+    }
+
+    #[test]
+    fn template_matchers_take_any_member_at_each_character() {
+        let key = format!("{}Hello+y3gors+Yeg0rs", ED25519_PREFIX);
+        assert!(Matcher::new("y[e3]g[o0]rs", true).is_match(key.as_bytes()));
+        assert!(!Matcher::new("y[e3]g[0]rs", true).is_match(key.as_bytes()));
+        assert!(!Matcher::new("y[e3]g[o0]rs", true).with_min_count(2).is_match(key.as_bytes()));
+        assert!(Matcher::new("y[e3]g[o0]rs", false).with_min_count(2).is_match(key.as_bytes()));
+        assert!(Matcher::at("[GH]ell[a-o]", true, Position::AtStart).is_match(key.as_bytes()));
+        assert!(Matcher::at("[A-Z]eg[0o]rs", true, Position::AtEnd).is_match(key.as_bytes()));
+        assert!(!Matcher::at("[a-z]eg[0o]rs", true, Position::AtEnd).is_match(key.as_bytes()));
+        assert!(Matcher::at("[a-z]eg[0o]rs", false, Position::AtEnd).is_match(key.as_bytes()));
+    }
+
+    #[test]
+    fn class_odds_add_up_their_members() {
+        assert_eq!(anchored_odds("[ab]", true, Position::AtEnd), Ok(Some(32.0)));
+        // "a" and "A" are one member once folded
+        assert_eq!(anchored_odds("[aA]", false, Position::AtEnd), Ok(Some(32.0)));
+        assert_eq!(anchored_odds("[AQ]", true, Position::AtStart), Ok(Some(16.0)));
+        assert!(anchored_odds("[QR]", true, Position::AtStart).unwrap_err().contains("always A-P"));
+        assert!(anchored_odds("a[-_]", true, Position::AtEnd).unwrap_err().contains("none of [-_] is a base64 character"));
+        assert!(match_odds("a[b", true, Position::Anywhere).unwrap_err().contains("never closed"));
+        let literal = match_odds("ab", true, Position::Anywhere).unwrap();
+        let class = match_odds("a[b-]", true, Position::Anywhere).unwrap();
+        assert!((class - literal).abs() < 1e-9, "{} {}", class, literal);
+        let twice = match_odds("[ab][cd]", true, Position::Anywhere).unwrap();
+        assert!(twice < literal / 3.9, "{} {}", twice, literal);
+        assert!(difficulty_warning("[a-c]bcdefghijk", true, Position::Anywhere).unwrap().contains("[abc] 1/21, b 1/64"));
+    }
+
+    /// Reference implementation: compare every window
+    fn naive_contains_ignore_case(haystack: &[u8], needle: &[u8]) -> bool {
+        needle.is_empty()
+            || haystack
+                .windows(needle.len())
+                .any(|window| window.eq_ignore_ascii_case(needle))
+    }
+
+    fn naive_contains(haystack: &[u8], needle: &[u8]) -> bool {
+        needle.is_empty() || haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    /// Reference implementation: leftmost non-overlapping occurrences
+    fn naive_count(haystack: &[u8], needle: &[u8], case_sensitive: bool) -> usize {
+        let mut count = 0;
+        let mut pos = 0;
+        while pos + needle.len() <= haystack.len() {
+            let window = &haystack[pos..pos + needle.len()];
+            if (case_sensitive && window == needle) || (!case_sensitive && window.eq_ignore_ascii_case(needle)) {
+                count += 1;
+                pos += needle.len();
+            } else {
+                pos += 1;
+            }
+        }
+        count
+    }
+
+    /// Reference key line for a raw public key, via the general base64 encoder
+    fn reference_line(public_key: &[u8; 32]) -> String {
+        use base64::Engine;
+        let mut blob = b"\0\0\0\x0bssh-ed25519\0\0\0\x20".to_vec();
+        blob.extend_from_slice(public_key);
+        format!("ssh-ed25519 {}", base64::engine::general_purpose::STANDARD.encode(blob))
+    }
+
+    fn position(index: u8) -> Position {
+        [Position::Anywhere, Position::AtStart, Position::AtEnd][index as usize % 3]
+    }
+
+    #[test]
+    fn scan_handles_targets_reaching_into_the_prefix() {
+        let keys = [[0u8; 32], [0xffu8; 32]];
+        let mut columns = KeyColumns::default();
+        columns.encode(keys);
+        // The fixed prefix matches every key, whatever the batch holds
+        assert_eq!(Matcher::new("C3Nz", true).scan(&columns), 0b11);
+        assert_eq!(Matcher::new("AIA", true).scan(&columns), 0b01);
+        assert_eq!(Matcher::new("aip", false).scan(&columns), 0b10);
+        let whole = reference_line(&keys[1]);
+        let suffix = &whole[whole.len() - 44..];
+        assert_eq!(Matcher::at(suffix, true, Position::AtEnd).scan(&columns), 0b10);
+        assert_eq!(Matcher::new("", true).scan(&columns), 0b11);
+    }
+
+    #[test]
+    fn min_count_needs_separate_occurrences() {
+        let key = b"ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIx777y7777z";
+        assert!(Matcher::new("777", true).with_min_count(2).is_match(key));
+        assert!(!Matcher::new("777", true).with_min_count(3).is_match(key));
+        // "77777" holds three overlapping "777"s but only one non-overlapping
+        assert!(!Matcher::new("777", false).with_min_count(2).is_match(b"x77777x"));
+        assert!(Matcher::new("Ab", false).with_min_count(3).is_match(b"abABaB"));
+        assert!(!Matcher::new("Ab", true).with_min_count(2).is_match(b"abABaB"));
+    }
+
+    #[test]
+    fn boundaries() {
+        assert!(contains_ignore_case(b"abc", b""));
+        assert!(contains_ignore_case(b"", b""));
+        assert!(!contains_ignore_case(b"", b"a"));
+        assert!(!contains_ignore_case(b"ab", b"abc"));
+        assert!(contains_ignore_case(b"xxABC", b"abc"));
+        assert!(contains_ignore_case(b"ABCxx", b"aBc"));
+        assert!(contains_ignore_case(b"abc", b"ABC"));
+        assert!(!contains_ignore_case(b"xxAB", b"abc"));
+        assert!(contains_ignore_case(b"aaab", b"AAB"));
+        assert!(!contains_ignore_case(b"+/09", b"+/0A"));
+    }
+
+    #[test]
+    fn matcher_modes() {
+        let key = b"ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIHelloXyz";
+        assert!(Matcher::new("Hello", true).is_match(key));
+        assert!(!Matcher::new("hello", true).is_match(key));
+        assert!(Matcher::new("hello", false).is_match(key));
+        assert!(Matcher::new("HELLO", false).is_match(key));
+        assert!(!Matcher::new("world", false).is_match(key));
+    }
+
+    proptest! {
+        #[test]
+        fn min_count_agrees_with_naive_count(
+            haystack in "[aAbB7]{0,40}",
+            needle in "[aAbB7]{1,3}",
+            min_count in 1usize..5,
+            case_sensitive in any::<bool>(),
+        ) {
+            let matcher = Matcher::new(&needle, case_sensitive).with_min_count(min_count);
+            prop_assert_eq!(
+                matcher.is_match(haystack.as_bytes()),
+                naive_count(haystack.as_bytes(), needle.as_bytes(), case_sensitive) >= min_count
+            );
+        }
+
+        #[test]
+        fn ignore_case_matches_naive_on_bytes(
+            haystack in proptest::collection::vec(any::<u8>(), 0..64),
+            needle in proptest::collection::vec(any::<u8>(), 0..6),
+        ) {
+            prop_assert_eq!(
+                contains_ignore_case(&haystack, &needle),
+                naive_contains_ignore_case(&haystack, &needle)
+            );
+        }
+
+        #[test]
+        fn every_key_starts_with_the_fixed_prefix(seed in any::<[u8; 32]>()) {
+            let keypair = ssh_key::private::Ed25519Keypair::from_seed(&seed);
+            let line = ssh_key::PublicKey::from(keypair.public).to_openssh().unwrap();
+            prop_assert!(line.starts_with(ED25519_PREFIX));
+            prop_assert_eq!(line.len(), ED25519_PREFIX.len() + VARIABLE_CHARS);
+            prop_assert!(FIRST_VARIABLE_CHARS.contains(&line.as_bytes()[ED25519_PREFIX.len()]));
+            prop_assert!(BASE64_CHARS.contains(line.as_bytes().last().unwrap()));
+        }
+
+        #[test]
+        fn ignore_case_matches_naive_on_base64(
+            haystack in "[A-Za-z0-9+/]{0,68}",
+            needle in "[A-Ca-c0-2+/]{0,4}",
+        ) {
+            prop_assert_eq!(
+                contains_ignore_case(haystack.as_bytes(), needle.as_bytes()),
+                naive_contains_ignore_case(haystack.as_bytes(), needle.as_bytes())
+            );
+        }
+
+        #[test]
+        fn needle_case_does_not_matter(
+            haystack in "[A-Za-z0-9]{0,40}",
+            needle in "[A-Za-z0-9]{0,4}",
+        ) {
+            let haystack = haystack.as_bytes();
+            prop_assert_eq!(
+                contains_ignore_case(haystack, needle.to_ascii_lowercase().as_bytes()),
+                contains_ignore_case(haystack, needle.to_ascii_uppercase().as_bytes())
+            );
+        }
+
+        #[test]
+        fn multi_matcher_agrees_with_single_matchers(
+            haystack in "[A-Za-z0-9+/]{0,68}",
+            targets in proptest::collection::vec(("[A-Ca-c0-2]{1,3}", any::<bool>()), 1..6),
+        ) {
+            let multi = MultiMatcher::new(
+                targets.iter().enumerate().map(|(id, (target, sensitive))| (id, target.as_str(), *sensitive)),
+            );
+            let mut found = Vec::new();
+            multi.find_all(haystack.as_bytes(), &mut found);
+            found.sort_unstable();
+
+            let expected: Vec<usize> = targets
+                .iter()
+                .enumerate()
+                .filter(|(_, (target, sensitive))| Matcher::new(target, *sensitive).is_match(haystack.as_bytes()))
+                .map(|(id, _)| id)
+                .collect();
+            prop_assert_eq!(found, expected);
+        }
+
+        #[test]
+        fn key_columns_encode_like_openssh(seeds in proptest::collection::vec(any::<[u8; 32]>(), 1..4)) {
+            let public_keys: Vec<[u8; 32]> = seeds
+                .iter()
+                .map(|seed| ssh_key::private::Ed25519Keypair::from_seed(seed).public.0)
+                .collect();
+            let mut columns = KeyColumns::default();
+            columns.encode(public_keys.iter().copied());
+            for (lane, public_key) in public_keys.iter().enumerate() {
+                let line = ssh_key::PublicKey::from(ssh_key::public::Ed25519PublicKey(*public_key)).to_openssh().unwrap();
+                prop_assert_eq!(&columns.line(lane)[..], line.as_bytes());
+            }
+        }
+
+        #[test]
+        fn scan_agrees_with_is_match(
+            public_keys in proptest::collection::vec(any::<[u8; 32]>(), 1..=BATCH),
+            needle in "(AI|I)?[A-Da-d0-2+/]{0,3}",
+            case_sensitive in any::<bool>(),
+            position_index in any::<u8>(),
+            min_count in 1usize..3,
+        ) {
+            let matcher = Matcher::at(&needle, case_sensitive, position(position_index)).with_min_count(min_count);
+            let mut columns = KeyColumns::default();
+            columns.encode(public_keys.iter().copied());
+            let expected = public_keys
+                .iter()
+                .enumerate()
+                .filter(|(_, key)| matcher.is_match(reference_line(key).as_bytes()))
+                .fold(0u64, |mask, (lane, _)| mask | 1 << lane);
+            prop_assert_eq!(matcher.scan(&columns), expected);
+        }
+
+        #[test]
+        fn matcher_agrees_with_naive(
+            haystack in "[A-Za-z0-9+/]{0,68}",
+            needle in "[A-Ca-c0-2]{0,3}",
+        ) {
+            let haystack = haystack.as_bytes();
+            prop_assert_eq!(
+                Matcher::new(&needle, true).is_match(haystack),
+                naive_contains(haystack, needle.as_bytes())
+            );
+            prop_assert_eq!(
+                Matcher::new(&needle, false).is_match(haystack),
+                naive_contains_ignore_case(haystack, needle.as_bytes())
+            );
+        }
+
+        #[test]
+        fn templates_match_like_any_of_their_spellings(
+            public_keys in proptest::collection::vec(any::<[u8; 32]>(), 1..=BATCH),
+            template in "(AI|I)?(\\[[A-Da-d0-2+]{1,3}\\]|[A-Da-d0-2]){1,3}",
+            case_sensitive in any::<bool>(),
+            position_index in any::<u8>(),
+            min_count in 1usize..3,
+        ) {
+            let position = position(position_index);
+            let matcher = Matcher::at(&template, case_sensitive, position).with_min_count(min_count);
+            let spellings = spellings(&target_classes(&template, case_sensitive).unwrap());
+            let multi = MultiMatcher::new([(0, template.as_str(), case_sensitive)]);
+            let mut columns = KeyColumns::default();
+            columns.encode(public_keys.iter().copied());
+            let mut expected = 0u64;
+            let mut found = Vec::new();
+            for (lane, key) in public_keys.iter().enumerate() {
+                let line = reference_line(key);
+                let any_spelling = spellings
+                    .iter()
+                    .any(|spelling| Matcher::at(std::str::from_utf8(spelling).unwrap(), case_sensitive, position).is_match(line.as_bytes()));
+                if min_count == 1 {
+                    prop_assert_eq!(matcher.is_match(line.as_bytes()), any_spelling);
+                }
+                if position == Position::Anywhere {
+                    multi.find_all(line.as_bytes(), &mut found);
+                    prop_assert_eq!(!found.is_empty(), any_spelling);
+                }
+                if matcher.is_match(line.as_bytes()) {
+                    expected |= 1 << lane;
+                }
+            }
+            prop_assert_eq!(matcher.scan(&columns), expected);
+        }
+    }
+}
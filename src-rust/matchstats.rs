@@ -0,0 +1,131 @@
+//! Attempts between matches in a `--count`/`--continuous` run: min, median,
+//! mean, max and a histogram, to check how hard a target really is against the
+//! odds the difficulty model gives for it.
+
+/// Bars of the histogram printed at the end of a run
+const BUCKETS: usize = 10;
+/// Width of the longest bar
+const BAR_WIDTH: usize = 40;
+
+/// The total attempt count at each match, in the order the matches came in
+#[derive(Debug, Default)]
+pub struct MatchLog {
+    totals: Vec<u64>,
+}
+
+impl MatchLog {
+    pub fn record(&mut self, total_attempts: u64) {
+        self.totals.push(total_attempts);
+    }
+
+    /// Attempts from the start (or the previous match) to each match. Workers
+    /// report totals a batch at a time, so a match can arrive with a slightly
+    /// lower total than the one before it; totals are sorted first.
+    pub fn gaps(&self) -> Vec<u64> {
+        let mut totals = self.totals.clone();
+        totals.sort_unstable();
+        let mut previous = 0;
+        totals
+            .into_iter()
+            .map(|total| {
+                let gap = total - previous;
+                previous = total;
+                gap
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub min: u64,
+    pub median: u64,
+    pub mean: f64,
+    pub max: u64,
+}
+
+impl Summary {
+    /// None without any gaps
+    pub fn of(gaps: &[u64]) -> Option<Self> {
+        let mut sorted = gaps.to_vec();
+        sorted.sort_unstable();
+        Some(Self {
+            min: *sorted.first()?,
+            median: sorted[sorted.len() / 2],
+            mean: sorted.iter().map(|&gap| gap as f64).sum::<f64>() / sorted.len() as f64,
+            max: *sorted.last()?,
+        })
+    }
+}
+
+/// `BUCKETS` equal ranges from 0 to the largest gap: (from, to, count), `to` exclusive
+pub fn histogram(gaps: &[u64]) -> Vec<(u64, u64, usize)> {
+    let Some(&max) = gaps.iter().max() else {
+        return Vec::new();
+    };
+    let width = (max / BUCKETS as u64 + 1).max(1);
+    let mut counts = [0usize; BUCKETS];
+    for &gap in gaps {
+        counts[((gap / width) as usize).min(BUCKETS - 1)] += 1;
+    }
+    counts
+        .iter()
+        .enumerate()
+        .map(|(bucket, &count)| (bucket as u64 * width, (bucket as u64 + 1) * width, count))
+        .collect()
+}
+
+/// The end-of-run report; `odds` is the model's one-in-how-many, when it covers the target
+pub fn print(log: &MatchLog, odds: Option<f64>) {
+    let gaps = log.gaps();
+    let Some(summary) = Summary::of(&gaps) else {
+        return;
+    };
+    println!(
+        "\nAttempts per match over {} matches: min {}, median {}, mean {:.1}, max {}",
+        gaps.len(),
+        summary.min,
+        summary.median,
+        summary.mean,
+        summary.max
+    );
+    if let Some(odds) = odds {
+        println!(
+            "Expected from the difficulty model: {:.1} per match; the mean seen is {:.2}x that",
+            odds,
+            summary.mean / odds
+        );
+    }
+    let buckets = histogram(&gaps);
+    let tallest = buckets.iter().map(|&(_, _, count)| count).max().unwrap_or(1);
+    let label_width = buckets.last().map_or(1, |&(_, to, _)| to.to_string().len());
+    for (from, to, count) in buckets {
+        println!(
+            "  {:>width$} - {:<width$} {:<bar$} {}",
+            from,
+            to,
+            "#".repeat(count * BAR_WIDTH / tallest),
+            count,
+            width = label_width,
+            bar = BAR_WIDTH
+        );
+    }
+}
+
+/// `--match-stats-json`: the same numbers for other tools
+pub fn to_json(log: &MatchLog, target: &str, case_sensitive: bool, odds: Option<f64>) -> String {
+    let gaps = log.gaps();
+    let summary = Summary::of(&gaps);
+    let value = serde_json::json!({
+        "target": target,
+        "case_sensitive": case_sensitive,
+        "matches": gaps.len(),
+        "attempts_per_match": gaps,
+        "min": summary.as_ref().map(|summary| summary.min),
+        "median": summary.as_ref().map(|summary| summary.median),
+        "mean": summary.as_ref().map(|summary| summary.mean),
+        "max": summary.as_ref().map(|summary| summary.max),
+        "expected": odds,
+    });
+    serde_json::to_string_pretty(&value).expect("JSON values always serialize")
+}
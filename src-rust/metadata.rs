@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// Provenance recorded next to a generated key. Never holds secret material:
+/// only the public key, its fingerprint and how the search was run.
+pub struct KeyMetadata<'a> {
+    pub target: &'a str,
+    pub case_sensitive: bool,
+    /// The target appears in the key with exactly the case given
+    pub exact_case: bool,
+    pub attempts: u64,
+    pub duration: Duration,
+    pub workers: usize,
+    pub rng_backend: &'a str,
+    pub public_key: &'a str,
+    pub fingerprint: &'a str,
+    pub private_key_file: Option<&'a str>,
+    pub encrypted: bool,
+    pub stored_in: Vec<String>,
+    /// `--expiry`: the date the key should be replaced by, YYYY-MM-DD
+    pub expires_at: Option<String>,
+}
+
+/// Sidecar path for a public key file: `id_ed25519.pub` -> `id_ed25519.meta.json`
+pub fn output_path(public_key_file: &str) -> String {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    format!("{}.meta.json", base)
+}
+
+/// Render the sidecar document
+fn to_json(meta: &KeyMetadata) -> serde_json::Value {
+    serde_json::json!({
+        "tool": "ssh-keygen-deluxe",
+        "version": env!("CARGO_PKG_VERSION"),
+        "created_at": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "expires_at": meta.expires_at,
+        "hostname": gethostname::gethostname().to_string_lossy(),
+        "key_type": "ssh-ed25519",
+        "public_key": meta.public_key.trim(),
+        "fingerprints": {
+            "sha256": meta.fingerprint,
+        },
+        "match": {
+            "target": meta.target,
+            "case_sensitive": meta.case_sensitive,
+            "exact_case": meta.exact_case,
+        },
+        "search": {
+            "attempts": meta.attempts,
+            "duration_seconds": meta.duration.as_secs_f64(),
+            "workers": meta.workers,
+            "rng_backend": meta.rng_backend,
+        },
+        "private_key_file": meta.private_key_file,
+        "private_key_encrypted": meta.encrypted,
+        "stored_in": meta.stored_in,
+    })
+}
+
+/// The sidecar as pretty-printed JSON
+pub fn render(meta: &KeyMetadata) -> Result<String, serde_json::Error> {
+    let mut text = serde_json::to_string_pretty(&to_json(meta))?;
+    text.push('\n');
+    Ok(text)
+}
@@ -0,0 +1,73 @@
+use crate::keygen::Seed;
+use crate::seedstream::KeyStream;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::io::Read;
+use zeroize::Zeroizing;
+
+/// Domain separation for per-index seeds; changing it changes every derived key
+const DERIVATION_LABEL: &[u8] = b"ssh-keygen-deluxe/mnemonic/ed25519/v1";
+
+/// Printed before a mnemonic search; the derived key is only as strong as the phrase
+pub const WARNING: &str = "\
+WARNING: --from-mnemonic derives keys deterministically from a BIP39 phrase.
+         Anyone who learns the phrase can regenerate the private key, and a
+         phrase that is guessable, reused or stored carelessly gives the key
+         away. Only use a freshly generated 24-word phrase kept offline.";
+
+/// Deterministic stream of Ed25519 seeds: seed(i) = HMAC-SHA512(bip39_seed, label || i)[..32]
+pub struct SeedStream {
+    master: Zeroizing<[u8; 64]>,
+}
+
+impl SeedStream {
+    /// Read a BIP39 phrase from a file ("-" for stdin) and derive the master seed
+    pub fn from_phrase_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut text = Zeroizing::new(String::new());
+        if path == "-" {
+            std::io::stdin().read_to_string(&mut text)?;
+        } else {
+            std::fs::File::open(path)
+                .map_err(|e| format!("cannot read mnemonic from {}: {}", path, e))?
+                .read_to_string(&mut text)?;
+        }
+        let mnemonic = bip39::Mnemonic::parse(text.trim())
+            .map_err(|e| format!("invalid BIP39 phrase: {}", e))?;
+        if mnemonic.word_count() < 24 {
+            eprintln!(
+                "Warning: {}-word phrase; 24 words are recommended for long-lived keys",
+                mnemonic.word_count()
+            );
+        }
+        Ok(Self {
+            master: Zeroizing::new(mnemonic.to_seed("")),
+        })
+    }
+}
+
+impl KeyStream for SeedStream {
+    fn seed(&self, index: u64) -> Seed {
+        let mut mac = Hmac::<Sha512>::new_from_slice(self.master.as_ref())
+            .expect("HMAC accepts keys of any length");
+        mac.update(DERIVATION_LABEL);
+        mac.update(&index.to_be_bytes());
+        let output = Zeroizing::new(<[u8; 64]>::from(mac.finalize().into_bytes()));
+        let mut seed = Zeroizing::new([0u8; 32]);
+        seed.copy_from_slice(&output[..32]);
+        seed
+    }
+
+    fn describe(&self) -> String {
+        "BIP39 mnemonic (HMAC-SHA512 stream)".to_string()
+    }
+
+    fn index_flag(&self) -> Option<&'static str> {
+        Some("--mnemonic-index")
+    }
+}
+
+impl std::fmt::Debug for SeedStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SeedStream([REDACTED])")
+    }
+}
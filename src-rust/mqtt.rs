@@ -0,0 +1,200 @@
+//! `--mqtt mqtt://[user:password@]host[:port]/topic`: progress and the final
+//! result, public information only, published to an MQTT broker for home
+//! automation dashboards. This is just enough of MQTT 3.1.1 to publish at
+//! QoS 0 over plain TCP: CONNECT, CONNACK, PUBLISH and DISCONNECT.
+
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 1883;
+/// The broker drops a client it has heard nothing from for 1.5 times this;
+/// progress goes out far more often
+const KEEP_ALIVE_SECS: u16 = 60;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where messages go: `<topic>/progress` and `<topic>/result`
+#[derive(Clone)]
+pub struct Topic {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+    topic: String,
+}
+
+/// Leaves the password out
+impl std::fmt::Debug for Topic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl std::fmt::Display for Topic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let host = if self.host.contains(':') { format!("[{}]", self.host) } else { self.host.clone() };
+        write!(f, "mqtt://{}:{}/{}", host, self.port, self.topic)
+    }
+}
+
+impl Topic {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        if url.starts_with("mqtts://") {
+            return Err("--mqtt: mqtts:// (TLS) is not supported; use mqtt:// to a broker on a trusted network".to_string());
+        }
+        let rest = url
+            .strip_prefix("mqtt://")
+            .ok_or_else(|| format!("--mqtt: expected mqtt://[user:password@]host[:port]/topic, got \"{}\"", url))?;
+        let (authority, topic) = rest
+            .split_once('/')
+            .filter(|(_, topic)| !topic.is_empty())
+            .ok_or_else(|| format!("--mqtt: \"{}\" names no topic after the broker", url))?;
+        if topic.contains(['+', '#']) {
+            return Err(format!("--mqtt: \"{}\" is a wildcard filter, not a topic to publish to", topic));
+        }
+        let (credentials, address) = match authority.rsplit_once('@') {
+            Some((userinfo, address)) => {
+                let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+                (Some((user.to_string(), password.to_string())), address)
+            }
+            None => (None, authority),
+        };
+        let (host, port) = match address.strip_prefix('[') {
+            // [IPv6]:port
+            Some(bracketed) => {
+                let (host, port) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| format!("--mqtt: unclosed [ in \"{}\"", address))?;
+                (host, port.strip_prefix(':'))
+            }
+            None => match address.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (address, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(format!("--mqtt: \"{}\" names no broker", url));
+        }
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| format!("--mqtt: \"{}\" is not a port number", port))?,
+            None => DEFAULT_PORT,
+        };
+        Ok(Topic {
+            host: host.to_string(),
+            port,
+            credentials,
+            topic: topic.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// A connection to the broker, shared by the progress thread and the end of the run
+pub struct Publisher {
+    stream: Mutex<TcpStream>,
+    topic: String,
+}
+
+impl Publisher {
+    /// Connect and wait for the broker to accept the session
+    pub fn connect(topic: &Topic) -> io::Result<Self> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{} does not resolve", topic.host));
+        let mut stream = None;
+        for address in (topic.host.as_str(), topic.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(connected) => {
+                    stream = Some(connected);
+                    break;
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        let mut stream = stream.ok_or(last_error)?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+
+        let mut variable = Vec::new();
+        put_string(&mut variable, "MQTT");
+        variable.push(4); // protocol level 3.1.1
+        let mut flags = 0x02; // clean session
+        if topic.credentials.is_some() {
+            flags |= 0x80 | 0x40; // user name and password
+        }
+        variable.push(flags);
+        variable.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+        put_string(&mut variable, &format!("ssh-keygen-{}", std::process::id()));
+        if let Some((user, password)) = &topic.credentials {
+            put_string(&mut variable, user);
+            put_string(&mut variable, password);
+        }
+        stream.write_all(&packet(0x10, &variable))?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[..2] != [0x20, 0x02] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the broker did not answer with CONNACK"));
+        }
+        let refused = match connack[3] {
+            0 => None,
+            1 => Some("unsupported protocol version (it needs MQTT 3.1.1)"),
+            2 => Some("client identifier rejected"),
+            3 => Some("server unavailable"),
+            4 => Some("bad user name or password"),
+            5 => Some("not authorized"),
+            _ => Some("refused"),
+        };
+        if let Some(reason) = refused {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("broker refused the connection: {}", reason)));
+        }
+        Ok(Publisher {
+            stream: Mutex::new(stream),
+            topic: topic.topic.clone(),
+        })
+    }
+
+    /// Publish `payload` as JSON to `<topic>/<leaf>`, at most once (QoS 0).
+    /// A retained message is what the broker hands to anyone subscribing later.
+    pub fn publish(&self, leaf: &str, payload: &Value, retain: bool) -> io::Result<()> {
+        let mut body = Vec::new();
+        put_string(&mut body, &format!("{}/{}", self.topic, leaf));
+        body.extend_from_slice(payload.to_string().as_bytes());
+        let mut stream = self.stream.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stream.write_all(&packet(0x30 | retain as u8, &body))?;
+        stream.flush()
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let stream = self.stream.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Without DISCONNECT the broker treats the session as lost; nothing
+        // depends on it hearing this
+        let _ = stream.write_all(&[0xE0, 0x00]);
+    }
+}
+
+/// A length-prefixed UTF-8 string
+fn put_string(buffer: &mut Vec<u8>, text: &str) {
+    buffer.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(text.as_bytes());
+}
+
+/// Fixed header (type and flags, then the remaining length as a base-128
+/// varint) followed by the body
+fn packet(first_byte: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![first_byte];
+    let mut remaining = body.len();
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if remaining == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
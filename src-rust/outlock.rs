@@ -0,0 +1,74 @@
+//! Advisory locks on output names, so two instances started with the same
+//! output never write over each other's keys. A run holds `<output>.lock`
+//! until it ends; the operating system releases the lock if it dies, so a
+//! crashed run never leaves a name locked.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Held for as long as the run may write `<output>*`; removes the lock file when dropped
+#[derive(Debug)]
+pub struct OutputLock {
+    path: PathBuf,
+    // Closing it releases the lock
+    _file: File,
+}
+
+/// Another instance holds the lock, with its process id when it could be read
+#[derive(Debug)]
+pub struct Held {
+    pub pid: Option<u32>,
+}
+
+impl OutputLock {
+    /// Lock `<output>.lock` without waiting
+    pub fn acquire(output: &str) -> io::Result<Result<Self, Held>> {
+        let path = PathBuf::from(format!("{}.lock", output));
+        loop {
+            let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(false).open(&path)?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(fs::TryLockError::WouldBlock) => {
+                    let mut pid = String::new();
+                    // Where locks are mandatory the holder's file cannot be read
+                    let _ = file.read_to_string(&mut pid);
+                    return Ok(Err(Held { pid: pid.trim().parse().ok() }));
+                }
+                Err(fs::TryLockError::Error(e)) => return Err(e),
+            }
+            // The holder removes the file as it finishes; a lock taken on the
+            // removed file guards nothing, so start over with a fresh one
+            if !is_current(&file, &path) {
+                continue;
+            }
+            file.set_len(0)?;
+            write!(file, "{}", std::process::id())?;
+            return Ok(Ok(OutputLock { path, _file: file }));
+        }
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        // Removed while still locked, so nobody can lock it in between
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether `file` is still the one at `path`
+#[cfg(unix)]
+fn is_current(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+/// Windows cannot remove a file another process has open, so the file at
+/// `path` is always the locked one
+#[cfg(not(unix))]
+fn is_current(_file: &File, _path: &Path) -> bool {
+    true
+}
@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once from `--show-private`; everything that writes to the terminal
+/// goes through this module so the policy is enforced in one place
+static SHOW_PRIVATE: AtomicBool = AtomicBool::new(false);
+/// Set once from `--raw-numbers`, for scripts that read the progress line
+static RAW_NUMBERS: AtomicBool = AtomicBool::new(false);
+
+const BEGIN_MARKER: &str = "-----BEGIN ";
+const PRIVATE_KEY_SUFFIX: &str = "PRIVATE KEY-----";
+const END_MARKER: &str = "-----END ";
+
+pub fn set_show_private(show: bool) {
+    SHOW_PRIVATE.store(show, Ordering::Relaxed);
+}
+
+pub fn show_private() -> bool {
+    SHOW_PRIVATE.load(Ordering::Relaxed)
+}
+
+pub fn set_raw_numbers(raw: bool) {
+    RAW_NUMBERS.store(raw, Ordering::Relaxed);
+}
+
+/// An attempt count for people: "987", "123,456", "12.4 M", "1.24 B";
+/// unchanged with `--raw-numbers`
+pub fn count(n: u64) -> String {
+    if RAW_NUMBERS.load(Ordering::Relaxed) {
+        return n.to_string();
+    }
+    const SUFFIXES: [(f64, &str); 4] = [(1e15, "Q"), (1e12, "T"), (1e9, "B"), (1e6, "M")];
+    let value = n as f64;
+    for (scale, suffix) in SUFFIXES {
+        if value >= scale {
+            // Three significant figures, whatever the size
+            let scaled = value / scale;
+            let decimals = if scaled >= 100.0 { 0 } else if scaled >= 10.0 { 1 } else { 2 };
+            return format!("{:.*} {}", decimals, scaled, suffix);
+        }
+    }
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Keys per second the same way, e.g. "3.12 M/s"
+pub fn rate(keys_per_second: f64) -> String {
+    format!("{}/s", count(keys_per_second.round() as u64))
+}
+
+/// Replace any PEM private key block in `text` unless `--show-private` was given.
+/// A block with no END line is redacted to the end of the text.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    if show_private() || !text.contains(PRIVATE_KEY_SUFFIX) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(begin) = find_private_marker(rest, BEGIN_MARKER) {
+        out.push_str(&rest[..begin]);
+        out.push_str("[REDACTED PRIVATE KEY]");
+        rest = &rest[begin..];
+        match find_private_marker(rest, END_MARKER) {
+            Some(end) => {
+                let after = rest[end..].find(PRIVATE_KEY_SUFFIX).unwrap() + PRIVATE_KEY_SUFFIX.len();
+                rest = &rest[end + after..];
+            }
+            None => rest = "",
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Offset of the first `marker ... PRIVATE KEY-----` line in `text`
+fn find_private_marker(text: &str, marker: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(pos) = text[offset..].find(marker) {
+        let start = offset + pos;
+        let line = text[start..].lines().next().unwrap_or("");
+        if line.trim_end().ends_with(PRIVATE_KEY_SUFFIX) {
+            return Some(start);
+        }
+        offset = start + marker.len();
+    }
+    None
+}
+
+/// Print the private key to stdout; the only call that may ever do so, and
+/// only when `--show-private` was passed
+pub fn print_private_key(pem: &str) {
+    if show_private() {
+        print!("{}", pem);
+    }
+}
+
+/// Report an error on stderr with any key material redacted
+pub fn error(message: impl std::fmt::Display) {
+    eprintln!("Error: {}", redact(&message.to_string()));
+}
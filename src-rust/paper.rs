@@ -0,0 +1,133 @@
+//! `--emit mnemonic` and `ssh-keygen restore-from-mnemonic`: the 32-byte Ed25519
+//! seed written as 24 BIP39 words for a cold paper backup, and the key rebuilt
+//! from them. The words are the seed itself in BIP39's encoding (with its
+//! checksum), not a wallet seed run through PBKDF2, so other BIP39 tools and
+//! `--from-mnemonic` derive different keys from the same words.
+
+use crate::error::Error;
+use crate::formats::{self, KeyFormat};
+use crate::passphrase::{self, PassphraseSource};
+use crate::permissions;
+use ed25519_dalek::SigningKey;
+use ssh_key::HashAlg;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Printed whenever a backup sheet is written
+pub const WARNING: &str = "\
+WARNING: the mnemonic file holds the private key as 24 words, unencrypted,
+         whatever --passphrase says. Anyone who reads the words has the key.
+         Write or print it onto paper, keep the paper offline (a safe, not a
+         photo or a cloud drive), then delete the file.";
+
+/// `<name>.mnemonic.txt`, named after the public key file
+pub fn output_path(public_key_file: &str) -> String {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    format!("{}.mnemonic.txt", base)
+}
+
+/// The backup sheet: what the words are for, then the words numbered four to a
+/// line so a missing or swapped one stands out when copying by hand
+pub fn sheet(signing_key: &SigningKey, public_key: &str, fingerprint: &str) -> Result<Zeroizing<String>, Error> {
+    let seed = Zeroizing::new(signing_key.to_bytes());
+    let mnemonic = bip39::Mnemonic::from_entropy(seed.as_slice()).map_err(|e| Error::io("encoding mnemonic", e))?;
+    let words: Vec<&str> = mnemonic.words().collect();
+    let mut text = Zeroizing::new(format!(
+        "# Paper backup of the SSH key {}\n\
+         # {}\n\
+         # These 24 words are the private key. Restore it with\n\
+         #   ssh-keygen restore-from-mnemonic <this file>\n\n",
+        fingerprint,
+        public_key.trim()
+    ));
+    for (row, chunk) in words.chunks(4).enumerate() {
+        let line: Vec<String> = chunk
+            .iter()
+            .enumerate()
+            .map(|(column, word)| format!("{:>2}. {:<9}", row * 4 + column + 1, word))
+            .collect();
+        text.push_str(line.join("  ").trim_end());
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// Options for `ssh-keygen restore-from-mnemonic`
+pub struct RestoreOptions<'a> {
+    /// A backup sheet or the bare words; `-` reads standard input
+    pub input: &'a str,
+    pub output: &'a str,
+    pub comment: &'a str,
+    /// Encrypt the restored key
+    pub passphrase: Option<PassphraseSource>,
+}
+
+/// Rebuild the OpenSSH key pair from the 24 words
+pub fn restore(options: &RestoreOptions) -> Result<(), Error> {
+    let mut text = Zeroizing::new(String::new());
+    if options.input == "-" {
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| Error::io("reading standard input", e))?;
+    } else {
+        fs::File::open(options.input)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| Error::config(format!("{}: cannot read: {}", options.input, e)))?;
+    }
+    // The words of a sheet, skipping its comments and numbers
+    let words = Zeroizing::new(
+        text.lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace)
+            .filter(|word| !word.trim_end_matches('.').chars().all(|c| c.is_ascii_digit()))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase(),
+    );
+    let mnemonic = bip39::Mnemonic::parse(words.as_str())
+        .map_err(|e| Error::config(format!("{}: not a valid mnemonic: {}", options.input, e)))?;
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    let seed: Zeroizing<[u8; 32]> = Zeroizing::new(entropy.as_slice().try_into().map_err(|_| {
+        Error::config(format!(
+            "{}: {} words encode {} bytes, but a key backup has 24 words (32 bytes)",
+            options.input,
+            mnemonic.word_count(),
+            entropy.len()
+        ))
+    })?);
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    let public_key_file = format!("{}.pub", options.output);
+    for path in [options.output, public_key_file.as_str()] {
+        if Path::new(path).exists() {
+            return Err(Error::config(format!("{} already exists; choose another file with --output", path)));
+        }
+    }
+    let passphrase = match &options.passphrase {
+        Some(source) => passphrase::resolve(source).map_err(Error::config)?,
+        None => None,
+    };
+    let private = formats::encode(
+        &signing_key,
+        options.comment,
+        KeyFormat::OpenSsh,
+        passphrase.as_ref().map(|p| p.expose()),
+    )
+    .map_err(|e| Error::io("encoding private key", e))?;
+    let public = formats::openssh_private_key(&signing_key, options.comment)
+        .map_err(|e| Error::io("encoding public key", e))?
+        .public_key()
+        .clone();
+    let public_line = public.to_openssh().map_err(|e| Error::io("encoding public key", e))?;
+
+    permissions::write_private(options.output.as_ref(), private.as_bytes())
+        .map_err(|e| Error::io(format!("writing {}", options.output), e))?;
+    fs::write(&public_key_file, &public_line)
+        .map_err(|e| Error::io(format!("writing {}", public_key_file), e))?;
+    println!("Restored {} and {}", options.output, public_key_file);
+    println!("Fingerprint: {}", public.fingerprint(HashAlg::Sha256));
+    println!("Public key: {}", public_line);
+    Ok(())
+}
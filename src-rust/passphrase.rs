@@ -0,0 +1,82 @@
+use crate::secret::SecretString;
+use std::io::BufRead;
+use zeroize::Zeroizing;
+
+/// Where the private key passphrase comes from
+#[derive(Debug, Clone)]
+pub enum PassphraseSource {
+    /// Prompt twice on the terminal with echo disabled
+    Prompt,
+    /// Given literally on the command line (visible in process listings)
+    Value(SecretString),
+    /// First line read from an inherited file descriptor
+    Fd(i32),
+    /// Read from an environment variable
+    Env(String),
+}
+
+/// Resolve the passphrase. Returns None for an empty passphrase, which like
+/// ssh-keygen means "leave the key unencrypted".
+pub fn resolve(source: &PassphraseSource) -> Result<Option<SecretString>, Box<dyn std::error::Error>> {
+    let passphrase = match source {
+        PassphraseSource::Prompt => {
+            let first = SecretString::new(prompt("Enter passphrase (empty for no passphrase): ")?);
+            let second = SecretString::new(prompt("Enter same passphrase again: ")?);
+            if first.expose() != second.expose() {
+                return Err("passphrases do not match".into());
+            }
+            first
+        }
+        other => read_source(other, "Enter passphrase: ")?,
+    };
+
+    Ok((!passphrase.expose().is_empty()).then_some(passphrase))
+}
+
+/// Read the passphrase of an existing key; prompts once instead of twice
+pub fn resolve_existing(
+    source: &PassphraseSource,
+    path: &str,
+) -> Result<SecretString, Box<dyn std::error::Error>> {
+    read_source(source, &format!("Enter passphrase for {}: ", path))
+}
+
+fn read_source(
+    source: &PassphraseSource,
+    prompt_message: &str,
+) -> Result<SecretString, Box<dyn std::error::Error>> {
+    Ok(match source {
+        PassphraseSource::Prompt => SecretString::new(prompt(prompt_message)?),
+        PassphraseSource::Value(value) => value.clone(),
+        PassphraseSource::Fd(fd) => read_fd(*fd)?,
+        PassphraseSource::Env(var) => SecretString::new(
+            std::env::var(var).map_err(|_| format!("environment variable {} is not set", var))?,
+        ),
+    })
+}
+
+/// Prompt on the controlling terminal with echo disabled
+fn prompt(message: &str) -> Result<String, String> {
+    rpassword::prompt_password(message).map_err(|e| {
+        format!("cannot prompt for a passphrase ({}); use --passphrase-fd or --passphrase-env", e)
+    })
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<SecretString, Box<dyn std::error::Error>> {
+    use std::os::unix::io::FromRawFd;
+
+    if fd < 0 {
+        return Err(format!("invalid passphrase file descriptor {}", fd).into());
+    }
+    // SAFETY: the caller handed us this descriptor explicitly; we take ownership and close it
+    let file = unsafe { std::fs::File::from_raw_fd(fd) };
+    let mut line = Zeroizing::new(String::new());
+    std::io::BufReader::new(file).read_line(&mut line)?;
+    Ok(SecretString::new(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<SecretString, Box<dyn std::error::Error>> {
+    Err("--passphrase-fd is only supported on Unix; use --passphrase-env instead".into())
+}
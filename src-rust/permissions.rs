@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Create (or truncate) a file that will hold secret material. It is owner-only
+/// before anything is written: mode 600 from creation on Unix, and the owner-only
+/// ACL (which also fixes an existing file's mode) before the first byte.
+pub fn create_private(path: &Path) -> Result<File, Box<dyn std::error::Error>> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let file = options.open(path)?;
+    restrict_to_owner(path)?;
+    Ok(file)
+}
+
+/// `fs::write` for secret material, through `create_private`
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    create_private(path)?.write_all(contents)?;
+    Ok(())
+}
+
+/// Restrict a private key file so only the current user can read it, as
+/// OpenSSH requires before it will use the key
+#[cfg(unix)]
+pub fn restrict_to_owner(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+/// Restrict a private key file so only the current user can read it, as
+/// OpenSSH requires before it will use the key
+#[cfg(windows)]
+pub fn restrict_to_owner(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // Mode bits mean nothing here; OpenSSH for Windows checks the DACL and
+    // rejects keys readable by anyone but the owner, SYSTEM or Administrators.
+    // The whole DACL is replaced, explicit entries of a truncated file as well
+    // as inherited ones, by a protected one granting the user this process
+    // runs as full control. The user comes from the process token, so a
+    // service running as SYSTEM gets SYSTEM and the environment has no say.
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::Authorization::{SetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        AddAccessAllowedAce, GetLengthSid, GetTokenInformation, InitializeAcl, TokenUser, ACCESS_ALLOWED_ACE, ACL,
+        ACL_REVISION, DACL_SECURITY_INFORMATION, PROTECTED_DACL_SECURITY_INFORMATION, TOKEN_QUERY, TOKEN_USER,
+    };
+    use windows_sys::Win32::Storage::FileSystem::FILE_ALL_ACCESS;
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    let failed = |call: &str| format!("{} failed: {}", call, std::io::Error::last_os_error());
+
+    // TOKEN_USER and the SID it points to, in u64s to keep them aligned
+    let mut token_user = [0u64; 64];
+    // SAFETY: the token handle is closed once read, and GetTokenInformation
+    // writes at most the buffer length it is given
+    unsafe {
+        let mut token: HANDLE = std::ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return Err(failed("OpenProcessToken").into());
+        }
+        let mut written = 0;
+        let read = GetTokenInformation(
+            token,
+            TokenUser,
+            token_user.as_mut_ptr().cast(),
+            std::mem::size_of_val(&token_user) as u32,
+            &mut written,
+        );
+        CloseHandle(token);
+        if read == 0 {
+            return Err(failed("GetTokenInformation").into());
+        }
+    }
+    // SAFETY: the buffer now starts with a TOKEN_USER whose SID lies within it
+    let sid = unsafe { (*token_user.as_ptr().cast::<TOKEN_USER>()).User.Sid };
+
+    // An ACL with a single ACE, whose SID takes the place of its SidStart field
+    // SAFETY: sid is a valid SID from the token
+    let sid_len = unsafe { GetLengthSid(sid) } as usize;
+    let acl_len =
+        std::mem::size_of::<ACL>() + std::mem::size_of::<ACCESS_ALLOWED_ACE>() - std::mem::size_of::<u32>() + sid_len;
+    let mut acl_buffer = vec![0u64; acl_len.div_ceil(8)];
+    let acl = acl_buffer.as_mut_ptr().cast::<ACL>();
+    // SAFETY: acl points to acl_len writable, aligned bytes
+    unsafe {
+        if InitializeAcl(acl, acl_len as u32, ACL_REVISION) == 0 {
+            return Err(failed("InitializeAcl").into());
+        }
+        if AddAccessAllowedAce(acl, ACL_REVISION, FILE_ALL_ACCESS, sid) == 0 {
+            return Err(failed("AddAccessAllowedAce").into());
+        }
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    // SAFETY: wide is NUL-terminated and acl was built above; owner, group and
+    // SACL are left as they are
+    let status = unsafe {
+        SetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            acl,
+            std::ptr::null(),
+        )
+    };
+    if status != 0 {
+        return Err(format!(
+            "could not restrict {}: {}",
+            path.display(),
+            std::io::Error::from_raw_os_error(status as i32)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn restrict_to_owner(_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(())
+}
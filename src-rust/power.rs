@@ -0,0 +1,56 @@
+//! Power source, for `--pause-on-battery`: searches left running on a laptop
+//! stop drawing on the battery and carry on once it is plugged back in.
+
+/// Whether the machine is running on battery; None where that cannot be told
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn on_battery() -> Option<bool> {
+    let mut charger_online = false;
+    let mut discharging = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" | "USB" => charger_online |= read("online").trim() == "1",
+            "Battery" => discharging |= read("status").trim() == "Discharging",
+            _ => {}
+        }
+    }
+    // An online charger means AC whatever the batteries report; no battery at
+    // all (a desktop or server) means AC too
+    Some(discharging && !charger_online)
+}
+
+/// `pmset -g batt` names the source on its first line
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let first = text.lines().next()?;
+    if first.contains("'Battery Power'") {
+        Some(true)
+    } else if first.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+pub fn on_battery() -> Option<bool> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    // SAFETY: SYSTEM_POWER_STATUS is plain data, so all zeroes is a valid value
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    // SAFETY: GetSystemPowerStatus only writes the provided struct
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    match status.ACLineStatus {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", windows)))]
+pub fn on_battery() -> Option<bool> {
+    None
+}
@@ -0,0 +1,193 @@
+//! Key expiry: `--expiry` records when a key should be replaced, in its comment
+//! (`created=2026-10-16 expires=2027-04-14`) and in the metadata sidecar, and
+//! `ssh-keygen check-rotation` finds the keys that are due.
+
+use crate::error::Error;
+use crate::install::key_identity;
+use chrono::{Days, NaiveDate};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// When a key expires: a lifetime counted from its creation, or a fixed date
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expiry {
+    After(Days),
+    On(NaiveDate),
+}
+
+impl Expiry {
+    /// `180d`, `26w`, `1y` (365 days) or a date, `2027-04-01`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Ok(Expiry::On(date));
+        }
+        parse_days(text)
+            .map(Expiry::After)
+            .ok_or_else(|| format!("\"{}\" is not a lifetime (e.g. 180d, 26w, 1y) or a date (YYYY-MM-DD)", text))
+    }
+
+    pub fn date(self, created: NaiveDate) -> NaiveDate {
+        match self {
+            Expiry::After(days) => created.checked_add_days(days).unwrap_or(NaiveDate::MAX),
+            Expiry::On(date) => date,
+        }
+    }
+}
+
+/// A number of days, weeks or years: `180d`, `26w`, `1y`
+pub fn parse_days(text: &str) -> Option<Days> {
+    let unit = text.chars().last()?;
+    let count: u64 = text[..text.len() - unit.len_utf8()].parse().ok().filter(|count| *count > 0)?;
+    let days = match unit {
+        'd' => count,
+        'w' => count.checked_mul(7)?,
+        'y' => count.checked_mul(365)?,
+        _ => return None,
+    };
+    Some(Days::new(days))
+}
+
+/// The key comment recording both dates
+pub fn comment(created: NaiveDate, expires: NaiveDate) -> String {
+    format!("created={} expires={}", created, expires)
+}
+
+/// The expiry date recorded in a key comment or authorized_keys line
+fn recorded_expiry(line: &str) -> Option<NaiveDate> {
+    line.split_whitespace()
+        .find_map(|word| word.strip_prefix("expires="))
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+/// Options for `ssh-keygen check-rotation`
+pub struct CheckOptions<'a> {
+    /// Files or directories; directories are walked for `*.pub` and `*.meta.json`
+    pub paths: Vec<&'a str>,
+    /// Also list keys expiring this soon
+    pub within: Option<Days>,
+    pub today: NaiveDate,
+}
+
+/// A key with a recorded expiry
+struct Recorded {
+    file: PathBuf,
+    expires: NaiveDate,
+}
+
+/// List the keys past their recorded expiry (and, with `within`, those due
+/// soon); fails if any has expired, so a cron job or CI check notices
+pub fn run(options: &CheckOptions) -> Result<(), Error> {
+    if options.paths.is_empty() {
+        return Err(Error::config("no paths to check"));
+    }
+    let mut files = Vec::new();
+    for path in &options.paths {
+        let path = Path::new(path);
+        if !path.exists() {
+            return Err(Error::config(format!("{}: no such file or directory", path.display())));
+        }
+        if path.is_dir() {
+            collect_files(path, &mut files);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    // A key with both a sidecar and a commented .pub is listed once, from the sidecar
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
+    let (sidecars, others): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        files.iter().partition(|file| file.to_string_lossy().ends_with(".meta.json"));
+    for file in sidecars {
+        let Some((identity, expires)) = read_sidecar(file) else {
+            continue;
+        };
+        if seen.insert(identity) {
+            // Listed as the key it describes, when that is next to it
+            let public = PathBuf::from(file.to_string_lossy().replace(".meta.json", ".pub"));
+            let file = if public.exists() { public } else { file.clone() };
+            keys.push(Recorded { file, expires });
+        }
+    }
+    for file in others {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Warning: skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        for line in text.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+            if let (Some(identity), Some(expires)) = (key_identity(line), recorded_expiry(line)) {
+                if seen.insert(identity) {
+                    keys.push(Recorded { file: file.clone(), expires });
+                }
+            }
+        }
+    }
+    keys.sort_by_key(|key| key.expires);
+
+    let due_by = options.within.and_then(|days| options.today.checked_add_days(days)).unwrap_or(options.today);
+    let mut expired = 0;
+    let mut due = 0;
+    for key in &keys {
+        let days = (key.expires - options.today).num_days();
+        let (status, when) = if days < 0 {
+            expired += 1;
+            ("EXPIRED", format!("{} days ago", -days))
+        } else if key.expires <= due_by {
+            due += 1;
+            ("due", if days == 0 { "today".to_string() } else { format!("in {} days", days) })
+        } else {
+            continue;
+        };
+        println!("{:<8} {}  {:<14} {}", status, key.expires, when, key.file.display());
+    }
+
+    let mut summary = format!("Checked {} keys with a recorded expiry: {} expired", keys.len(), expired);
+    if options.within.is_some() {
+        summary.push_str(&format!(", {} due within {} days", due, (due_by - options.today).num_days()));
+    }
+    println!("{}", summary);
+    if expired > 0 {
+        return Err(Error::RotationDue(format!(
+            "{} key{} past {} expiry; generate replacements and retire them",
+            expired,
+            if expired == 1 { " is" } else { "s are" },
+            if expired == 1 { "its" } else { "their" }
+        )));
+    }
+    Ok(())
+}
+
+/// The key and expiry a `<key>.meta.json` sidecar records
+fn read_sidecar(file: &Path) -> Option<(String, NaiveDate)> {
+    let document: serde_json::Value = serde_json::from_str(&fs::read_to_string(file).ok()?).ok()?;
+    let expires = NaiveDate::parse_from_str(document["expires_at"].as_str()?, "%Y-%m-%d").ok()?;
+    Some((key_identity(document["public_key"].as_str()?)?, expires))
+}
+
+/// `*.pub`, `*.meta.json` and `authorized_keys*` files below `dir`, in a stable order
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: skipping {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect();
+    paths.sort();
+
+    for path in paths {
+        // Do not follow directory symlinks, so loops cannot recurse forever
+        let is_dir = fs::symlink_metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if is_dir {
+            collect_files(&path, files);
+        } else if name.ends_with(".pub") || name.ends_with(".meta.json") || name.starts_with("authorized_keys") {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! `--rpc-stdio`: line-delimited JSON-RPC 2.0 on stdin and stdout, a stable
+//! interface for GUI frontends and editor plugins. Each request line gets one
+//! response line (none for notifications, which have no id); a running search
+//! adds `progress` (once subscribed), `match` and `finished` notifications.
+//! stdout carries nothing else in this mode.
+
+use crate::matching::Position;
+use serde_json::{json, Value};
+use std::io::Write;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// `start` while a search is running
+pub const BUSY: i64 = -32000;
+/// `cancel` with no search running
+pub const IDLE: i64 = -32001;
+
+/// What a request asks for
+#[derive(Debug, PartialEq)]
+pub enum Method {
+    /// Start a search; only one runs at a time
+    Start(Start),
+    /// Stop the running search; it ends with a `finished` notification
+    Cancel,
+    Status,
+    /// Turn the once-a-second `progress` notifications on or off
+    Subscribe { progress: bool },
+    /// The odds of each key matching, for `start` params; threads and output
+    /// are ignored
+    Estimate(Start),
+}
+
+/// `start` params
+#[derive(Debug, PartialEq)]
+pub struct Start {
+    pub target: String,
+    /// `"ignore_case": true` turns this off
+    pub case_sensitive: bool,
+    /// `"position"`: "anywhere" (default), "start" or "end"
+    pub position: Position,
+    pub threads: Option<usize>,
+    /// Private key file; the public key goes next to it with `.pub`
+    pub output: Option<String>,
+}
+
+/// One request line; `id` is None for a notification
+#[derive(Debug)]
+pub struct Request {
+    pub id: Option<Value>,
+    pub method: Method,
+}
+
+/// Why a request line was refused: the id to answer (null if it could not be
+/// read), the error code and a message
+#[derive(Debug)]
+pub struct Failure {
+    pub id: Value,
+    pub code: i64,
+    pub message: String,
+}
+
+impl Request {
+    pub fn parse(line: &str) -> Result<Self, Failure> {
+        let fail = |id: &Value, code, message: String| Failure {
+            id: id.clone(),
+            code,
+            message,
+        };
+        let message: Value = serde_json::from_str(line).map_err(|e| fail(&Value::Null, PARSE_ERROR, e.to_string()))?;
+        let id = message.get("id").cloned();
+        let reply_to = id.clone().unwrap_or(Value::Null);
+        if message.get("jsonrpc") != Some(&json!("2.0")) {
+            return Err(fail(&reply_to, INVALID_REQUEST, "expected \"jsonrpc\": \"2.0\"".to_string()));
+        }
+        let Some(name) = message.get("method").and_then(Value::as_str) else {
+            return Err(fail(&reply_to, INVALID_REQUEST, "expected a \"method\" string".to_string()));
+        };
+        let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+        let method = match name {
+            "start" => Method::Start(Start::parse(&params).map_err(|e| fail(&reply_to, INVALID_PARAMS, e))?),
+            "cancel" => Method::Cancel,
+            "status" => Method::Status,
+            "estimate" => Method::Estimate(Start::parse(&params).map_err(|e| fail(&reply_to, INVALID_PARAMS, e))?),
+            "subscribe" => Method::Subscribe {
+                progress: params.get("progress").and_then(Value::as_bool).unwrap_or(true),
+            },
+            _ => return Err(fail(&reply_to, METHOD_NOT_FOUND, format!("unknown method \"{}\"", name))),
+        };
+        Ok(Request { id, method })
+    }
+}
+
+impl Start {
+    fn parse(params: &Value) -> Result<Self, String> {
+        let target = match params.get("target").and_then(Value::as_str) {
+            Some(target) if !target.is_empty() => target.to_string(),
+            _ => return Err("start needs a non-empty \"target\"".to_string()),
+        };
+        let position = match params.get("position").and_then(Value::as_str) {
+            None | Some("anywhere") => Position::Anywhere,
+            Some("start") => Position::AtStart,
+            Some("end") => Position::AtEnd,
+            Some(other) => return Err(format!("\"position\" is anywhere, start or end, not \"{}\"", other)),
+        };
+        let threads = match params.get("threads") {
+            None => None,
+            Some(threads) => match threads.as_u64() {
+                Some(count @ 1..=4096) => Some(count as usize),
+                _ => return Err("\"threads\" is a worker count from 1 to 4096".to_string()),
+            },
+        };
+        Ok(Start {
+            target,
+            case_sensitive: !params.get("ignore_case").and_then(Value::as_bool).unwrap_or(false),
+            position,
+            threads,
+            output: params.get("output").and_then(Value::as_str).map(str::to_string),
+        })
+    }
+}
+
+pub fn respond(id: &Value, result: Value) {
+    send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+}
+
+pub fn error(id: &Value, code: i64, message: &str) {
+    send(&json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }));
+}
+
+pub fn notify(method: &str, params: Value) {
+    send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+}
+
+/// One line; holding the stdout lock keeps the request loop and search threads
+/// from interleaving
+fn send(message: &Value) {
+    let mut stdout = std::io::stdout().lock();
+    // A client that went away finds out on its end; stdin closing stops the search
+    let _ = writeln!(stdout, "{}", message).and_then(|()| stdout.flush());
+}
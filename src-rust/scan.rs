@@ -0,0 +1,108 @@
+use crate::error::Error;
+use crate::install::key_identity;
+use crate::matching::Matcher;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Options for `ssh-keygen scan`
+pub struct ScanOptions<'a> {
+    pub target: &'a str,
+    pub case_sensitive: bool,
+    /// Files or directories; directories are walked for key files
+    pub paths: Vec<&'a str>,
+}
+
+/// Report existing public keys that already contain the target
+pub fn run(options: &ScanOptions) -> Result<(), Error> {
+    if options.target.is_empty() {
+        return Err(Error::config("target sequence cannot be empty"));
+    }
+    if options.paths.is_empty() {
+        return Err(Error::config("no paths to scan"));
+    }
+    let matcher = Matcher::new(options.target, options.case_sensitive);
+
+    let mut files = Vec::new();
+    for path in &options.paths {
+        let path = Path::new(path);
+        if !path.exists() {
+            return Err(Error::config(format!("{}: no such file or directory", path.display())));
+        }
+        if path.is_dir() {
+            collect_key_files(path, &mut files);
+        } else {
+            // Files named explicitly are scanned whatever they are called
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut keys = 0;
+    let mut matches = 0;
+    for file in &files {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Warning: skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Match on "<type> <base64>" only, exactly like generation does
+            let Some(identity) = key_identity(line) else {
+                continue;
+            };
+            keys += 1;
+            if matcher.is_match(identity.as_bytes()) {
+                matches += 1;
+                println!("{}:{}: {}", file.display(), number + 1, line);
+            }
+        }
+    }
+
+    println!(
+        "Scanned {} keys in {} files: {} contain \"{}\"",
+        keys,
+        files.len(),
+        matches,
+        options.target
+    );
+    if matches == 0 {
+        return Err(Error::TargetNotFound(format!(
+            "no existing key contains \"{}\"",
+            options.target
+        )));
+    }
+    Ok(())
+}
+
+/// `*.pub` and `authorized_keys*` files below `dir`, in a stable order
+fn collect_key_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: skipping {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect();
+    paths.sort();
+
+    for path in paths {
+        // Do not follow directory symlinks, so loops cannot recurse forever
+        let is_dir = fs::symlink_metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
+        if is_dir {
+            collect_key_files(&path, files);
+        } else if is_key_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn is_key_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name.ends_with(".pub") || name.starts_with("authorized_keys")
+}
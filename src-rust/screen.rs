@@ -0,0 +1,129 @@
+//! `--screen`: a last look at a match before it is accepted. Keys end up pasted
+//! into wikis and tickets along with their fingerprints and randomart, so the
+//! key text and every rendering `ssh-keygen fingerprint` prints are checked for
+//! unwanted words, from the packaged list and `--screen-file`, and a key showing
+//! one anywhere is skipped like a non-match.
+
+use crate::fingerprint::{self, FingerprintFormat};
+use crate::matching::{self, ED25519_PREFIX};
+use aho_corasick::AhoCorasick;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const PACKAGED: &str = include_str!("profanity.txt");
+
+/// Where a key is shown, in the order they are screened
+const PLACES: [&str; 6] = ["key", "SHA256 fingerprint", "hex fingerprint", "bubble-babble fingerprint", "MD5 fingerprint", "randomart"];
+
+#[derive(Debug)]
+pub struct Screen {
+    automaton: AhoCorasick,
+    entries: Vec<String>,
+    /// Keys skipped, by the first place an unwanted word showed up
+    rejected: [AtomicU64; PLACES.len()],
+}
+
+impl Screen {
+    /// The packaged list plus the entries in `files`, one per line with `#` comments
+    pub fn load(files: &[&str]) -> Result<Self, String> {
+        let mut entries: Vec<String> = parse_entries(PACKAGED).collect();
+        for path in files {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            entries.extend(parse_entries(&text));
+        }
+        entries.sort();
+        entries.dedup();
+
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(&entries)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            automaton,
+            entries,
+            rejected: Default::default(),
+        })
+    }
+
+    /// Tells lists apart, so a checkpoint is only resumed by a search that
+    /// throws away the same keys
+    pub fn identity(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"ssh-keygen-deluxe/screen-identity/v1");
+        for entry in &self.entries {
+            hasher.update(entry.as_bytes());
+            hasher.update(b"\n");
+        }
+        hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Whether a key matching `target` shows an unwanted word anywhere; counts it
+    /// if so. As with the blocklist, words any spelling of the target contains are allowed.
+    pub fn rejects(&self, public_key_line: &str, target: &str) -> bool {
+        let unwanted = |text: &str| {
+            self.automaton.find_overlapping_iter(text).any(|hit| {
+                let entry = &self.entries[hit.pattern().as_usize()];
+                !matching::spelling_contains(target, entry)
+            })
+        };
+        let hit = renderings(public_key_line).iter().position(|text| unwanted(text));
+        if let Some(place) = hit {
+            self.rejected[place].fetch_add(1, Ordering::Relaxed);
+        }
+        hit.is_some()
+    }
+
+    /// e.g. "Screened out 3 matching keys with unwanted words: SHA256 fingerprint 2, randomart 1"
+    pub fn summary(&self) -> Option<String> {
+        let counts: Vec<(&str, u64)> = PLACES
+            .iter()
+            .zip(&self.rejected)
+            .map(|(place, count)| (*place, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        let total: u64 = counts.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return None;
+        }
+        let places: Vec<String> = counts.iter().map(|(place, count)| format!("{} {}", place, count)).collect();
+        Some(format!(
+            "Screened out {} matching key{} with unwanted words: {}",
+            total,
+            if total == 1 { "" } else { "s" },
+            places.join(", ")
+        ))
+    }
+}
+
+fn parse_entries(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+/// The key as each place in `PLACES` shows it. The key text starts after the
+/// prefix every Ed25519 key shares, and the randomart is only the picture inside
+/// its frame, one row per line so no word is read across rows.
+fn renderings(public_key_line: &str) -> Vec<String> {
+    let key = public_key_line.strip_prefix(ED25519_PREFIX).unwrap_or(public_key_line).to_string();
+    let (md5, randomart) = match ssh_key::PublicKey::from_openssh(public_key_line.trim()) {
+        Ok(public) => (
+            fingerprint::md5(&public).unwrap_or_default(),
+            fingerprint::randomart(&public)
+                .lines()
+                .filter_map(|row| row.strip_prefix('|')?.strip_suffix('|'))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Err(_) => (String::new(), String::new()),
+    };
+    vec![
+        key,
+        FingerprintFormat::Sha256.render(public_key_line),
+        FingerprintFormat::Sha256Hex.render(public_key_line),
+        FingerprintFormat::BubbleBabble.render(public_key_line),
+        md5,
+        randomart,
+    ]
+}
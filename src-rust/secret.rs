@@ -0,0 +1,35 @@
+use zeroize::Zeroize;
+
+/// Wipe every string in a JSON document that carried secret material.
+/// serde_json keeps its own copies, so callers wipe the document once it
+/// has been serialized into a zeroizing buffer.
+pub fn wipe_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => s.zeroize(),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(wipe_json),
+        serde_json::Value::Object(map) => map.values_mut().for_each(wipe_json),
+        _ => {}
+    }
+}
+
+/// A string secret (e.g. a passphrase) that is wiped on drop and never
+/// shows up in Debug output
+#[derive(Clone)]
+pub struct SecretString(zeroize::Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(zeroize::Zeroizing::new(value))
+    }
+
+    /// Borrow the secret value for the single call site that needs it
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
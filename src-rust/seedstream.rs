@@ -0,0 +1,211 @@
+use crate::keygen::Seed;
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+/// Number of consecutive indices a worker claims at a time
+pub const CHUNK_SIZE: u64 = 1000;
+
+/// Chunk size for a worker flagged as lagging
+pub const SLOW_CHUNK_SIZE: u64 = CHUNK_SIZE / 10;
+
+/// Printed before a --deterministic-seed search
+pub const TEST_ONLY_WARNING: &str = "\
+WARNING: --deterministic-seed is for testing only. Every key it produces can be
+         recomputed by anyone who knows the seed; never use these keys.";
+
+/// An indexed, reproducible sequence of candidate keys. Searches over a stream
+/// report the lowest matching index, so the result does not depend on timing.
+pub trait KeyStream: Send + Sync + std::fmt::Debug {
+    /// Derive the seed of the candidate at `index`; the key generator turns it
+    /// into a key
+    fn seed(&self, index: u64) -> Seed;
+
+    /// RNG backend description for metadata and logs
+    fn describe(&self) -> String;
+
+    /// Flag that restarts the stream at a given index, if the user can resume it
+    fn index_flag(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Tells streams apart without revealing anything about them, so a
+    /// checkpoint is only resumed against the stream it was taken from
+    fn identity(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"ssh-keygen-deluxe/stream-identity/v1");
+        hasher.update(self.seed(u64::MAX).as_ref());
+        hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// Test-only stream: seed(i) = SHA-256(label || seed || i)
+#[derive(Debug)]
+pub struct DeterministicStream {
+    seed: u64,
+}
+
+impl DeterministicStream {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl KeyStream for DeterministicStream {
+    fn seed(&self, index: u64) -> Seed {
+        let mut hasher = Sha256::new();
+        hasher.update(b"ssh-keygen-deluxe/deterministic-test-seed/v1");
+        hasher.update(self.seed.to_be_bytes());
+        hasher.update(index.to_be_bytes());
+        Zeroizing::new(<[u8; 32]>::from(hasher.finalize()))
+    }
+
+    fn describe(&self) -> String {
+        format!("deterministic test stream (seed {})", self.seed)
+    }
+}
+
+/// Hands out index chunks to workers, tracks what each one has left of its
+/// chunk so an interrupted search knows exactly which indices are unchecked,
+/// and keeps the lowest matching index found so far
+#[derive(Debug)]
+pub struct IndexCursor {
+    /// The unchecked rest of each worker's chunk
+    progress: Vec<Mutex<Range<u64>>>,
+    unclaimed: Mutex<Unclaimed>,
+    best: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Unclaimed {
+    /// Left unchecked by an earlier run, lowest first; handed out before `next`
+    pending: Vec<Range<u64>>,
+    next: u64,
+}
+
+/// Every index not yet checked: the ranges in `pending` and everything from `next` on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub pending: Vec<Range<u64>>,
+    pub next: u64,
+}
+
+impl Position {
+    /// Indices below `next` that were checked, counting from `start`
+    pub fn checked(&self, start: u64) -> u64 {
+        let unchecked: u64 = self.pending.iter().map(|range| range.end - range.start).sum();
+        (self.next - start).saturating_sub(unchecked)
+    }
+}
+
+impl IndexCursor {
+    pub fn new(start: u64, workers: usize) -> Self {
+        Self::resume(
+            Position {
+                pending: Vec::new(),
+                next: start,
+            },
+            workers,
+        )
+    }
+
+    /// Pick up where a checkpointed search stopped
+    pub fn resume(position: Position, workers: usize) -> Self {
+        let mut pending = position.pending;
+        pending.retain(|range| !range.is_empty());
+        pending.sort_by_key(|range| range.start);
+        Self {
+            progress: (0..workers).map(|_| Mutex::new(0..0)).collect(),
+            unclaimed: Mutex::new(Unclaimed {
+                pending,
+                next: position.next,
+            }),
+            best: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    /// Claim up to `size` indices for a worker; None once they could only
+    /// hold indices above an already found match
+    pub fn claim(&self, worker: usize, size: u64) -> Option<Range<u64>> {
+        // Held together so position() never sees the chunk in neither place
+        let mut progress = lock(&self.progress[worker]);
+        let mut unclaimed = lock(&self.unclaimed);
+        let chunk = match unclaimed.pending.first_mut() {
+            Some(range) => {
+                let chunk = range.start..range.end.min(range.start.saturating_add(size));
+                range.start = chunk.end;
+                if range.is_empty() {
+                    unclaimed.pending.remove(0);
+                }
+                chunk
+            }
+            None => {
+                let start = unclaimed.next;
+                unclaimed.next = start.saturating_add(size);
+                start..unclaimed.next
+            }
+        };
+        if chunk.start >= self.best() {
+            *progress = 0..0;
+            return None;
+        }
+        *progress = chunk.clone();
+        Some(chunk)
+    }
+
+    /// A worker has checked every index of its chunk below `index`
+    pub fn advance(&self, worker: usize, index: u64) {
+        let mut progress = lock(&self.progress[worker]);
+        progress.start = index.min(progress.end);
+    }
+
+    /// Record a match; returns false if a lower one is already known
+    pub fn record_match(&self, index: u64) -> bool {
+        self.best.fetch_min(index, Ordering::SeqCst) > index
+    }
+
+    /// Lowest matching index found so far (u64::MAX if none)
+    pub fn best(&self) -> u64 {
+        self.best.load(Ordering::SeqCst)
+    }
+
+    /// Exactly which indices are still unchecked, for a checkpoint
+    pub fn position(&self) -> Position {
+        // Same lock order as claim(): the workers' chunks, then the unclaimed ranges
+        let progress: Vec<_> = self.progress.iter().map(lock).collect();
+        let unclaimed = lock(&self.unclaimed);
+        let mut ranges: Vec<Range<u64>> = progress
+            .iter()
+            .map(|range| Range::clone(range))
+            .chain(unclaimed.pending.iter().cloned())
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        // Merge neighbours, so a checkpoint stays small however often it is taken
+        let mut pending: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match pending.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => pending.push(range),
+            }
+        }
+        let mut next = unclaimed.next;
+        if pending.last().is_some_and(|last| last.end == next) {
+            next = pending.pop().unwrap().start;
+        }
+        Position { pending, next }
+    }
+
+    /// Lowest index that may not have been checked yet
+    pub fn resume_index(&self) -> u64 {
+        let position = self.position();
+        position.pending.first().map_or(position.next, |range| range.start)
+    }
+}
+
+/// A worker that panicked left its chunk as it was, which a checkpoint still needs
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
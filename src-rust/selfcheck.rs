@@ -0,0 +1,62 @@
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use ssh_key::public::KeyData;
+use ssh_key::PublicKey;
+use std::fs;
+use zeroize::Zeroizing;
+
+const TEST_MESSAGE: &[u8] = b"ssh-keygen post-generation self-check";
+
+/// Extract the Ed25519 verifying key from an OpenSSH public key line
+fn parse_public_key(public_key_line: &str) -> Result<VerifyingKey, String> {
+    let public_key = PublicKey::from_openssh(public_key_line.trim())
+        .map_err(|e| format!("public key does not parse: {}", e))?;
+    match public_key.key_data() {
+        KeyData::Ed25519(key) => {
+            VerifyingKey::from_bytes(&key.0).map_err(|e| format!("invalid Ed25519 point: {}", e))
+        }
+        other => Err(format!("unexpected public key type {}", other.algorithm())),
+    }
+}
+
+/// Sign a test message with the private key and verify it against the
+/// serialized public key, proving the two actually belong together
+pub fn verify_keypair(private_key: &SigningKey, public_key_line: &str) -> Result<(), String> {
+    let verifying_key = parse_public_key(public_key_line)?;
+    if verifying_key != private_key.verifying_key() {
+        return Err("serialized public key does not match the private key".to_string());
+    }
+
+    let signature = private_key.sign(TEST_MESSAGE);
+    verifying_key
+        .verify(TEST_MESSAGE, &signature)
+        .map_err(|_| "test signature failed to verify against the public key".to_string())
+}
+
+/// Re-read the written key files and confirm they round-trip to the same key.
+/// `private_path` is None when the private key was not written in plain form
+/// (e.g. age-encrypted);
+/// `passphrase` decrypts it when it was written encrypted.
+pub fn verify_written_files(
+    private_key: &SigningKey,
+    private_path: Option<&str>,
+    public_path: &str,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    let public_text = fs::read_to_string(public_path)
+        .map_err(|e| format!("cannot re-read {}: {}", public_path, e))?;
+    verify_keypair(private_key, &public_text).map_err(|e| format!("{}: {}", public_path, e))?;
+
+    if let Some(private_path) = private_path {
+        let text = Zeroizing::new(
+            fs::read_to_string(private_path).map_err(|e| format!("cannot re-read {}: {}", private_path, e))?,
+        );
+        let reparsed = crate::formats::decode(&text, passphrase)
+            .map_err(|e| format!("{}: private key does not parse: {}", private_path, e))?;
+        let expected = Zeroizing::new(private_key.to_bytes());
+        if *Zeroizing::new(reparsed.signing_key.to_bytes()) != *expected {
+            return Err(format!("{}: private key does not match the generated key", private_path));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! `ssh-keygen self-update`: replace this binary with the latest GitHub release
+//! for the platform, for machines that run it outside any package manager.
+//!
+//! Each release asset (named as build-rust.sh names it, e.g.
+//! `ssh-keygen-rust-linux-amd64`) comes with an armored SSHSIG signature in
+//! `<asset>.sig`, made by the release key with `ssh-keygen sign --namespace
+//! ssh-keygen-deluxe-release`. Nothing is replaced unless that signature
+//! verifies against the trusted key and the new binary runs and reports the
+//! release's version. The trusted key is built in from `SSH_KEYGEN_RELEASE_KEY`
+//! at compile time or given with `--trusted-key`; without either, nothing is
+//! downloaded at all.
+
+use crate::cleanup;
+use crate::error::Error;
+use ssh_key::{HashAlg, PublicKey, SshSig};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+const USER_AGENT: &str = concat!("ssh-keygen/", env!("CARGO_PKG_VERSION"));
+
+/// Namespace of release signatures, checked on verification
+pub const RELEASE_NAMESPACE: &str = "ssh-keygen-deluxe-release";
+
+/// Largest asset downloaded; release binaries are a few MB
+const MAX_DOWNLOAD: u64 = 256 * 1024 * 1024;
+
+/// Options for `ssh-keygen self-update`
+pub struct UpdateOptions<'a> {
+    /// `owner/name` of the GitHub repository
+    pub repo: &'a str,
+    pub api_url: &'a str,
+    /// A public key file or an `ssh-ed25519 AAAA...` line; None uses the built-in key
+    pub trusted_key: Option<&'a str>,
+    /// The binary to replace; None replaces the running one
+    pub path: Option<&'a str>,
+    /// Only report whether a newer release exists
+    pub check: bool,
+}
+
+/// Check for a newer release and install it
+pub fn run(options: &UpdateOptions) -> Result<(), Error> {
+    let trusted = trusted_key(options.trusted_key)?;
+    let release = latest_release(options.api_url, options.repo)?;
+    let latest = parse_version(&release.tag)
+        .ok_or_else(|| Error::config(format!("cannot read a version from the release tag {:?}", release.tag)))?;
+    let current = parse_version(env!("CARGO_PKG_VERSION")).expect("the package version is numeric");
+    if latest <= current {
+        println!("ssh-keygen {} is up to date (latest release: {})", env!("CARGO_PKG_VERSION"), release.tag);
+        return Ok(());
+    }
+    println!("Release {} is available (this is {})", release.tag, env!("CARGO_PKG_VERSION"));
+    if options.check {
+        return Ok(());
+    }
+
+    let name = asset_name()?;
+    let url = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|(asset, _)| asset == name)
+            .map(|(_, url)| url.as_str())
+            .ok_or_else(|| Error::config(format!("release {} has no asset {}", release.tag, name)))
+    };
+    let signature_name = format!("{}.sig", name);
+    let (binary_url, signature_url) = (url(&name)?, url(&signature_name)?);
+    println!("Downloading {}...", name);
+    let binary = download(binary_url).map_err(|e| Error::io(format!("downloading {}", name), e))?;
+    let signature = download(signature_url).map_err(|e| Error::io(format!("downloading {}", signature_name), e))?;
+    verify(&trusted, &binary, &signature).map_err(|e| Error::Integrity(format!("{}: {}; not installed", name, e)))?;
+    println!("Signature: good, by {}", trusted.fingerprint(HashAlg::Sha256));
+
+    let path = match options.path {
+        // Absolute, so the version check runs this file and never searches PATH
+        Some(path) => std::path::absolute(path).map_err(|e| Error::io(format!("resolving {}", path), e))?,
+        None => std::env::current_exe().map_err(|e| Error::io("locating the running binary", e))?,
+    };
+    let version = latest.iter().map(u64::to_string).collect::<Vec<_>>().join(".");
+    install(&path, &binary, &version)?;
+    println!("Updated {} to {}", path.display(), release.tag);
+    Ok(())
+}
+
+/// The key release signatures must verify against
+fn trusted_key(given: Option<&str>) -> Result<PublicKey, Error> {
+    let (text, origin) = match given {
+        Some(key) if key.trim_start().starts_with("ssh-") => (key.to_string(), "--trusted-key".to_string()),
+        Some(path) => (
+            fs::read_to_string(path).map_err(|e| Error::config(format!("{}: cannot read: {}", path, e)))?,
+            path.to_string(),
+        ),
+        None => match option_env!("SSH_KEYGEN_RELEASE_KEY") {
+            Some(key) => (key.to_string(), "the built-in release key".to_string()),
+            None => {
+                return Err(Error::config(
+                    "this build has no release signing key (SSH_KEYGEN_RELEASE_KEY at build time); \
+                     pass the key releases are signed with as --trusted-key",
+                ))
+            }
+        },
+    };
+    PublicKey::from_openssh(text.trim()).map_err(|e| Error::config(format!("{}: not an OpenSSH public key: {}", origin, e)))
+}
+
+/// The latest release's tag and its assets' names and download URLs
+struct Release {
+    tag: String,
+    assets: Vec<(String, String)>,
+}
+
+fn latest_release(api_url: &str, repo: &str) -> Result<Release, Error> {
+    let url = format!("{}/repos/{}/releases/latest", api_url.trim_end_matches('/'), repo);
+    let body: serde_json::Value = match ureq::get(&url)
+        .set("User-Agent", USER_AGENT)
+        .set("Accept", "application/vnd.github+json")
+        .call()
+    {
+        Ok(response) => response.into_json().map_err(|e| Error::io("reading the latest release", e))?,
+        Err(ureq::Error::Status(404, _)) => {
+            return Err(Error::config(format!("{} has no published releases (or is not a GitHub repository)", repo)))
+        }
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            return Err(Error::io("checking for updates", format!("GitHub API returned {}: {}", code, body.trim())));
+        }
+        Err(e) => return Err(Error::io("checking for updates", e)),
+    };
+    let tag = body["tag_name"]
+        .as_str()
+        .ok_or_else(|| Error::io("reading the latest release", "no tag_name in the response"))?
+        .to_string();
+    let assets = body["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|asset| Some((asset["name"].as_str()?.to_string(), asset["browser_download_url"].as_str()?.to_string())))
+        .collect();
+    Ok(Release { tag, assets })
+}
+
+/// `v1.2.3` or `1.2.3` as numbers, compared part by part
+fn parse_version(text: &str) -> Option<Vec<u64>> {
+    text.trim_start_matches('v').split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// This platform's asset, as build-rust.sh names it
+fn asset_name() -> Result<String, Error> {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "arm" => "arm",
+        arch => return Err(Error::config(format!("no release binaries are built for {}", arch))),
+    };
+    Ok(format!("ssh-keygen-rust-{}-{}{}", os, arch, std::env::consts::EXE_SUFFIX))
+}
+
+fn download(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = match ureq::get(url).set("User-Agent", USER_AGENT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, _)) => return Err(format!("server returned {}", code).into()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut data = Vec::new();
+    response.into_reader().take(MAX_DOWNLOAD + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > MAX_DOWNLOAD {
+        return Err(format!("larger than {} MB", MAX_DOWNLOAD / (1024 * 1024)).into());
+    }
+    Ok(data)
+}
+
+/// The signature must be by the trusted key, in the release namespace, over these bytes
+fn verify(trusted: &PublicKey, binary: &[u8], signature: &[u8]) -> Result<(), String> {
+    let signature = std::str::from_utf8(signature)
+        .ok()
+        .and_then(|text| SshSig::from_pem(text.trim()).ok())
+        .ok_or("the signature is not an armored SSH signature")?;
+    if signature.public_key() != trusted.key_data() {
+        return Err(format!(
+            "signed by {}, not the trusted key {}",
+            PublicKey::from(signature.public_key().clone()).fingerprint(HashAlg::Sha256),
+            trusted.fingerprint(HashAlg::Sha256)
+        ));
+    }
+    trusted
+        .verify(RELEASE_NAMESPACE, binary, &signature)
+        .map_err(|e| format!("bad signature ({})", e))
+}
+
+/// Put the new binary next to the old one, check that it runs, then swap it in
+fn install(path: &Path, binary: &[u8], version: &str) -> Result<(), Error> {
+    let mut new = path.as_os_str().to_owned();
+    new.push(".new");
+    let pending = cleanup::Pending::new(new, false);
+    let write = |e| Error::io(format!("writing {}", pending.path().display()), e);
+    fs::write(pending.path(), binary).map_err(write)?;
+    // Same mode (and executable bit) as the binary it replaces
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(pending.path(), metadata.permissions()).map_err(write)?;
+    }
+
+    let output = Command::new(pending.path())
+        .arg("--version")
+        .output()
+        .map_err(|e| Error::Integrity(format!("the downloaded binary does not run here ({}); not installed", e)))?;
+    let reported = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() || reported.split_whitespace().nth(1) != Some(version) {
+        return Err(Error::Integrity(format!(
+            "the downloaded binary reports {:?}, not version {}; not installed",
+            reported.trim(),
+            version
+        )));
+    }
+
+    let replace = |e| Error::io(format!("replacing {}", path.display()), e);
+    // A running .exe cannot be overwritten, but it can be renamed out of the way
+    #[cfg(windows)]
+    {
+        let mut old = path.as_os_str().to_owned();
+        old.push(".old");
+        let _ = fs::remove_file(&old);
+        fs::rename(path, &old).map_err(replace)?;
+    }
+    fs::rename(pending.path(), path).map_err(replace)?;
+    pending.keep();
+    Ok(())
+}
@@ -0,0 +1,366 @@
+//! `ssh-keygen service`: a search running as a native Windows service, so a build
+//! server can grind through the night with nobody logged in. `install` registers
+//! the service with the search's options, `uninstall` stops and removes it, and
+//! `run` is what the service control manager starts. The search runs in the
+//! configured directory, where its key files land, and reports its start,
+//! progress, matches and outcome to the Application event log.
+
+use crate::error::Error;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+pub const DEFAULT_NAME: &str = "ssh-keygen";
+
+/// The running search's stop flag: a stop request from the service manager
+/// ends the search the way Ctrl-C does
+static STOP: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+/// A stop request that came before the search was ready for it
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Options for `ssh-keygen service install`
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct InstallOptions<'a> {
+    pub name: &'a str,
+    /// Where the search runs and writes its key files
+    pub directory: PathBuf,
+    /// Start with Windows instead of on `sc start`
+    pub auto_start: bool,
+    /// The search's own options and targets
+    pub search: Vec<String>,
+}
+
+/// Options for `ssh-keygen service run`, as `install` recorded them
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct RunOptions {
+    pub name: String,
+    pub directory: PathBuf,
+    pub search: Vec<OsString>,
+}
+
+/// Runs a search from its command line, program name first
+pub type Search = fn(Vec<OsString>) -> Result<(), Error>;
+
+/// Let a stop request end the search behind `found`
+pub fn watch(found: Arc<AtomicBool>) {
+    if STOP.set(found.clone()).is_ok() && STOP_REQUESTED.load(Ordering::Relaxed) {
+        found.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(windows)]
+pub use windows::{install, run, uninstall};
+
+#[cfg(not(windows))]
+pub fn install(options: &InstallOptions) -> Result<(), Error> {
+    Err(unsupported(options.name))
+}
+
+#[cfg(not(windows))]
+pub fn uninstall(name: &str) -> Result<(), Error> {
+    Err(unsupported(name))
+}
+
+#[cfg(not(windows))]
+pub fn run(options: RunOptions, _search: Search) -> Result<(), Error> {
+    Err(unsupported(&options.name))
+}
+
+#[cfg(not(windows))]
+fn unsupported(name: &str) -> Error {
+    Error::config(format!(
+        "service {}: Windows services are only available on Windows; elsewhere run the search under systemd or launchd with --log",
+        name
+    ))
+}
+
+/// `text` as a NUL-terminated UTF-16 string
+#[cfg(windows)]
+pub fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// One argument quoted so `CommandLineToArgvW` reads it back unchanged
+#[cfg(windows)]
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        // Backslashes only escape when a quote follows them
+        let escapes = if c == '"' { backslashes * 2 + 1 } else { backslashes };
+        quoted.push_str(&"\\".repeat(escapes));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::{quote, wide, InstallOptions, RunOptions, Search, STOP, STOP_REQUESTED};
+    use crate::error::Error;
+    use crate::syslog;
+    use std::ffi::{c_void, OsString};
+    use std::io;
+    use std::ptr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::OnceLock;
+    use windows_sys::core::PWSTR;
+    use windows_sys::Win32::Foundation::{
+        ERROR_CALL_NOT_IMPLEMENTED, ERROR_FAILED_SERVICE_CONTROLLER_CONNECT, ERROR_SERVICE_DOES_NOT_EXIST,
+        ERROR_SERVICE_EXISTS, ERROR_SERVICE_NOT_ACTIVE, ERROR_SERVICE_SPECIFIC_ERROR, NO_ERROR,
+    };
+    use windows_sys::Win32::System::Services::{
+        ChangeServiceConfig2W, CloseServiceHandle, ControlService, CreateServiceW, DeleteService, OpenSCManagerW,
+        OpenServiceW, RegisterServiceCtrlHandlerExW, SetServiceStatus, StartServiceCtrlDispatcherW, SC_HANDLE,
+        SC_MANAGER_CONNECT, SC_MANAGER_CREATE_SERVICE, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP, SERVICE_ALL_ACCESS,
+        SERVICE_AUTO_START, SERVICE_CONFIG_DESCRIPTION, SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_SHUTDOWN,
+        SERVICE_CONTROL_STOP, SERVICE_DEMAND_START, SERVICE_DESCRIPTIONW, SERVICE_ERROR_NORMAL, SERVICE_RUNNING,
+        SERVICE_STATUS, SERVICE_STATUS_HANDLE, SERVICE_STOPPED, SERVICE_STOP_PENDING, SERVICE_TABLE_ENTRYW,
+        SERVICE_WIN32_OWN_PROCESS,
+    };
+
+    /// What `service_main` runs, set before the dispatcher starts
+    static SERVICE: OnceLock<(RunOptions, Search)> = OnceLock::new();
+    /// The handle `SetServiceStatus` reports through
+    static STATUS_HANDLE: AtomicUsize = AtomicUsize::new(0);
+
+    /// A service manager or service handle, closed when dropped
+    struct Handle(SC_HANDLE);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from OpenSCManagerW/OpenServiceW/CreateServiceW
+            unsafe {
+                CloseServiceHandle(self.0);
+            }
+        }
+    }
+
+    fn open_manager(access: u32) -> Result<Handle, Error> {
+        // SAFETY: null machine and database names select the local defaults
+        let handle = unsafe { OpenSCManagerW(ptr::null(), ptr::null(), access) };
+        if handle.is_null() {
+            return Err(Error::io("opening the service manager (run as Administrator)", io::Error::last_os_error()));
+        }
+        Ok(Handle(handle))
+    }
+
+    fn last_error_is(code: u32) -> bool {
+        io::Error::last_os_error().raw_os_error() == Some(code as i32)
+    }
+
+    /// Register the service to run the search in `options.directory`
+    pub fn install(options: &InstallOptions) -> Result<(), Error> {
+        let exe = std::env::current_exe().map_err(|e| Error::io("locating ssh-keygen.exe", e))?;
+        std::fs::create_dir_all(&options.directory)
+            .map_err(|e| Error::io(format!("creating {}", options.directory.display()), e))?;
+        let directory = std::path::absolute(&options.directory)
+            .map_err(|e| Error::io(format!("resolving {}", options.directory.display()), e))?;
+
+        let mut command = vec![
+            exe.display().to_string(),
+            "service".to_string(),
+            "run".to_string(),
+            "--name".to_string(),
+            options.name.to_string(),
+            "--dir".to_string(),
+            directory.display().to_string(),
+            "--".to_string(),
+        ];
+        command.extend(options.search.iter().cloned());
+        let command_line: Vec<String> = command.iter().map(|arg| quote(arg)).collect();
+
+        let manager = open_manager(SC_MANAGER_CREATE_SERVICE)?;
+        let display_name = format!("ssh-keygen vanity search ({})", options.name);
+        // SAFETY: every string is NUL-terminated and outlives the call; the null
+        // arguments select no load order group, dependencies or account (LocalSystem)
+        let service = unsafe {
+            CreateServiceW(
+                manager.0,
+                wide(options.name).as_ptr(),
+                wide(&display_name).as_ptr(),
+                SERVICE_ALL_ACCESS,
+                SERVICE_WIN32_OWN_PROCESS,
+                if options.auto_start { SERVICE_AUTO_START } else { SERVICE_DEMAND_START },
+                SERVICE_ERROR_NORMAL,
+                wide(&command_line.join(" ")).as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+            )
+        };
+        if service.is_null() {
+            if last_error_is(ERROR_SERVICE_EXISTS) {
+                return Err(Error::config(format!(
+                    "a service named {} is already installed; remove it with `ssh-keygen service uninstall --name {}`",
+                    options.name, options.name
+                )));
+            }
+            return Err(Error::io(format!("installing service {}", options.name), io::Error::last_os_error()));
+        }
+        let service = Handle(service);
+
+        let mut description = wide(&format!("Searches for an SSH key matching: {}", options.search.join(" ")));
+        let info = SERVICE_DESCRIPTIONW {
+            lpDescription: description.as_mut_ptr(),
+        };
+        // SAFETY: info points at a NUL-terminated string that outlives the call.
+        // The description is cosmetic, so a failure is ignored.
+        unsafe {
+            ChangeServiceConfig2W(service.0, SERVICE_CONFIG_DESCRIPTION, &info as *const _ as *const c_void);
+        }
+
+        println!("Installed service {}: ssh-keygen {}", options.name, options.search.join(" "));
+        println!("Key files will be written to {}", directory.display());
+        println!(
+            "Start it with `sc start {}`{}; progress and results go to the Application event log (source {})",
+            options.name,
+            if options.auto_start { " or restart Windows" } else { "" },
+            options.name
+        );
+        Ok(())
+    }
+
+    /// Stop the service if it is running and remove it
+    pub fn uninstall(name: &str) -> Result<(), Error> {
+        let manager = open_manager(SC_MANAGER_CONNECT)?;
+        // SAFETY: the name is NUL-terminated and outlives the call
+        let service = unsafe { OpenServiceW(manager.0, wide(name).as_ptr(), SERVICE_ALL_ACCESS) };
+        if service.is_null() {
+            if last_error_is(ERROR_SERVICE_DOES_NOT_EXIST) {
+                return Err(Error::config(format!("no service named {} is installed", name)));
+            }
+            return Err(Error::io(format!("opening service {}", name), io::Error::last_os_error()));
+        }
+        let service = Handle(service);
+
+        // SAFETY: SERVICE_STATUS is plain data, so all zeroes is a valid value
+        let mut status: SERVICE_STATUS = unsafe { std::mem::zeroed() };
+        // SAFETY: ControlService only writes the provided struct
+        if unsafe { ControlService(service.0, SERVICE_CONTROL_STOP, &mut status) } != 0 {
+            println!("Stopped service {}", name);
+        } else if !last_error_is(ERROR_SERVICE_NOT_ACTIVE) {
+            eprintln!("Warning: service {} not stopped: {}", name, io::Error::last_os_error());
+        }
+        // SAFETY: the handle was opened with DELETE access (part of SERVICE_ALL_ACCESS)
+        if unsafe { DeleteService(service.0) } == 0 {
+            return Err(Error::io(format!("removing service {}", name), io::Error::last_os_error()));
+        }
+        println!("Removed service {}; the key files it wrote are left where they are", name);
+        Ok(())
+    }
+
+    /// Hand this process to the service manager, which calls `service_main`;
+    /// returns once the service has stopped
+    pub fn run(options: RunOptions, search: Search) -> Result<(), Error> {
+        let mut name = wide(&options.name);
+        let _ = SERVICE.set((options, search));
+        let table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: name.as_mut_ptr(),
+                lpServiceProc: Some(service_main),
+            },
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: ptr::null_mut(),
+                lpServiceProc: None,
+            },
+        ];
+        // SAFETY: the table ends with a null entry and outlives the call, which
+        // blocks until the service stops
+        if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+            if last_error_is(ERROR_FAILED_SERVICE_CONTROLLER_CONNECT) {
+                return Err(Error::config(
+                    "`service run` is started by the service manager; use `sc start NAME`, or run the search directly",
+                ));
+            }
+            return Err(Error::io("starting the service dispatcher", io::Error::last_os_error()));
+        }
+        // service_main reported and logged how the search ended
+        Ok(())
+    }
+
+    unsafe extern "system" fn service_main(_argc: u32, _argv: *mut PWSTR) {
+        let Some((options, search)) = SERVICE.get() else {
+            return;
+        };
+        let name = wide(&options.name);
+        // SAFETY: the name is NUL-terminated; control_handler needs no context
+        let handle = unsafe { RegisterServiceCtrlHandlerExW(name.as_ptr(), Some(control_handler), ptr::null()) };
+        if handle.is_null() {
+            return;
+        }
+        STATUS_HANDLE.store(handle as usize, Ordering::Relaxed);
+        report(SERVICE_RUNNING, 0);
+
+        let code = match start(options, *search) {
+            // Being stopped is how a service without a target in reach ends
+            Ok(()) | Err(Error::Interrupted) => 0,
+            Err(e) => {
+                if !matches!(e, Error::Timeout { .. }) {
+                    syslog::log(syslog::Priority::Err, "error", &e.to_string(), &[]);
+                }
+                e.exit_code()
+            }
+        };
+        report(SERVICE_STOPPED, code);
+    }
+
+    /// Run the search in its directory, logging to the event log
+    fn start(options: &RunOptions, search: Search) -> Result<(), Error> {
+        std::fs::create_dir_all(&options.directory)
+            .and_then(|()| std::env::set_current_dir(&options.directory))
+            .map_err(|e| Error::io(format!("entering {}", options.directory.display()), e))?;
+        syslog::init(syslog::Logger::open(&format!("eventlog:{}", options.name)).map_err(Error::config)?);
+        let mut args = vec![OsString::from("ssh-keygen")];
+        args.extend(options.search.iter().cloned());
+        search(args)
+    }
+
+    unsafe extern "system" fn control_handler(control: u32, _event_type: u32, _data: *mut c_void, _context: *mut c_void) -> u32 {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+                report(SERVICE_STOP_PENDING, 0);
+                STOP_REQUESTED.store(true, Ordering::Relaxed);
+                if let Some(found) = STOP.get() {
+                    found.store(true, Ordering::Relaxed);
+                }
+                NO_ERROR
+            }
+            SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+            _ => ERROR_CALL_NOT_IMPLEMENTED,
+        }
+    }
+
+    /// Tell the service manager the service's state; a non-zero `exit_code` is
+    /// the search's own exit code
+    fn report(state: u32, exit_code: i32) {
+        let status = SERVICE_STATUS {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwCurrentState: state,
+            dwControlsAccepted: if state == SERVICE_RUNNING { SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN } else { 0 },
+            dwWin32ExitCode: if exit_code == 0 { NO_ERROR } else { ERROR_SERVICE_SPECIFIC_ERROR },
+            dwServiceSpecificExitCode: exit_code as u32,
+            dwCheckPoint: 0,
+            // Workers notice the stop within a batch
+            dwWaitHint: if state == SERVICE_STOP_PENDING { 10_000 } else { 0 },
+        };
+        let handle = STATUS_HANDLE.load(Ordering::Relaxed) as SERVICE_STATUS_HANDLE;
+        // SAFETY: the handle came from RegisterServiceCtrlHandlerExW
+        unsafe {
+            SetServiceStatus(handle, &status);
+        }
+    }
+}
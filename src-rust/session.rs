@@ -0,0 +1,215 @@
+//! `--record FILE`: a seeded search written down as it runs, for
+//! `ssh-keygen replay FILE` to search the same candidates again. A seeded
+//! search reports the lowest matching index whatever the timing, so the same
+//! stream and target give the same answer on any machine and at any worker
+//! count. A replay that disagrees points at the machine or build that
+//! recorded it.
+//!
+//! The file is JSON lines. The header holds the search, its stream, the
+//! machine and the build. Events follow as they happen: `position` (what a
+//! checkpoint would save) every ten seconds, then `match` and `finished`.
+//! Each line is flushed when written, so a run that crashed still leaves a
+//! file to replay. A `--from-mnemonic` stream is recorded by its identity
+//! only; replaying it needs the phrase again.
+
+use crate::checkpoint::{self, Search};
+use crate::matching;
+use crate::seedstream::Position;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const VERSION: u64 = 1;
+
+/// How often a running search's position is recorded
+pub const INTERVAL: Duration = Duration::from_secs(10);
+
+/// Where the recorded search's candidates came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stream {
+    /// `--deterministic-seed`
+    Deterministic(u64),
+    /// `--from-mnemonic`; the phrase is never written down
+    Mnemonic,
+}
+
+/// The first line of a session file
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub search: Search,
+    pub stream: Stream,
+    pub workers: usize,
+    /// Entries in the blocklist the search used; 0 for none
+    pub blocklist_entries: usize,
+    /// `ssh-keygen <version>`
+    pub build: String,
+    pub host: String,
+}
+
+/// Appends events to a session file
+pub struct Recorder {
+    file: Mutex<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Create (or truncate) `path` and write the header
+    pub fn create(path: &Path, header: &Header) -> io::Result<Self> {
+        let stream = match header.stream {
+            Stream::Deterministic(seed) => json!({ "kind": "deterministic", "seed": seed }),
+            Stream::Mnemonic => json!({ "kind": "mnemonic" }),
+        };
+        let line = json!({
+            "session": VERSION,
+            "search": header.search.to_json(),
+            "stream": stream,
+            "workers": header.workers,
+            "blocklist_entries": header.blocklist_entries,
+            "build": header.build,
+            "host": header.host,
+        });
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", line)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            started: Instant::now(),
+        })
+    }
+
+    /// Where the search stands: every index below `next` outside `pending` is checked
+    pub fn position(&self, position: &Position, attempts: u64) -> io::Result<()> {
+        self.event("position", json!({ "attempts": attempts, "next": position.next, "pending": checkpoint::ranges_to_json(&position.pending) }))
+    }
+
+    /// The lowest matching index
+    pub fn matched(&self, index: u64, fingerprint: &str) -> io::Result<()> {
+        self.event("match", json!({ "index": index, "fingerprint": fingerprint }))
+    }
+
+    pub fn finished(&self, outcome: &str, position: &Position, attempts: u64) -> io::Result<()> {
+        self.event(
+            "finished",
+            json!({ "outcome": outcome, "attempts": attempts, "next": position.next, "pending": checkpoint::ranges_to_json(&position.pending) }),
+        )
+    }
+
+    fn event(&self, event: &str, mut fields: Value) -> io::Result<()> {
+        fields["event"] = json!(event);
+        fields["t"] = json!((self.started.elapsed().as_secs_f64() * 1000.0).round() / 1000.0);
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", fields)?;
+        file.flush()
+    }
+}
+
+/// A session file as read back
+#[derive(Debug)]
+pub struct Recording {
+    pub header: Header,
+    /// Lowest matching index and the key's fingerprint
+    pub matched: Option<(u64, String)>,
+    /// How the search ended; None if the file stops before that
+    pub outcome: Option<String>,
+    /// Seconds from the start to the last event
+    pub elapsed: f64,
+    /// The last recorded position
+    pub position: Position,
+}
+
+impl Recording {
+    /// Every index the recorded search checked: all those up to a match, or
+    /// else those the last position counts as checked
+    pub fn checked(&self) -> Vec<Range<u64>> {
+        let start = self.header.search.start;
+        let mut checked = Vec::new();
+        if let Some((index, _)) = &self.matched {
+            checked.push(start..index + 1);
+            return checked;
+        }
+        let mut from = start;
+        for range in &self.position.pending {
+            if range.start > from {
+                checked.push(from..range.start);
+            }
+            from = from.max(range.end);
+        }
+        if self.position.next > from {
+            checked.push(from..self.position.next);
+        }
+        checked
+    }
+
+    /// The recorded search's match position
+    pub fn match_position(&self) -> Option<matching::Position> {
+        match self.header.search.position.as_str() {
+            "Anywhere" => Some(matching::Position::Anywhere),
+            "AtStart" => Some(matching::Position::AtStart),
+            "AtEnd" => Some(matching::Position::AtEnd),
+            _ => None,
+        }
+    }
+}
+
+/// Read a session file; a missing `finished` line is fine, a malformed line is not
+pub fn load(path: &Path) -> Result<Recording, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let invalid = |line: usize| format!("{}: line {} is not part of a session file", path.display(), line);
+    let mut lines = text.lines().enumerate();
+    let first: Value = lines
+        .next()
+        .and_then(|(_, line)| serde_json::from_str(line).ok())
+        .ok_or_else(|| format!("{} is not a session file", path.display()))?;
+    if first["session"].as_u64() != Some(VERSION) {
+        return Err(format!("{}: unsupported session version {}", path.display(), first["session"]));
+    }
+    let stream = match (first["stream"]["kind"].as_str(), first["stream"]["seed"].as_u64()) {
+        (Some("deterministic"), Some(seed)) => Stream::Deterministic(seed),
+        (Some("mnemonic"), _) => Stream::Mnemonic,
+        _ => return Err(invalid(1)),
+    };
+    let header = Header {
+        search: Search::from_json(&first["search"]).ok_or_else(|| invalid(1))?,
+        stream,
+        workers: first["workers"].as_u64().ok_or_else(|| invalid(1))? as usize,
+        blocklist_entries: first["blocklist_entries"].as_u64().ok_or_else(|| invalid(1))? as usize,
+        build: first["build"].as_str().unwrap_or_default().to_string(),
+        host: first["host"].as_str().unwrap_or_default().to_string(),
+    };
+
+    let mut recording = Recording {
+        position: Position {
+            pending: Vec::new(),
+            next: header.search.start,
+        },
+        header,
+        matched: None,
+        outcome: None,
+        elapsed: 0.0,
+    };
+    for (number, line) in lines {
+        let invalid = || invalid(number + 1);
+        let event: Value = serde_json::from_str(line).map_err(|_| invalid())?;
+        recording.elapsed = event["t"].as_f64().ok_or_else(invalid)?;
+        match event["event"].as_str() {
+            Some("position") | Some("finished") => {
+                let next = event["next"].as_u64().ok_or_else(invalid)?;
+                let pending = checkpoint::ranges_from_json(&event["pending"], next).ok_or_else(invalid)?;
+                recording.position = Position { pending, next };
+                if let Some(outcome) = event["outcome"].as_str() {
+                    recording.outcome = Some(outcome.to_string());
+                }
+            }
+            Some("match") => {
+                let index = event["index"].as_u64().ok_or_else(invalid)?;
+                let fingerprint = event["fingerprint"].as_str().ok_or_else(invalid)?;
+                recording.matched = Some((index, fingerprint.to_string()));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(recording)
+}
@@ -0,0 +1,257 @@
+//! `--split K/N` and `ssh-keygen recover`: Shamir secret sharing of the private
+//! key file, so a high-value key never has to live in a single file. The file
+//! is split byte by byte over GF(256); any K of the N shares rebuild it exactly
+//! (same format, passphrase and comment), and fewer than K reveal nothing.
+//!
+//! The shared secret is the file followed by its SHA-256, so a wrong or
+//! corrupted share is caught on recovery instead of yielding a broken key.
+
+use crate::error::Error;
+use crate::permissions;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+const BEGIN: &str = "-----BEGIN SSH-KEYGEN KEY SHARE-----";
+const END: &str = "-----END SSH-KEYGEN KEY SHARE-----";
+const VERSION: u8 = 1;
+/// Version, split id, threshold, x
+const HEADER_LEN: usize = 1 + 8 + 1 + 1;
+
+/// `--split K/N`: N shares, any K of which recover the key
+#[derive(Debug, Clone, Copy)]
+pub struct Split {
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+impl Split {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (threshold, shares) = text
+            .split_once('/')
+            .and_then(|(k, n)| Some((k.trim().parse::<u8>().ok()?, n.trim().parse::<u8>().ok()?)))
+            .ok_or_else(|| format!("--split {:?}: expected K/N, e.g. 3/5", text))?;
+        if threshold < 2 {
+            return Err("--split: K must be at least 2; with 1 every share is a full copy of the key".to_string());
+        }
+        if threshold > shares {
+            return Err(format!("--split {}/{}: K cannot exceed the number of shares", threshold, shares));
+        }
+        Ok(Split { threshold, shares })
+    }
+
+    /// `<private key>.share-1-of-N` and so on
+    pub fn paths(&self, private_key_file: &str) -> Vec<String> {
+        (1..=self.shares)
+            .map(|x| format!("{}.share-{}-of-{}", private_key_file, x, self.shares))
+            .collect()
+    }
+
+    /// Split the private key file as written into owner-only share files
+    pub fn write(&self, private_key_file: &str, fingerprint: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut secret = Zeroizing::new(fs::read(private_key_file)?);
+        let checksum = Sha256::digest(secret.as_slice());
+        secret.extend_from_slice(&checksum);
+
+        let mut id = [0u8; 8];
+        OsRng.fill_bytes(&mut id);
+        // Coefficients 1..K-1 of one random polynomial per byte; coefficient 0 is the byte
+        let degree = usize::from(self.threshold) - 1;
+        let mut coefficients = Zeroizing::new(vec![0u8; secret.len() * degree]);
+        OsRng.fill_bytes(&mut coefficients);
+
+        let paths = self.paths(private_key_file);
+        for (x, path) in (1..=self.shares).zip(&paths) {
+            let mut payload = Zeroizing::new(Vec::with_capacity(HEADER_LEN + secret.len()));
+            payload.push(VERSION);
+            payload.extend_from_slice(&id);
+            payload.extend([self.threshold, x]);
+            for (byte, coefficients) in secret.iter().zip(coefficients.chunks(degree)) {
+                let y = coefficients.iter().rev().fold(0, |y, c| mul(y, x) ^ c);
+                payload.push(mul(y, x) ^ byte);
+            }
+            let text = armor(fingerprint, x, self, &payload);
+            permissions::write_private(path.as_ref(), text.as_bytes())?;
+        }
+        Ok(paths)
+    }
+}
+
+fn armor(fingerprint: &str, x: u8, split: &Split, payload: &[u8]) -> Zeroizing<String> {
+    let encoded = Zeroizing::new(base64::engine::general_purpose::STANDARD.encode(payload));
+    let mut text = Zeroizing::new(format!(
+        "{}\nKey: {}\nShare: {} of {} (any {} recover the key with `ssh-keygen recover`)\n\n",
+        BEGIN, fingerprint, x, split.shares, split.threshold
+    ));
+    for line in encoded.as_bytes().chunks(64) {
+        text.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        text.push('\n');
+    }
+    text.push_str(END);
+    text.push('\n');
+    text
+}
+
+/// One share file, decoded
+struct Share {
+    id: [u8; 8],
+    threshold: u8,
+    x: u8,
+    /// The `Key:` line, for the summary
+    fingerprint: Option<String>,
+    y: Zeroizing<Vec<u8>>,
+}
+
+fn read_share(path: &str) -> Result<Share, Error> {
+    let invalid = |why: &str| Error::config(format!("{}: not a key share ({})", path, why));
+    let text = Zeroizing::new(fs::read_to_string(path).map_err(|e| Error::config(format!("{}: cannot read: {}", path, e)))?);
+    let body = text
+        .split_once(BEGIN)
+        .and_then(|(_, rest)| rest.split_once(END))
+        .map(|(body, _)| body)
+        .ok_or_else(|| invalid("no share block"))?;
+    let mut fingerprint = None;
+    let mut encoded = Zeroizing::new(String::new());
+    for line in body.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("Key:") {
+            fingerprint = Some(value.trim().to_string());
+        } else if !line.contains(':') {
+            encoded.push_str(line);
+        }
+    }
+    let payload = Zeroizing::new(
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.as_bytes())
+            .map_err(|_| invalid("bad base64"))?,
+    );
+    // At least one byte of key file besides the checksum
+    if payload.len() <= HEADER_LEN + 32 {
+        return Err(invalid("too short"));
+    }
+    if payload[0] != VERSION {
+        return Err(invalid(&format!("unknown version {}", payload[0])));
+    }
+    let (threshold, x) = (payload[9], payload[10]);
+    if threshold < 2 || x == 0 {
+        return Err(invalid("bad header"));
+    }
+    Ok(Share {
+        id: payload[1..9].try_into().expect("8 bytes"),
+        threshold,
+        x,
+        fingerprint,
+        y: Zeroizing::new(payload[HEADER_LEN..].to_vec()),
+    })
+}
+
+/// Options for `ssh-keygen recover`
+pub struct RecoverOptions<'a> {
+    pub shares: Vec<&'a str>,
+    /// Defaults to the share file name without `.share-X-of-N`
+    pub output: Option<&'a str>,
+}
+
+/// Rebuild the private key file from enough shares
+pub fn recover(options: &RecoverOptions) -> Result<(), Error> {
+    let shares = options.shares.iter().map(|path| read_share(path)).collect::<Result<Vec<_>, _>>()?;
+    let first = &shares[0];
+    for (share, path) in shares.iter().zip(&options.shares).skip(1) {
+        if share.id != first.id {
+            return Err(Error::config(format!(
+                "{} comes from a different split than {}",
+                path, options.shares[0]
+            )));
+        }
+    }
+    let mut xs: Vec<u8> = shares.iter().map(|share| share.x).collect();
+    xs.sort_unstable();
+    if let Some(pair) = xs.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(Error::config(format!("share {} was given more than once", pair[0])));
+    }
+    let threshold = usize::from(first.threshold);
+    if shares.len() < threshold {
+        return Err(Error::config(format!(
+            "this key needs {} shares to recover, but {} were given",
+            threshold,
+            shares.len()
+        )));
+    }
+    let used = &shares[..threshold];
+    if used.iter().any(|share| share.y.len() != first.y.len()) {
+        return Err(Error::config("the shares have different lengths; at least one is damaged"));
+    }
+
+    // Lagrange interpolation at x = 0; subtraction is XOR in GF(256)
+    let basis: Vec<u8> = used
+        .iter()
+        .map(|share| {
+            used.iter()
+                .filter(|other| other.x != share.x)
+                .fold(1, |basis, other| mul(basis, mul(other.x, inverse(other.x ^ share.x))))
+        })
+        .collect();
+    let mut secret = Zeroizing::new(vec![0u8; first.y.len()]);
+    for (share, basis) in used.iter().zip(&basis) {
+        for (byte, y) in secret.iter_mut().zip(share.y.iter()) {
+            *byte ^= mul(*y, *basis);
+        }
+    }
+    let (file, checksum) = secret.split_at(secret.len() - 32);
+    if Sha256::digest(file).as_slice() != checksum {
+        return Err(Error::Integrity(
+            "the shares do not rebuild the key (checksum mismatch); at least one is damaged or from another key".to_string(),
+        ));
+    }
+
+    let output = match options.output {
+        Some(output) => output.to_string(),
+        None => default_output(options.shares[0])
+            .ok_or_else(|| Error::config("cannot tell the key file name from the share name; choose one with --output"))?,
+    };
+    if Path::new(&output).exists() {
+        return Err(Error::config(format!("{} already exists; choose another file with --output", output)));
+    }
+    permissions::write_private(output.as_ref(), file).map_err(|e| Error::io(format!("writing {}", output), e))?;
+    println!(
+        "Recovered {} from {} shares{}",
+        output,
+        threshold,
+        first.fingerprint.as_ref().map_or(String::new(), |fingerprint| format!(" (key {})", fingerprint))
+    );
+    Ok(())
+}
+
+/// `id_ed25519.share-2-of-5` -> `id_ed25519`
+fn default_output(share: &str) -> Option<String> {
+    let (base, suffix) = share.rsplit_once(".share-")?;
+    let (x, n) = suffix.split_once("-of-")?;
+    (!base.is_empty() && x.parse::<u8>().is_ok() && n.parse::<u8>().is_ok()).then(|| base.to_string())
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1,
+/// without secret-dependent branches
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254 = a^2 * a^4 * ... * a^128, which is a^-1 for nonzero a
+fn inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    for _ in 1..8 {
+        power = mul(power, power);
+        result = mul(result, power);
+    }
+    result
+}
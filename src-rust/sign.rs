@@ -0,0 +1,267 @@
+//! `ssh-keygen sign`: OpenSSH file signatures, the same as `ssh-keygen -Y sign`,
+//! so a new vanity key can sign git commits and release artifacts right away.
+//! Each FILE gets a FILE.sig in the armored SSHSIG format that `ssh-keygen -Y
+//! verify` and git read. `--allowed-signers` adds the key to an allowed-signers
+//! file, which is what those tools check signatures against.
+//!
+//! A search with `--setup-git-signing` does the same for the key it finds and
+//! prints (or with `--apply`, runs) the `git config` commands that make git
+//! sign commits with it.
+//!
+//! `--attest` signs the `--meta` sidecar with the key it describes, so a team
+//! receiving a key can check that its holder produced that record.
+
+use crate::error::Error;
+use crate::formats;
+use crate::passphrase::{self, PassphraseSource};
+use ed25519_dalek::SigningKey;
+use ssh_key::{HashAlg, LineEnding, PrivateKey, PublicKey};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zeroize::Zeroizing;
+
+/// Options for `ssh-keygen sign`
+pub struct SignOptions<'a> {
+    pub key_file: &'a str,
+    /// `-` signs standard input and writes the signature to standard output
+    pub files: Vec<&'a str>,
+    /// What the signature is for, e.g. "git" or "file"; checked on verification
+    pub namespace: &'a str,
+    pub allowed_signers: Option<&'a str>,
+    pub principal: Option<&'a str>,
+    pub passphrase: PassphraseSource,
+}
+
+/// Sign every file and record the key in the allowed-signers file
+pub fn run(options: &SignOptions) -> Result<(), Error> {
+    if options.namespace.is_empty() {
+        return Err(Error::config("--namespace must not be empty"));
+    }
+    let key = load_key(options.key_file, &options.passphrase)?;
+    // Standard output carries the signature then, so messages go to stderr
+    let to_stdout = options.files.contains(&"-");
+    let note = |message: String| if to_stdout { eprintln!("{}", message) } else { println!("{}", message) };
+
+    for file in &options.files {
+        let data = if *file == "-" {
+            let mut data = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .map_err(|e| Error::io("reading standard input", e))?;
+            data
+        } else {
+            fs::read(file).map_err(|e| Error::config(format!("{}: cannot read: {}", file, e)))?
+        };
+        // SHA-512, like ssh-keygen -Y sign
+        let signature = key
+            .sign(options.namespace, HashAlg::Sha512, &data)
+            .map_err(|e| Error::config(format!("{}: cannot sign: {}", file, e)))?;
+        // Never hand out a signature that would not verify
+        key.public_key()
+            .verify(options.namespace, &data, &signature)
+            .map_err(|e| Error::Integrity(format!("{}: signature failed verification: {}", file, e)))?;
+        let armored = signature
+            .to_pem(LineEnding::LF)
+            .map_err(|e| Error::io("encoding signature", e))?;
+
+        if *file == "-" {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(armored.as_bytes())
+                .and_then(|()| stdout.flush())
+                .map_err(|e| Error::io("writing signature", e))?;
+        } else {
+            let path = format!("{}.sig", file);
+            fs::write(&path, armored).map_err(|e| Error::io(format!("writing {}", path), e))?;
+            note(format!("Signed {}: {} (namespace \"{}\")", file, path, options.namespace));
+        }
+    }
+
+    if let (Some(path), Some(principal)) = (options.allowed_signers, options.principal) {
+        let line = allowed_signer(principal, options.namespace, key.public_key())?;
+        if add_line(path, &line)? {
+            note(format!("Added {} to {}", principal, path));
+        } else {
+            note(format!("{} already lists this key for {}; left unchanged", path, principal));
+        }
+    }
+    Ok(())
+}
+
+/// Namespace of `--attest` signatures, checked on verification
+pub const ATTESTATION_NAMESPACE: &str = "ssh-keygen-deluxe-attestation";
+
+/// `--attest`: sign the metadata sidecar as written with the key it describes,
+/// into `<sidecar>.sig`; returns that path
+pub fn attest(signing_key: &SigningKey, metadata_file: &str, document: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let key = formats::openssh_private_key(signing_key, "")?;
+    let signature = key.sign(ATTESTATION_NAMESPACE, HashAlg::Sha512, document.as_bytes())?;
+    key.public_key()
+        .verify(ATTESTATION_NAMESPACE, document.as_bytes(), &signature)
+        .map_err(|e| format!("attestation failed verification: {}", e))?;
+    let path = format!("{}.sig", metadata_file);
+    fs::write(&path, signature.to_pem(LineEnding::LF)?)?;
+    Ok(path)
+}
+
+/// The private key in any supported format, decrypted if need be
+fn load_key(path: &str, source: &PassphraseSource) -> Result<PrivateKey, Error> {
+    let text = Zeroizing::new(
+        fs::read_to_string(path).map_err(|e| Error::config(format!("{}: cannot read private key: {}", path, e)))?,
+    );
+    let encrypted = formats::is_encrypted(&text).map_err(|e| Error::config(format!("{}: {}", path, e)))?;
+    let passphrase = if encrypted {
+        Some(passphrase::resolve_existing(source, path).map_err(Error::config)?)
+    } else {
+        None
+    };
+    let decoded = formats::decode(&text, passphrase.as_ref().map(|p| p.expose()))
+        .map_err(|e| Error::config(format!("{}: {}", path, e)))?;
+    formats::openssh_private_key(&decoded.signing_key, &decoded.comment)
+        .map_err(|e| Error::config(format!("{}: {}", path, e)))
+}
+
+/// `--setup-git-signing`: who the new key signs as, and where git looks it up
+#[derive(Debug, Clone)]
+pub struct GitSigning {
+    pub principal: String,
+    pub allowed_signers: PathBuf,
+    /// `--apply`: run the `git config` commands instead of printing them
+    pub apply: bool,
+}
+
+impl GitSigning {
+    /// Settle everything before the search starts. The principal defaults to
+    /// git's user.email; the file is git's gpg.ssh.allowedSignersFile if set,
+    /// otherwise ~/.ssh/allowed_signers.
+    pub fn resolve(principal: Option<&str>, apply: bool) -> Result<Self, Error> {
+        let principal = match principal {
+            Some(principal) => principal.to_string(),
+            None => git_config("user.email").ok_or_else(|| {
+                Error::config("--setup-git-signing: git has no user.email; set it or give --signing-principal")
+            })?,
+        };
+        let allowed_signers = match git_config("gpg.ssh.allowedSignersFile") {
+            Some(path) => match path.strip_prefix("~/") {
+                Some(rest) => home()?.join(rest),
+                None => PathBuf::from(path),
+            },
+            None => home()?.join(".ssh").join("allowed_signers"),
+        };
+        check_principal(&principal)?;
+        if apply && Command::new("git").arg("--version").output().is_err() {
+            return Err(Error::config("--apply: git is not installed"));
+        }
+        Ok(GitSigning {
+            principal,
+            allowed_signers,
+            apply,
+        })
+    }
+
+    /// Add the written key to the allowed-signers file and point git at it
+    pub fn setup(&self, public_key_file: &str, public: &PublicKey) -> Result<(), Error> {
+        let allowed_signers = self.allowed_signers.to_string_lossy();
+        let line = allowed_signer(&self.principal, "git", public)?;
+        if let Some(parent) = self.allowed_signers.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| Error::io(format!("creating {}", parent.display()), e))?;
+        }
+        if add_line(&allowed_signers, &line)? {
+            println!("Added {} to {}", self.principal, allowed_signers);
+        } else {
+            println!("{} already lists this key for {}; left unchanged", allowed_signers, self.principal);
+        }
+
+        let signing_key = absolute(Path::new(public_key_file));
+        let settings = [
+            ("gpg.format", "ssh".to_string()),
+            ("user.signingkey", signing_key.to_string_lossy().into_owned()),
+            ("gpg.ssh.allowedSignersFile", absolute(&self.allowed_signers).to_string_lossy().into_owned()),
+        ];
+        if !self.apply {
+            println!("To sign git commits with this key:");
+            for (name, value) in &settings {
+                println!("  git config --global {} {}", name, shell_quote(value));
+            }
+            println!("  git config --global commit.gpgsign true   # optional: sign every commit");
+            return Ok(());
+        }
+        for (name, value) in &settings {
+            let status = Command::new("git")
+                .args(["config", "--global", name, value])
+                .status()
+                .map_err(|e| Error::io("running git config", e))?;
+            if !status.success() {
+                return Err(Error::config(format!("git config --global {} failed ({})", name, status)));
+            }
+        }
+        println!("git now signs with {} when signing is on (git commit -S, or commit.gpgsign)", signing_key.display());
+        Ok(())
+    }
+}
+
+/// A git configuration value, if git is installed and the value is set
+fn git_config(name: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", "--get", name]).output().ok()?;
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+fn home() -> Result<PathBuf, Error> {
+    crate::install::home_dir().ok_or_else(|| Error::config("--setup-git-signing: cannot determine home directory"))
+}
+
+/// git resolves relative paths against wherever it runs, so settings get absolute ones
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| std::env::current_dir().map_or_else(|_| path.to_path_buf(), |dir| dir.join(path)))
+}
+
+/// Quote for a POSIX shell when needed, for commands printed to be pasted
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "/._-+@:=~".contains(c)) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// `PRINCIPAL namespaces="NAMESPACE" ssh-ed25519 AAAA...`, the allowed-signers
+/// format from ssh-keygen(1); the key's comment is left out
+fn allowed_signer(principal: &str, namespace: &str, public: &PublicKey) -> Result<String, Error> {
+    check_principal(principal)?;
+    let key = PublicKey::new(public.key_data().clone(), "")
+        .to_openssh()
+        .map_err(|e| Error::io("encoding public key", e))?;
+    Ok(format!("{} namespaces=\"{}\" {}", principal, namespace, key))
+}
+
+fn check_principal(principal: &str) -> Result<(), Error> {
+    if principal.is_empty() || principal.contains(char::is_whitespace) || principal.contains('"') {
+        return Err(Error::config(format!(
+            "principal \"{}\" must be a name or email address without spaces or quotes",
+            principal
+        )));
+    }
+    Ok(())
+}
+
+/// Append `line` unless the file already has it; whether it was added
+fn add_line(path: &str, line: &str) -> Result<bool, Error> {
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::io(format!("reading {}", path), e)),
+    };
+    if existing.lines().any(|existing| existing.trim() == line) {
+        return Ok(false);
+    }
+    let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(format!("{}{}\n", separator, line).as_bytes()))
+        .map_err(|e| Error::io(format!("writing {}", path), e))?;
+    Ok(true)
+}
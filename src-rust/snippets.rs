@@ -0,0 +1,139 @@
+//! `--emit cloud-init`, `--emit terraform` and `--emit ansible`: the public key
+//! already wrapped in the snippet infrastructure code takes, so it never has to
+//! be copied in by hand. cloud-init gets a `users` entry with the key in
+//! `ssh_authorized_keys`; Terraform gets an `aws_key_pair` resource; Ansible
+//! gets a vars file a provisioning play can load with `include_vars`.
+
+/// `<name>.cloud-init.yaml` and `<name>.tf`, named after the public key file
+pub fn output_paths(public_key_file: &str) -> (String, String) {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    (format!("{}.cloud-init.yaml", base), format!("{}.tf", base))
+}
+
+/// `<name>.ansible.yml`, named after the public key file
+pub fn ansible_path(public_key_file: &str) -> String {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    format!("{}.ansible.yml", base)
+}
+
+/// A name Ansible accepts for a variable
+pub fn validate_variable(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("--ansible-var {:?} is not a valid variable name (e.g. deploy_key)", name));
+    }
+    Ok(())
+}
+
+/// A user name cloud-init (useradd) accepts
+pub fn validate_user(user: &str) -> Result<(), String> {
+    let mut chars = user.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        && user.len() <= 32;
+    if !valid {
+        return Err(format!("--cloud-init-user {:?} is not a valid user name (e.g. deploy)", user));
+    }
+    Ok(())
+}
+
+/// A `#cloud-config` document that keeps the image's default user and adds
+/// `user` with the key; sudo rights are left for the reader to grant
+pub fn cloud_init(public_key: &str, user: &str, fingerprint: &str) -> String {
+    format!(
+        "#cloud-config\n\
+         # {}\n\
+         users:\n  \
+           - default\n  \
+           - name: {}\n    \
+             shell: /bin/bash\n    \
+             ssh_authorized_keys:\n      \
+               - {}\n",
+        fingerprint,
+        user,
+        yaml_string(public_key.trim())
+    )
+}
+
+/// What a search produced, for the Ansible vars file
+pub struct AnsibleKey<'a> {
+    pub public_key: &'a str,
+    pub fingerprint: &'a str,
+    pub target: &'a str,
+    /// Absolute paths, since the play rarely runs where the key was written
+    pub private_key_path: &'a str,
+    pub public_key_path: &'a str,
+    /// Passphrase-protected or age-encrypted
+    pub encrypted: bool,
+}
+
+/// A vars file with everything under one variable, e.g. `{{ variable.public_key }}`
+pub fn ansible(variable: &str, key: &AnsibleKey) -> String {
+    format!(
+        "---\n\
+         # {}\n\
+         {}:\n  \
+           public_key: {}\n  \
+           fingerprint: {}\n  \
+           target: {}\n  \
+           private_key_path: {}\n  \
+           public_key_path: {}\n  \
+           private_key_encrypted: {}\n",
+        key.fingerprint,
+        variable,
+        yaml_string(key.public_key.trim()),
+        yaml_string(key.fingerprint),
+        yaml_string(key.target),
+        yaml_string(key.private_key_path),
+        yaml_string(key.public_key_path),
+        key.encrypted
+    )
+}
+
+/// A double-quoted YAML scalar
+fn yaml_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// An `aws_key_pair` resource named after the key file, with `key_name` as
+/// the key pair's name in EC2
+pub fn terraform(public_key: &str, key_name: &str, public_key_file: &str, fingerprint: &str) -> String {
+    let base = public_key_file.strip_suffix(".pub").unwrap_or(public_key_file);
+    let file_name = std::path::Path::new(base).file_name().map_or(base.into(), |name| name.to_string_lossy());
+    // Terraform names are letters, digits, _ and -, and cannot start with a digit
+    let mut label: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    if !label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        label.insert(0, '_');
+    }
+    format!(
+        "# {}\nresource \"aws_key_pair\" \"{}\" {{\n  key_name   = \"{}\"\n  public_key = \"{}\"\n}}\n",
+        fingerprint,
+        label,
+        hcl_string(key_name),
+        hcl_string(public_key.trim())
+    )
+}
+
+/// The inside of an HCL quoted string: escapes, and no template interpolation
+fn hcl_string(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
@@ -0,0 +1,199 @@
+//! `--log syslog|journald|eventlog`: start, progress, match and end-of-run events
+//! in the system log, for searches running as a service with nobody watching the
+//! terminal. Syslog messages go to `/dev/log` with facility `user`; journald
+//! entries go over its native socket and carry `SSH_KEYGEN_*` fields as well,
+//! so `journalctl SSH_KEYGEN_EVENT=match` finds every key found. Either takes
+//! `:SOCKET` for a log socket elsewhere, e.g. inside a container. On Windows,
+//! events go to the Application event log, with one event ID per kind of event.
+//!
+//! Set once at startup; logging never stops a search, so failed sends are ignored.
+
+use std::io;
+use std::sync::OnceLock;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+const IDENTIFIER: &str = "ssh-keygen";
+
+/// Syslog severities, which journald uses as well
+#[derive(Debug, Clone, Copy)]
+pub enum Priority {
+    Err = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sink {
+    Syslog,
+    Journald,
+    EventLog,
+}
+
+pub struct Logger {
+    sink: Sink,
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+    /// The event source handle
+    #[cfg(windows)]
+    source: usize,
+    /// The socket, or the event log source name
+    path: String,
+}
+
+impl Logger {
+    /// `syslog` or `journald`, optionally followed by `:SOCKET`, or `eventlog`,
+    /// optionally followed by `:SOURCE`
+    pub fn open(spec: &str) -> Result<Self, String> {
+        let (name, path) = spec.split_once(':').map_or((spec, None), |(name, path)| (name, Some(path)));
+        let (sink, default_path) = match name {
+            "syslog" => (Sink::Syslog, "/dev/log"),
+            "journald" => (Sink::Journald, "/run/systemd/journal/socket"),
+            "eventlog" => (Sink::EventLog, IDENTIFIER),
+            other => return Err(format!("--log: unknown destination \"{}\" (syslog, journald or eventlog)", other)),
+        };
+        let path = path.unwrap_or(default_path).to_string();
+        match sink {
+            Sink::EventLog => Self::register(sink, path),
+            _ => Self::connect(sink, path),
+        }
+    }
+
+    #[cfg(unix)]
+    fn connect(sink: Sink, path: String) -> Result<Self, String> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()
+            .and_then(|socket| socket.connect(&path).map(|()| socket))
+            .map_err(|e| format!("--log: cannot connect to {}: {}", path, e))?;
+        Ok(Logger { sink, socket, path })
+    }
+
+    #[cfg(not(unix))]
+    fn connect(_sink: Sink, _path: String) -> Result<Self, String> {
+        Err("--log: syslog and journald are only available on Unix".to_string())
+    }
+
+    #[cfg(windows)]
+    fn register(sink: Sink, source: String) -> Result<Self, String> {
+        use windows_sys::Win32::System::EventLog::RegisterEventSourceW;
+        // SAFETY: a null server name is the local machine; the source name is
+        // NUL-terminated and outlives the call
+        let handle = unsafe { RegisterEventSourceW(std::ptr::null(), crate::service::wide(&source).as_ptr()) };
+        if handle.is_null() {
+            return Err(format!("--log: cannot open the event log as {}: {}", source, io::Error::last_os_error()));
+        }
+        Ok(Logger {
+            sink,
+            source: handle as usize,
+            path: source,
+        })
+    }
+
+    #[cfg(not(windows))]
+    fn register(_sink: Sink, _source: String) -> Result<Self, String> {
+        Err("--log: the event log is only available on Windows".to_string())
+    }
+
+    /// Where entries go, for messages
+    pub fn describe(&self) -> String {
+        match self.sink {
+            Sink::Syslog => format!("syslog ({})", self.path),
+            Sink::Journald => format!("journald ({})", self.path),
+            Sink::EventLog => format!("the event log (source {})", self.path),
+        }
+    }
+
+    fn send(&self, priority: Priority, event: &str, message: &str, fields: &[(&str, String)]) -> io::Result<()> {
+        // One line per entry: both formats would need more framing for newlines
+        let message = message.replace('\n', " ");
+        let datagram = match self.sink {
+            Sink::EventLog => return self.report(priority, event, &message),
+            Sink::Syslog => format!(
+                "<{}>{} {}[{}]: {}",
+                8 + priority as u8, // facility user
+                chrono::Local::now().format("%b %e %H:%M:%S"),
+                IDENTIFIER,
+                std::process::id(),
+                message
+            ),
+            Sink::Journald => {
+                let mut entry = format!(
+                    "MESSAGE={}\nPRIORITY={}\nSYSLOG_IDENTIFIER={}\nSYSLOG_PID={}\nSSH_KEYGEN_EVENT={}\n",
+                    message,
+                    priority as u8,
+                    IDENTIFIER,
+                    std::process::id(),
+                    event
+                );
+                for (name, value) in fields {
+                    entry.push_str(&format!("SSH_KEYGEN_{}={}\n", name, value.replace('\n', " ")));
+                }
+                entry
+            }
+        };
+        self.write(datagram.as_bytes())
+    }
+
+    #[cfg(unix)]
+    fn write(&self, datagram: &[u8]) -> io::Result<()> {
+        self.socket.send(datagram).map(|_| ())
+    }
+
+    #[cfg(not(unix))]
+    fn write(&self, _datagram: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn report(&self, priority: Priority, event: &str, message: &str) -> io::Result<()> {
+        use windows_sys::Win32::System::EventLog::{
+            ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE,
+        };
+        let kind = match priority {
+            Priority::Err => EVENTLOG_ERROR_TYPE,
+            Priority::Warning => EVENTLOG_WARNING_TYPE,
+            Priority::Notice | Priority::Info => EVENTLOG_INFORMATION_TYPE,
+        };
+        // Event Viewer can filter on these
+        let id = match event {
+            "start" => 1,
+            "progress" => 2,
+            "match" => 3,
+            "finished" => 4,
+            _ => 5,
+        };
+        let text = crate::service::wide(message);
+        let strings = [text.as_ptr()];
+        // SAFETY: the handle came from RegisterEventSourceW and the one string is
+        // NUL-terminated; there is no user SID or binary data
+        let reported = unsafe {
+            ReportEventW(self.source as _, kind, 0, id, std::ptr::null_mut(), 1, 0, strings.as_ptr(), std::ptr::null())
+        };
+        if reported == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn report(&self, _priority: Priority, _event: &str, _message: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Send every later event to `logger`
+pub fn init(logger: Logger) {
+    let _ = LOGGER.set(logger);
+}
+
+pub fn enabled() -> bool {
+    LOGGER.get().is_some()
+}
+
+/// Log `message` if `--log` was given. `event` is start, progress, match,
+/// finished or error; `fields` (TARGET, ATTEMPTS, ...) only reach journald.
+pub fn log(priority: Priority, event: &str, message: &str, fields: &[(&str, String)]) {
+    if let Some(logger) = LOGGER.get() {
+        let _ = logger.send(priority, event, message, fields);
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+use std::time::Duration;
+
+const HEADER: &str = "timestamp,elapsed_seconds,attempts,rate,avg_rate,threads";
+
+/// `--telemetry-csv`: one row per progress tick, appended so several runs
+/// (or machines) can share a file
+pub struct CsvLog {
+    file: File,
+    path: String,
+    threads: usize,
+}
+
+impl CsvLog {
+    /// Open for appending, writing the header only into a new or empty file
+    pub fn open(path: &str, threads: usize) -> Result<Self, std::io::Error> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", HEADER)?;
+        }
+        Ok(Self {
+            file,
+            path: path.to_string(),
+            threads,
+        })
+    }
+
+    pub fn record(&mut self, elapsed_seconds: f64, attempts: u64, rate: u64, avg_rate: f64) -> Result<(), String> {
+        writeln!(
+            self.file,
+            "{},{:.3},{},{},{:.0},{}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            elapsed_seconds,
+            attempts,
+            rate,
+            avg_rate,
+            self.threads
+        )
+        .map_err(|e| format!("{}: {}", self.path, e))
+    }
+}
+
+/// Exponentially weighted moving average of the per-tick rate for the progress
+/// line. A tick's count depends on where the workers' batches happen to end, so
+/// the raw per-second rate jumps around; the weight of each tick decays with
+/// time rather than tick count, so late or missed ticks are weighed correctly.
+pub struct SmoothedRate {
+    /// Time constant: a reading's weight falls to 1/e after this long
+    window: f64,
+    value: Option<f64>,
+}
+
+impl SmoothedRate {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window: window.as_secs_f64(),
+            value: None,
+        }
+    }
+
+    /// Fold in the rate measured over the last `interval`; returns the new average
+    pub fn update(&mut self, rate: f64, interval: Duration) -> f64 {
+        let weight = 1.0 - (-interval.as_secs_f64() / self.window).exp();
+        let value = match self.value {
+            Some(value) => value + weight * (rate - value),
+            None => rate,
+        };
+        self.value = Some(value);
+        value
+    }
+}
@@ -0,0 +1,114 @@
+//! CPU temperature, for `--max-temp`: long searches on small machines with
+//! little cooling otherwise sit at their thermal limit for hours. Above the
+//! limit workers are parked a step at a time, and brought back once the CPU
+//! has cooled.
+
+use std::time::{Duration, Instant};
+
+/// The CPU must drop this far below the limit before a worker comes back
+const COOL_MARGIN: f64 = 5.0;
+/// Time for the temperature to respond to a change before the next one
+const SETTLE: Duration = Duration::from_secs(10);
+
+/// The hottest CPU sensor in °C; None where there is none to read
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn cpu_temperature() -> Option<f64> {
+    let millidegrees = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path).ok()?.trim().parse::<f64>().ok().map(|value| value / 1000.0)
+    };
+    let hottest = |readings: Vec<f64>| readings.into_iter().reduce(f64::max);
+
+    // hwmon drivers for the CPU package and cores
+    let mut readings = Vec::new();
+    for hwmon in std::fs::read_dir("/sys/class/hwmon").into_iter().flatten().flatten() {
+        let name = std::fs::read_to_string(hwmon.path().join("name")).unwrap_or_default();
+        if !matches!(name.trim(), "coretemp" | "k10temp" | "zenpower" | "cpu_thermal") {
+            continue;
+        }
+        for entry in std::fs::read_dir(hwmon.path()).into_iter().flatten().flatten() {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            if file.starts_with("temp") && file.ends_with("_input") {
+                readings.extend(millidegrees(entry.path()));
+            }
+        }
+    }
+    if !readings.is_empty() {
+        return hottest(readings);
+    }
+
+    // Otherwise the thermal zones that belong to the CPU (ARM boards, some laptops)
+    for zone in std::fs::read_dir("/sys/class/thermal").into_iter().flatten().flatten() {
+        let kind = std::fs::read_to_string(zone.path().join("type")).unwrap_or_default();
+        let kind = kind.trim();
+        if kind == "x86_pkg_temp" || kind.contains("cpu") || kind.contains("soc") {
+            readings.extend(millidegrees(zone.path().join("temp")));
+        }
+    }
+    hottest(readings)
+}
+
+/// macOS and Windows only expose CPU sensors to privileged tools
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn cpu_temperature() -> Option<f64> {
+    None
+}
+
+/// Decides how many workers run, fed a reading every poll
+pub struct Governor {
+    limit: f64,
+    workers: usize,
+    active: usize,
+    last_change: Option<Instant>,
+}
+
+impl Governor {
+    pub fn new(limit: f64, workers: usize) -> Self {
+        Self {
+            limit,
+            workers,
+            active: workers,
+            last_change: None,
+        }
+    }
+
+    /// Workers parked or brought back per change: a quarter of them, at least one
+    fn step(&self) -> usize {
+        self.workers.div_ceil(4)
+    }
+
+    /// Take a reading; returns the new worker count and a message when it changes.
+    /// With every worker parked the search waits for the CPU to cool, so even a
+    /// single worker ends up running on a duty cycle.
+    pub fn update(&mut self, temperature: f64) -> Option<(usize, String)> {
+        if self.last_change.is_some_and(|at| at.elapsed() < SETTLE) {
+            return None;
+        }
+        let active = if temperature > self.limit && self.active > 0 {
+            self.active.saturating_sub(self.step())
+        } else if temperature <= self.limit - COOL_MARGIN && self.active < self.workers {
+            (self.active + self.step()).min(self.workers)
+        } else {
+            return None;
+        };
+        let hot = active < self.active;
+        self.active = active;
+        self.last_change = Some(Instant::now());
+        let message = match (hot, active) {
+            (true, 0) => format!(
+                "CPU at {:.0}°C (limit {:.0}°C): search paused until it cools to {:.0}°C",
+                temperature,
+                self.limit,
+                self.limit - COOL_MARGIN
+            ),
+            (true, _) => format!(
+                "CPU at {:.0}°C (limit {:.0}°C): down to {} of {} workers",
+                temperature, self.limit, active, self.workers
+            ),
+            (false, _) => format!(
+                "CPU cooled to {:.0}°C: up to {} of {} workers",
+                temperature, active, self.workers
+            ),
+        };
+        Some((active, message))
+    }
+}
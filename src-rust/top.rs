@@ -0,0 +1,103 @@
+//! `--top`: the progress line plus a table of workers, redrawn each second in
+//! place. Each row shows a worker's attempts, its rate over the last second,
+//! that rate as a share of the median worker, and the CPU it last ran on;
+//! a worker below the lagging share is marked. Redrawing only needs the
+//! cursor-up and clear-to-end escapes every terminal emulator supports.
+//! With `--ci` each table is printed below the last instead.
+
+use crate::balance::{self, Balance};
+use crate::output;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Kept by the progress thread
+pub struct Top {
+    last: Vec<u64>,
+    /// Redraw in place, rather than print each table below the last
+    redraw: bool,
+    /// Lines of the table on screen, for the next redraw to go back over
+    drawn: usize,
+}
+
+impl Top {
+    pub fn new(workers: usize, redraw: bool) -> Self {
+        Self {
+            last: vec![0; workers],
+            redraw,
+            drawn: 0,
+        }
+    }
+
+    /// Draw the table under `summary` (the progress line) from each worker's
+    /// running total and the CPU it last ran on
+    pub fn draw(&mut self, summary: &str, totals: &[u64], cpus: &[Option<usize>], balance: &Balance, interval: Duration) {
+        let seconds = interval.as_secs_f64().max(f64::EPSILON);
+        let rates: Vec<f64> = totals
+            .iter()
+            .zip(&self.last)
+            .map(|(total, last)| total.saturating_sub(*last) as f64 / seconds)
+            .collect();
+        self.last = totals.to_vec();
+
+        let mut running: Vec<f64> = (0..rates.len())
+            .filter(|&worker| balance.is_running(worker))
+            .map(|worker| rates[worker])
+            .collect();
+        running.sort_by(f64::total_cmp);
+        let median = running.get(running.len() / 2).copied().filter(|median| *median > 0.0);
+
+        let mut frame = String::new();
+        if self.redraw && self.drawn > 0 {
+            let _ = write!(frame, "\x1b[{}A", self.drawn);
+        }
+        if self.redraw {
+            frame.push_str("\r\x1b[J");
+        }
+        let _ = writeln!(frame, "{}", summary);
+        let _ = writeln!(frame, "{:>6}  {:>4}  {:>14}  {:>12}  {:>6}", "Worker", "CPU", "Attempts", "Rate", "Share");
+        for (worker, (total, rate)) in totals.iter().zip(&rates).enumerate() {
+            let cpu = cpus[worker].map_or("-".to_string(), |cpu| cpu.to_string());
+            let (rate, share, lagging) = if !balance.is_running(worker) {
+                ("idle".to_string(), String::new(), false)
+            } else {
+                let share = median.map(|median| rate / median);
+                (
+                    output::rate(*rate),
+                    share.map_or(String::new(), |share| format!("{:.0}%", share * 100.0)),
+                    share.is_some_and(|share| share < balance::LAG_SHARE),
+                )
+            };
+            let row = format!("{:>6}  {:>4}  {:>14}  {:>12}  {:>6}", worker, cpu, output::count(*total), rate, share);
+            let _ = match (lagging, self.redraw) {
+                // Reverse video
+                (true, true) => writeln!(frame, "\x1b[7m{}  slow\x1b[0m", row),
+                (true, false) => writeln!(frame, "{}  slow", row),
+                (false, _) => writeln!(frame, "{}", row),
+            };
+        }
+        self.drawn = totals.len() + 2;
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(frame.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    /// Something else was printed below the table; draw the next one below it
+    pub fn interrupted(&mut self) {
+        self.drawn = 0;
+    }
+}
+
+/// The CPU this thread is running on, where the OS can tell
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn current_cpu() -> Option<usize> {
+    // SAFETY: sched_getcpu has no preconditions
+    let cpu = unsafe { libc::sched_getcpu() };
+    usize::try_from(cpu).ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn current_cpu() -> Option<usize> {
+    None
+}
@@ -0,0 +1,293 @@
+#[cfg(feature = "uploaders")]
+use std::process::Command;
+
+#[cfg(feature = "uploaders")]
+const USER_AGENT: &str = concat!("ssh-keygen/", env!("CARGO_PKG_VERSION"));
+
+/// Services accepted by `--upload`
+pub const SERVICES: [&str; 9] = [
+    "github",
+    "gitlab",
+    "gitea",
+    "codeberg",
+    "aws",
+    "digitalocean",
+    "hetzner",
+    "vultr",
+    "linode",
+];
+
+#[cfg(feature = "uploaders")]
+/// Cloud providers whose SSH key API is one bearer-token JSON POST
+static CLOUDS: [Cloud; 4] = [
+    Cloud {
+        name: "digitalocean",
+        api_url: "https://api.digitalocean.com/v2",
+        path: "/account/keys",
+        token_vars: &["DIGITALOCEAN_TOKEN", "DIGITALOCEAN_ACCESS_TOKEN"],
+        name_field: "name",
+        key_field: "public_key",
+    },
+    Cloud {
+        name: "hetzner",
+        api_url: "https://api.hetzner.cloud/v1",
+        path: "/ssh_keys",
+        token_vars: &["HCLOUD_TOKEN"],
+        name_field: "name",
+        key_field: "public_key",
+    },
+    Cloud {
+        name: "vultr",
+        api_url: "https://api.vultr.com/v2",
+        path: "/ssh-keys",
+        token_vars: &["VULTR_API_KEY"],
+        name_field: "name",
+        key_field: "ssh_key",
+    },
+    Cloud {
+        name: "linode",
+        api_url: "https://api.linode.com/v4",
+        path: "/profile/sshkeys",
+        token_vars: &["LINODE_TOKEN", "LINODE_CLI_TOKEN"],
+        name_field: "label",
+        key_field: "ssh_key",
+    },
+];
+
+#[cfg(feature = "uploaders")]
+/// A forge, cloud provider or service that public keys can be registered with
+pub trait Uploader {
+    /// Human-readable service name used in messages
+    fn name(&self) -> &str;
+
+    /// Register the public key with the authenticated account
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "uploaders")]
+/// Build the uploader for a service name, optionally overriding its API base URL;
+/// `region` is the AWS region for `aws`
+pub fn uploader(
+    service: &str,
+    api_url: Option<&str>,
+    region: Option<&str>,
+) -> Result<Box<dyn Uploader>, Box<dyn std::error::Error>> {
+    let api_url = api_url.map(|url| url.trim_end_matches('/').to_string());
+    if let Some(cloud) = CLOUDS.iter().find(|cloud| cloud.name == service) {
+        return Ok(Box::new(CloudUploader {
+            cloud,
+            api_url: api_url.unwrap_or_else(|| cloud.api_url.to_string()),
+        }));
+    }
+    match service {
+        "github" => Ok(Box::new(GitHub {
+            api_url: api_url.unwrap_or_else(|| "https://api.github.com".to_string()),
+        })),
+        "gitlab" => Ok(Box::new(GitLab {
+            api_url: api_url.unwrap_or_else(|| "https://gitlab.com/api/v4".to_string()),
+        })),
+        "gitea" => Ok(Box::new(Gitea {
+            name: "gitea",
+            api_url: api_url.ok_or("--upload gitea requires --api-url (e.g. https://git.example.com/api/v1)")?,
+            token_vars: &["GITEA_TOKEN"],
+        })),
+        "codeberg" => Ok(Box::new(Gitea {
+            name: "codeberg",
+            api_url: api_url.unwrap_or_else(|| "https://codeberg.org/api/v1".to_string()),
+            token_vars: &["CODEBERG_TOKEN", "GITEA_TOKEN"],
+        })),
+        "aws" => {
+            let region = crate::aws::region(region)?;
+            Ok(Box::new(Ec2 {
+                name: format!("aws ({})", region),
+                endpoint: api_url.unwrap_or_else(|| crate::aws::endpoint(&region)),
+                region,
+            }))
+        }
+        other => Err(format!("unsupported upload service: {}", other).into()),
+    }
+}
+
+/// Expand `{target}` and `{fingerprint}` placeholders in a key title template
+pub fn render_title(template: &str, target: &str, fingerprint: &str) -> String {
+    template
+        .replace("{target}", target)
+        .replace("{fingerprint}", fingerprint)
+}
+
+#[cfg(feature = "uploaders")]
+/// Return the first non-empty token found in the given environment variables
+fn env_token(vars: &[&str]) -> Option<String> {
+    vars.iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+#[cfg(feature = "uploaders")]
+/// Send a JSON body and turn non-2xx responses into readable errors
+fn send_json(
+    service: &str,
+    request: ureq::Request,
+    body: serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match request.set("User-Agent", USER_AGENT).send_json(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("{} API returned {}: {}", service, code, body.trim()).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// GitHub (or GitHub Enterprise via `--api-url`) user keys API
+struct GitHub {
+    api_url: String,
+}
+
+#[cfg(feature = "uploaders")]
+impl GitHub {
+    /// Token from the environment, falling back to the credential stored by
+    /// the `gh` CLI (which lives in the system keyring)
+    fn token() -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(token) = env_token(&["GITHUB_TOKEN", "GH_TOKEN"]) {
+            return Ok(token);
+        }
+
+        if let Ok(output) = Command::new("gh").args(["auth", "token"]).output() {
+            let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !token.is_empty() {
+                return Ok(token);
+            }
+        }
+
+        Err("no GitHub token found (set GITHUB_TOKEN or log in with `gh auth login`)".into())
+    }
+}
+
+#[cfg(feature = "uploaders")]
+impl Uploader for GitHub {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let request = ureq::post(&format!("{}/user/keys", self.api_url))
+            .set("Authorization", &format!("Bearer {}", Self::token()?))
+            .set("Accept", "application/vnd.github+json")
+            .set("X-GitHub-Api-Version", "2022-11-28");
+        send_json(
+            "GitHub",
+            request,
+            serde_json::json!({ "title": title, "key": public_key.trim() }),
+        )
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// GitLab.com or self-managed GitLab user keys API
+struct GitLab {
+    api_url: String,
+}
+
+#[cfg(feature = "uploaders")]
+impl Uploader for GitLab {
+    fn name(&self) -> &str {
+        "gitlab"
+    }
+
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let token = env_token(&["GITLAB_TOKEN"]).ok_or("no GitLab token found (set GITLAB_TOKEN)")?;
+        let request = ureq::post(&format!("{}/user/keys", self.api_url)).set("PRIVATE-TOKEN", &token);
+        send_json(
+            "GitLab",
+            request,
+            serde_json::json!({ "title": title, "key": public_key.trim() }),
+        )
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// Gitea-compatible user keys API (Gitea, Forgejo, Codeberg)
+struct Gitea {
+    name: &'static str,
+    api_url: String,
+    token_vars: &'static [&'static str],
+}
+
+#[cfg(feature = "uploaders")]
+impl Uploader for Gitea {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let token = env_token(self.token_vars)
+            .ok_or_else(|| format!("no {} token found (set {})", self.name, self.token_vars.join(" or ")))?;
+        let request = ureq::post(&format!("{}/user/keys", self.api_url))
+            .set("Authorization", &format!("token {}", token));
+        send_json(
+            self.name,
+            request,
+            serde_json::json!({ "title": title, "key": public_key.trim() }),
+        )
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// EC2 key pairs in one region; the title is the key pair name
+struct Ec2 {
+    name: String,
+    endpoint: String,
+    region: String,
+}
+
+#[cfg(feature = "uploaders")]
+impl Uploader for Ec2 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+        crate::aws::import_key_pair(&self.endpoint, &self.region, title, public_key)
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// A cloud provider's SSH key API: where it is, which token it takes and what
+/// the JSON fields are called
+struct Cloud {
+    name: &'static str,
+    api_url: &'static str,
+    path: &'static str,
+    token_vars: &'static [&'static str],
+    name_field: &'static str,
+    key_field: &'static str,
+}
+
+#[cfg(feature = "uploaders")]
+/// Account SSH keys at a cloud provider, for new droplets and servers
+struct CloudUploader {
+    cloud: &'static Cloud,
+    api_url: String,
+}
+
+#[cfg(feature = "uploaders")]
+impl Uploader for CloudUploader {
+    fn name(&self) -> &str {
+        self.cloud.name
+    }
+
+    fn upload(&self, public_key: &str, title: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let token = env_token(self.cloud.token_vars)
+            .ok_or_else(|| format!("no {} token found (set {})", self.cloud.name, self.cloud.token_vars.join(" or ")))?;
+        let request = ureq::post(&format!("{}{}", self.api_url, self.cloud.path))
+            .set("Authorization", &format!("Bearer {}", token));
+        let mut body = serde_json::Map::new();
+        body.insert(self.cloud.name_field.to_string(), title.into());
+        body.insert(self.cloud.key_field.to_string(), public_key.trim().into());
+        send_json(self.cloud.name, request, body.into())
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(feature = "uploaders")]
+use std::fs;
+#[cfg(feature = "uploaders")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "uploaders")]
+const USER_AGENT: &str = concat!("ssh-keygen/", env!("CARGO_PKG_VERSION"));
+
+/// Location in Vault parsed from a `vault://mount/path` URL
+#[derive(Debug, Clone)]
+pub struct VaultLocation {
+    pub mount: String,
+    pub path: String,
+}
+
+impl VaultLocation {
+    /// Parse `vault://mount/path/to/secret`
+    pub fn parse(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let rest = url
+            .strip_prefix("vault://")
+            .ok_or_else(|| format!("unsupported store URL {:?} (expected vault://mount/path)", url))?;
+        let rest = rest.trim_matches('/');
+        match rest.split_once('/') {
+            Some((mount, path)) if !mount.is_empty() && !path.is_empty() => Ok(Self {
+                mount: mount.to_string(),
+                path: path.trim_matches('/').to_string(),
+            }),
+            _ => Err(format!("store URL {:?} must include a mount and a path", url).into()),
+        }
+    }
+}
+
+impl std::fmt::Display for VaultLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "vault://{}/{}", self.mount, self.path)
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// Connection settings taken from the standard Vault CLI environment
+struct VaultClient {
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+}
+
+#[cfg(feature = "uploaders")]
+impl VaultClient {
+    fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let addr = std::env::var("VAULT_ADDR").map_err(|_| "VAULT_ADDR is not set")?;
+
+        // Same lookup order as the vault CLI: VAULT_TOKEN, then ~/.vault-token
+        let token = match std::env::var("VAULT_TOKEN") {
+            Ok(token) if !token.trim().is_empty() => token,
+            _ => crate::install::home_dir()
+                .and_then(|home| fs::read_to_string(home.join(".vault-token")).ok())
+                .ok_or("no Vault token found (set VAULT_TOKEN or run `vault login`)")?,
+        };
+
+        Ok(Self {
+            addr: addr.trim_end_matches('/').to_string(),
+            token: token.trim().to_string(),
+            namespace: std::env::var("VAULT_NAMESPACE").ok().filter(|ns| !ns.is_empty()),
+        })
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let mut request = ureq::request(method, &format!("{}/v1/{}", self.addr, path))
+            .set("X-Vault-Token", &self.token)
+            .set("User-Agent", USER_AGENT);
+        if let Some(namespace) = &self.namespace {
+            request = request.set("X-Vault-Namespace", namespace);
+        }
+        request
+    }
+
+    /// Ask Vault which KV engine version backs a mount (defaults to v2)
+    fn kv_version(&self, mount: &str) -> u8 {
+        self.request("GET", &format!("sys/internal/ui/mounts/{}", mount))
+            .call()
+            .ok()
+            .and_then(|response| response.into_json::<serde_json::Value>().ok())
+            .and_then(|body| body["data"]["options"]["version"].as_str().map(str::to_string))
+            .map_or(2, |version| if version == "1" { 1 } else { 2 })
+    }
+}
+
+#[cfg(feature = "uploaders")]
+/// Write the secret fields to the KV engine at `location`
+pub fn store(
+    location: &VaultLocation,
+    data: serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = VaultClient::from_env()?;
+
+    let (path, mut payload) = if client.kv_version(&location.mount) == 1 {
+        (format!("{}/{}", location.mount, location.path), data)
+    } else {
+        (
+            format!("{}/data/{}", location.mount, location.path),
+            serde_json::json!({ "data": data }),
+        )
+    };
+    let body = Zeroizing::new(serde_json::to_string(&payload)?);
+    crate::secret::wipe_json(&mut payload);
+
+    let result = client
+        .request("POST", &path)
+        .set("Content-Type", "application/json")
+        .send_string(&body);
+    match result {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("Vault returned {}: {}", code, body.trim()).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
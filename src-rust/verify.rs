@@ -0,0 +1,88 @@
+use crate::error::Error;
+use crate::matching::Matcher;
+use crate::passphrase::{self, PassphraseSource};
+use ssh_key::{HashAlg, PrivateKey, PublicKey};
+use std::path::Path;
+
+/// Options for `ssh-keygen verify`
+pub struct VerifyOptions<'a> {
+    pub private_key_file: &'a str,
+    /// Defaults to `<private>.pub`
+    pub public_key_file: Option<&'a str>,
+    pub target: Option<&'a str>,
+    pub case_sensitive: bool,
+    pub passphrase: PassphraseSource,
+}
+
+/// Check that a key pair on disk belongs together and report what it is
+pub fn run(options: &VerifyOptions) -> Result<(), Error> {
+    let private_path = options.private_key_file;
+    let public_path = options
+        .public_key_file
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}.pub", private_path));
+
+    let mut private = PrivateKey::read_openssh_file(Path::new(private_path))
+        .map_err(|e| Error::config(format!("{}: cannot read private key: {}", private_path, e)))?;
+    let encrypted = private.is_encrypted();
+    if encrypted {
+        let passphrase = passphrase::resolve_existing(&options.passphrase, private_path)
+            .map_err(Error::config)?;
+        private = private
+            .decrypt(passphrase.expose())
+            .map_err(|_| Error::config(format!("{}: incorrect passphrase", private_path)))?;
+    }
+
+    let public_line = std::fs::read_to_string(&public_path)
+        .map_err(|e| Error::config(format!("{}: cannot read public key: {}", public_path, e)))?;
+    let public = PublicKey::from_openssh(public_line.trim())
+        .map_err(|e| Error::config(format!("{}: cannot parse public key: {}", public_path, e)))?;
+
+    println!(
+        "Private key: {} ({}{})",
+        private_path,
+        private.algorithm(),
+        if encrypted { ", encrypted" } else { "" }
+    );
+    println!("Public key:  {}", public_path);
+    println!("Comment:     {}", display_comment(public.comment()));
+    println!("Fingerprint: {}", public.fingerprint(HashAlg::Sha256));
+    println!("Fingerprint: {}", crate::fingerprint::md5(&public)?);
+
+    if private.public_key().key_data() != public.key_data() {
+        return Err(Error::Integrity(format!(
+            "{} does not belong to {}",
+            public_path, private_path
+        )));
+    }
+    // For Ed25519 also prove the private half can actually sign
+    if let Some(keypair) = private.key_data().ed25519() {
+        let seed = zeroize::Zeroizing::new(keypair.private.to_bytes());
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&seed);
+        crate::selfcheck::verify_keypair(&signing_key, public_line.trim()).map_err(Error::Integrity)?;
+    }
+    println!("Key pair:    OK");
+
+    if let Some(target) = options.target {
+        let body = public
+            .to_openssh()
+            .map_err(|e| Error::io("encoding public key", e))?;
+        if !Matcher::new(target, options.case_sensitive).is_match(body.as_bytes()) {
+            return Err(Error::TargetNotFound(format!(
+                "target \"{}\" does not appear in {}",
+                target, public_path
+            )));
+        }
+        println!("Target:      \"{}\" found", target);
+    }
+    Ok(())
+}
+
+fn display_comment(comment: &str) -> &str {
+    if comment.is_empty() {
+        "(none)"
+    } else {
+        comment
+    }
+}
+
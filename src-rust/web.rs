@@ -0,0 +1,350 @@
+//! `ssh-keygen serve-web`: a one-page browser UI for long runs on a remote
+//! machine, reached through an SSH tunnel instead of a terminal left open.
+//!
+//! The page talks JSON-RPC over a WebSocket. This module carries those
+//! messages to and from an `ssh-keygen --rpc-stdio` child process, which
+//! runs the searches. Search state therefore lives on the server: closing the
+//! tab does not stop a search, and a page opened later picks it up. Every
+//! browser gets the notifications. Request ids are renumbered on the way to
+//! the backend, so each response goes back only to the page that asked.
+//!
+//! The server listens on 127.0.0.1 only. The WebSocket also needs the random
+//! token in the printed URL and a same-origin page, so other local users and
+//! other web pages cannot start searches that write key files.
+
+use crate::error::Error;
+use crate::rpc;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+const PAGE: &str = include_str!("web.html");
+
+/// RFC 6455: appended to the client's key before hashing
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+/// Requests are a few hundred bytes; anything near this is not from the page
+const MAX_MESSAGE: u64 = 64 * 1024;
+const MAX_HEADER: usize = 8 * 1024;
+/// Match and finished notifications kept for pages opened later
+const REPLAY: usize = 100;
+
+/// Options for `ssh-keygen serve-web`
+pub struct ServeOptions {
+    /// 0 picks a free port
+    pub port: u16,
+}
+
+/// Serve the page until the backend exits
+pub fn run(options: &ServeOptions) -> Result<(), Error> {
+    let listener = TcpListener::bind(("127.0.0.1", options.port))
+        .map_err(|e| Error::io(format!("listening on 127.0.0.1:{}", options.port), e))?;
+    let port = listener.local_addr().map_err(|e| Error::io("listening", e))?.port();
+    let mut token = [0u8; 16];
+    OsRng.fill_bytes(&mut token);
+    let token: String = token.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    let exe = std::env::current_exe().map_err(|e| Error::io("locating this binary", e))?;
+    let mut backend = Command::new(exe)
+        .arg("--rpc-stdio")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::io("starting the search backend", e))?;
+    let hub = Arc::new(Hub::new(backend.stdin.take().expect("stdin is piped")));
+    // Progress flows whether or not a page asked for it
+    hub.write_backend(&json!({ "jsonrpc": "2.0", "method": "subscribe", "params": { "progress": true } }))
+        .map_err(|e| Error::io("starting the search backend", e))?;
+
+    println!("Serving on http://127.0.0.1:{}/?token={}", port, token);
+    println!("From another machine: ssh -L {0}:127.0.0.1:{0} <this host>, then open the same URL", port);
+    {
+        let hub = hub.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (hub, token) = (hub.clone(), token.clone());
+                std::thread::spawn(move || {
+                    let _ = connection(stream, &hub, &token);
+                });
+            }
+        });
+    }
+
+    let stdout = backend.stdout.take().expect("stdout is piped");
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| Error::io("reading from the search backend", e))?;
+        hub.dispatch(&line);
+    }
+    let status = backend.wait().map_err(|e| Error::io("waiting for the search backend", e))?;
+    Err(Error::io("the search backend exited", status.to_string()))
+}
+
+/// A page's writer thread takes frames as opcode and payload
+type Frames = mpsc::Sender<(u8, Vec<u8>)>;
+
+/// The backend and the pages connected to it
+struct Hub {
+    backend: Mutex<ChildStdin>,
+    pages: Mutex<Vec<(u64, Frames)>>,
+    /// Backend request id -> the page that sent it and the id it used
+    pending: Mutex<HashMap<u64, (u64, Value)>>,
+    replay: Mutex<Vec<String>>,
+    next_id: AtomicU64,
+    next_page: AtomicU64,
+}
+
+impl Hub {
+    fn new(backend: ChildStdin) -> Self {
+        Hub {
+            backend: Mutex::new(backend),
+            pages: Mutex::new(Vec::new()),
+            pending: Mutex::new(HashMap::new()),
+            replay: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+            next_page: AtomicU64::new(1),
+        }
+    }
+
+    fn write_backend(&self, message: &Value) -> io::Result<()> {
+        let mut backend = self.backend.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(backend, "{}", message).and_then(|()| backend.flush())
+    }
+
+    /// A new page: send it what it missed, then everything from now on
+    fn join(&self, sender: Frames) -> u64 {
+        let page = self.next_page.fetch_add(1, Ordering::Relaxed);
+        // Under the replay lock, so no notification is both replayed and sent
+        let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        for message in replay.iter() {
+            let _ = sender.send((TEXT, message.clone().into_bytes()));
+        }
+        self.pages.lock().unwrap_or_else(|e| e.into_inner()).push((page, sender));
+        page
+    }
+
+    fn leave(&self, page: u64) {
+        self.pages.lock().unwrap_or_else(|e| e.into_inner()).retain(|(id, _)| *id != page);
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).retain(|_, (from, _)| *from != page);
+    }
+
+    /// A request from a page, renumbered for the backend
+    fn request(&self, page: u64, text: &str) {
+        let mut message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                let reply = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": rpc::PARSE_ERROR, "message": e.to_string() } });
+                return self.send_to(page, &reply.to_string());
+            }
+        };
+        if let Some(id) = message.get("id").cloned() {
+            let backend_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            self.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(backend_id, (page, id));
+            message["id"] = json!(backend_id);
+        }
+        // A dead backend ends `run`, and with it the server
+        let _ = self.write_backend(&message);
+    }
+
+    /// A line from the backend: a response goes to the page that asked, a
+    /// notification to every page
+    fn dispatch(&self, line: &str) {
+        let Ok(mut message) = serde_json::from_str::<Value>(line) else {
+            return;
+        };
+        if let Some(id) = message.get("id").and_then(Value::as_u64) {
+            let asked = self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+            if let Some((page, id)) = asked {
+                message["id"] = id;
+                self.send_to(page, &message.to_string());
+            }
+            return;
+        }
+        let text = message.to_string();
+        let mut replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        if message["method"] != "progress" {
+            if replay.len() == REPLAY {
+                replay.remove(0);
+            }
+            replay.push(text.clone());
+        }
+        self.pages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(_, sender)| sender.send((TEXT, text.clone().into_bytes())).is_ok());
+    }
+
+    fn send_to(&self, page: u64, text: &str) {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, sender)) = pages.iter().find(|(id, _)| *id == page) {
+            let _ = sender.send((TEXT, text.as_bytes().to_vec()));
+        }
+    }
+}
+
+/// One HTTP connection: the page, or a WebSocket for it
+fn connection(mut stream: TcpStream, hub: &Hub, token: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_HEADER || stream.read(&mut byte)? == 0 {
+            return Ok(());
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (method, target) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+    let headers: HashMap<String, &str> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    match (method, path) {
+        ("GET", "/") => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        ("GET", "/ws") => {
+            let given = query.split('&').find_map(|pair| pair.strip_prefix("token=")).unwrap_or_default();
+            let same_origin = match (headers.get("origin"), headers.get("host")) {
+                (Some(origin), Some(host)) => *origin == format!("http://{}", host),
+                // Not a browser, so no other site's page either
+                (None, _) => true,
+                (Some(_), None) => false,
+            };
+            if !same_token(given, token) || !same_origin {
+                return respond(&mut stream, "403 Forbidden", "text/plain", "wrong or missing token\n");
+            }
+            let Some(key) = headers.get("sec-websocket-key") else {
+                return respond(&mut stream, "400 Bad Request", "text/plain", "expected a WebSocket upgrade\n");
+            };
+            let accept = base64::engine::general_purpose::STANDARD.encode(Sha1::digest(format!("{}{}", key, WEBSOCKET_GUID)));
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept
+            )?;
+            stream.set_read_timeout(None)?;
+            websocket(stream, hub)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found\n"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Compare without stopping at the first difference
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Relay a page's messages until it goes away
+fn websocket(stream: TcpStream, hub: &Hub) -> io::Result<()> {
+    let (sender, frames) = mpsc::channel::<(u8, Vec<u8>)>();
+    let mut writer = stream.try_clone()?;
+    // A page that stops reading only holds up its own writer
+    std::thread::spawn(move || {
+        for (opcode, payload) in frames {
+            if write_frame(&mut writer, opcode, &payload).is_err() || opcode == CLOSE {
+                break;
+            }
+        }
+        let _ = writer.shutdown(std::net::Shutdown::Both);
+    });
+    let page = hub.join(sender.clone());
+    let mut reader = BufReader::new(stream);
+    let result = loop {
+        match read_frame(&mut reader) {
+            Ok((TEXT, payload)) => hub.request(page, &String::from_utf8_lossy(&payload)),
+            Ok((PING, payload)) => {
+                let _ = sender.send((PONG, payload));
+            }
+            Ok((CLOSE, _)) => {
+                let _ = sender.send((CLOSE, Vec::new()));
+                break Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    hub.leave(page);
+    result
+}
+
+/// One client frame, unmasked; fragmented messages are refused
+fn read_frame(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header)?;
+    let (fin, opcode, masked) = (header[0] & 0x80 != 0, header[0] & 0x0f, header[1] & 0x80 != 0);
+    if !fin || opcode == 0 {
+        return Err(invalid("fragmented messages are not supported"));
+    }
+    if !masked {
+        return Err(invalid("client frames must be masked"));
+    }
+    let length = match header[1] & 0x7f {
+        126 => {
+            let mut length = [0u8; 2];
+            reader.read_exact(&mut length)?;
+            u64::from(u16::from_be_bytes(length))
+        }
+        127 => {
+            let mut length = [0u8; 8];
+            reader.read_exact(&mut length)?;
+            u64::from_be_bytes(length)
+        }
+        length => u64::from(length),
+    };
+    if length > MAX_MESSAGE {
+        return Err(invalid("message too large"));
+    }
+    let mut mask = [0u8; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload)?;
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+    Ok((opcode, payload))
+}
+
+/// One unmasked server frame
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
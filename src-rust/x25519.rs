@@ -0,0 +1,184 @@
+//! `--x25519`: node keys for X25519 mesh VPNs. The search is the same; only how
+//! the public key is shown (and matched) and how the key files look differ.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use curve25519_dalek::MontgomeryPoint;
+use std::fs;
+use zeroize::Zeroizing;
+
+pub const LAYOUTS: [&str; 3] = ["nebula", "wireguard", "tailscale"];
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+const NEBULA_PRIVATE_LABEL: &str = "NEBULA X25519 PRIVATE KEY";
+const NEBULA_PUBLIC_LABEL: &str = "NEBULA X25519 PUBLIC KEY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// `nebula-cert keygen`: PEM `host.key`/`host.pub`, shown as base64
+    Nebula,
+    /// `wg genkey | wg pubkey`: one base64 line each in `privatekey`/`publickey`
+    WireGuard,
+    /// Tailscale-style `privkey:`/`nodekey:` hex, in `nodekey`/`nodekey.pub`
+    Tailscale,
+}
+
+impl Layout {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "nebula" => Some(Layout::Nebula),
+            "wireguard" => Some(Layout::WireGuard),
+            "tailscale" => Some(Layout::Tailscale),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Nebula => "nebula",
+            Layout::WireGuard => "wireguard",
+            Layout::Tailscale => "tailscale",
+        }
+    }
+
+    /// Private and public key files, in the current directory
+    pub fn output_paths(self) -> (&'static str, &'static str) {
+        match self {
+            Layout::Nebula => ("host.key", "host.pub"),
+            Layout::WireGuard => ("privatekey", "publickey"),
+            Layout::Tailscale => ("nodekey", "nodekey.pub"),
+        }
+    }
+
+    /// The public key as the VPN's configs and tools show it; targets are
+    /// matched against this. `seed` is clamped into an X25519 scalar.
+    pub fn public_key(self, seed: &[u8; 32]) -> String {
+        let public = MontgomeryPoint::mul_base_clamped(*seed).to_bytes();
+        self.encode(&public)
+    }
+
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Layout::Nebula | Layout::WireGuard => BASE64.encode(bytes),
+            Layout::Tailscale => {
+                let mut hex = String::with_capacity(bytes.len() * 2);
+                for byte in bytes {
+                    hex.push(HEX[(byte >> 4) as usize] as char);
+                    hex.push(HEX[(byte & 0x0f) as usize] as char);
+                }
+                hex
+            }
+        }
+    }
+
+    /// Reject targets the public key can never contain
+    pub fn check_target(self, target: &str, case_sensitive: bool) -> Result<(), String> {
+        let (name, length) = match self {
+            Layout::Nebula | Layout::WireGuard => ("base64", 44),
+            Layout::Tailscale => ("lowercase hex", 64),
+        };
+        if target.len() > length {
+            return Err(format!("\"{}\" is longer than the {}-character {} public key", target, length, self.name()));
+        }
+        for (i, c) in target.char_indices() {
+            let allowed = match self {
+                // 32 bytes always end in one '=' of padding
+                Layout::Nebula | Layout::WireGuard => {
+                    c.is_ascii_alphanumeric() || c == '+' || c == '/' || (c == '=' && i == target.len() - 1)
+                }
+                Layout::Tailscale => {
+                    let c = if case_sensitive { c } else { c.to_ascii_lowercase() };
+                    c.is_ascii_digit() || ('a'..='f').contains(&c)
+                }
+            };
+            if c == '=' && !allowed {
+                return Err(format!("'=' only ever ends a {} public key", self.name()));
+            }
+            if !allowed {
+                return Err(format!("'{}' never appears in a {} public key, so \"{}\" can never match", c, name, target));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the key pair in the layout's files, the private one owner-only
+    pub fn save(self, seed: &[u8; 32], public_key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let (private_path, public_path) = self.output_paths();
+        let private_key = Zeroizing::new(self.encode(clamp(seed).as_slice()));
+        let (private_text, public_text) = match self {
+            Layout::Nebula => (
+                Zeroizing::new(pem(NEBULA_PRIVATE_LABEL, &private_key)),
+                pem(NEBULA_PUBLIC_LABEL, public_key),
+            ),
+            Layout::WireGuard => (Zeroizing::new(format!("{}\n", *private_key)), format!("{}\n", public_key)),
+            Layout::Tailscale => (
+                Zeroizing::new(format!("privkey:{}\n", *private_key)),
+                format!("nodekey:{}\n", public_key),
+            ),
+        };
+        crate::permissions::write_private(private_path.as_ref(), private_text.as_bytes())?;
+        fs::write(public_path, public_text)?;
+        Ok(())
+    }
+
+    /// Read the files back and check the private key still yields the public one
+    pub fn verify_written(self) -> Result<(), String> {
+        let (private_path, public_path) = self.output_paths();
+        let read = |path: &str| fs::read_to_string(path).map(Zeroizing::new).map_err(|e| format!("{}: {}", path, e));
+        let private_text = read(private_path)?;
+        let public_text = read(public_path)?;
+        let private_key = Zeroizing::new(self.unwrap(&private_text, NEBULA_PRIVATE_LABEL, "privkey:"));
+        let public_key = self.unwrap(&public_text, NEBULA_PUBLIC_LABEL, "nodekey:");
+
+        let mut seed = Zeroizing::new([0u8; 32]);
+        let decoded = Zeroizing::new(match self {
+            Layout::Nebula | Layout::WireGuard => BASE64.decode(private_key.as_bytes()).map_err(|e| e.to_string())?,
+            Layout::Tailscale => decode_hex(&private_key).ok_or("invalid hex")?,
+        });
+        if decoded.len() != 32 {
+            return Err(format!("{}: not a 32-byte key", private_path));
+        }
+        seed.copy_from_slice(&decoded);
+        if self.public_key(&seed) != public_key {
+            return Err(format!("{} does not belong to {}", public_path, private_path));
+        }
+        Ok(())
+    }
+
+    /// The bare key from a file's contents
+    fn unwrap(self, text: &str, label: &str, prefix: &str) -> String {
+        let text = text.trim();
+        match self {
+            Layout::Nebula => text
+                .lines()
+                .filter(|line| !line.starts_with("-----") || !line.contains(label))
+                .collect(),
+            Layout::WireGuard => text.to_string(),
+            Layout::Tailscale => text.strip_prefix(prefix).unwrap_or(text).to_string(),
+        }
+    }
+}
+
+/// X25519 clamping, so the stored private key is the scalar actually used
+fn clamp(seed: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    let mut scalar = Zeroizing::new(*seed);
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    scalar
+}
+
+fn pem(label: &str, body: &str) -> String {
+    format!("-----BEGIN {}-----\n{}\n-----END {}-----\n", label, body, label)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}